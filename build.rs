@@ -1,5 +1,7 @@
 // MIT/Apache2 License
 
+#[path = "build/gl_registry.rs"]
+mod gl_registry;
 #[path = "build/pci_ids.rs"]
 mod pci_ids;
 
@@ -7,6 +9,7 @@ use std::{env, error::Error, fs, path::PathBuf};
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build/gl_registry.rs");
     println!("cargo:rerun-if-changed=build/pci_ids.rs");
 
     // create the "auto" directory
@@ -20,6 +23,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         env::var("TARGET").unwrap()
     );
 
+    gl_registry::process_gl_registry(&auto_path)?;
     pci_ids::process_pci_ids(&auto_path)?;
     Ok(())
 }
@@ -0,0 +1,108 @@
+// MIT/Apache2 License
+
+use std::{error::Error, fs, io::prelude::*, path::Path};
+
+/// Generate the lists of GL and GLX entry points from the Khronos registry, so function names can be
+/// checked before they're looked up.
+pub fn process_gl_registry(auto_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = fs::File::create(auto_dir.join("gl_registry.rs"))?;
+
+    writeln!(out, "// Generated by build.rs")?;
+    write_entry_points(&mut out, "GL_ENTRY_POINTS", khronos_api::GL_XML)?;
+    write_entry_points(&mut out, "GLX_ENTRY_POINTS", khronos_api::GLX_XML)?;
+    Ok(())
+}
+
+fn write_entry_points(out: &mut fs::File, name: &str, xml: &[u8]) -> Result<(), Box<dyn Error>> {
+    let names = command_names(std::str::from_utf8(xml)?);
+
+    writeln!(
+        out,
+        "pub(crate) const {}: [&str; {}] = [",
+        name,
+        names.len()
+    )?;
+    for name in names {
+        writeln!(out, "{:?},", name)?;
+    }
+    writeln!(out, "];")?;
+    Ok(())
+}
+
+/// Find the names of the commands in a registry, sorted so they can be binary searched. Each command
+/// looks like `<proto>void <name>glFlush</name></proto>`.
+fn command_names(xml: &str) -> Vec<&str> {
+    const NAME_START: &str = "<name>";
+    const NAME_END: &str = "</name>";
+
+    let mut names: Vec<&str> = xml
+        .split("<proto")
+        .skip(1)
+        .filter_map(|proto| {
+            let proto = &proto[..proto.find("</proto>")?];
+            let start = proto.find(NAME_START)? + NAME_START.len();
+            let end = start + proto[start..].find(NAME_END)?;
+            Some(&proto[start..end])
+        })
+        .collect();
+
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_names_from_protos() {
+        let xml = r#"
+            <commands namespace="GL">
+                <command>
+                    <proto>void <name>glFlush</name></proto>
+                </command>
+                <command>
+                    <proto group="String">const <ptype>GLubyte</ptype> *<name>glGetString</name></proto>
+                    <param group="StringName"><ptype>GLenum</ptype> <name>name</name></param>
+                </command>
+                <command>
+                    <proto>void <name>glClear</name></proto>
+                    <param><ptype>GLbitfield</ptype> <name>mask</name></param>
+                </command>
+                <command>
+                    <proto>void <name>glFlush</name></proto>
+                </command>
+            </commands>
+        "#;
+
+        // parameter names aren't commands, and commands come out sorted without duplicates
+        assert_eq!(
+            command_names(xml),
+            vec!["glClear", "glFlush", "glGetString"]
+        );
+    }
+
+    #[test]
+    fn command_names_skip_malformed_protos() {
+        assert!(command_names("").is_empty());
+        assert!(command_names("<proto>void glFlush</proto>").is_empty());
+        assert!(command_names("<proto>void <name>glFlush</proto></name>").is_empty());
+        assert_eq!(
+            command_names("<proto>void <name>glFinish</name></proto><proto>void <name>glFlush"),
+            vec!["glFinish"]
+        );
+    }
+
+    #[test]
+    fn registries() {
+        let gl = command_names(std::str::from_utf8(khronos_api::GL_XML).unwrap());
+        let glx = command_names(std::str::from_utf8(khronos_api::GLX_XML).unwrap());
+
+        assert!(gl.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(glx.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(gl.binary_search(&"glClear").is_ok());
+        assert!(glx.binary_search(&"glXSwapBuffers").is_ok());
+        assert!(gl.iter().chain(&glx).all(|name| name.starts_with("gl")));
+    }
+}
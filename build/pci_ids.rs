@@ -11,9 +11,14 @@ use std::{
 pub fn process_pci_ids(auto_dir: &Path) -> Result<(), Box<dyn Error>> {
     let mut pci_dir: PathBuf = env::var_os("CARGO_MANIFEST_DIR").unwrap().into();
     pci_dir.push("pci_ids");
-    let pci_id_sets: Vec<PciMapping> = fs::read_dir(pci_dir)?
+    let mut pci_id_sets: Vec<PciMapping> = fs::read_dir(pci_dir)?
         .map(|f| process_pci_id(f?.path()))
-        .collect::<Result<_, Box<dyn Error>>>()?;
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?
+        .into_iter()
+        .flat_map(split_by_generation)
+        .collect();
+    // keep the output stable regardless of directory order
+    pci_id_sets.sort_by(|a, b| a.name.cmp(&b.name));
 
     let mut pci_id_path = PathBuf::new();
     pci_id_path.push(auto_dir);
@@ -28,7 +33,7 @@ pub fn process_pci_ids(auto_dir: &Path) -> Result<(), Box<dyn Error>> {
         .for_each(|PciMapping { mut name, ids }| {
             name.make_ascii_uppercase();
             write!(out, "pub(crate) const {}: [c_int; {}] = [\n", name, ids.len()).unwrap();
-            ids.into_iter().for_each(|id| {
+            ids.into_iter().for_each(|(id, _)| {
                 write!(out, "{},", id).unwrap();
             });
             write!(out, "];\n").unwrap();
@@ -39,7 +44,36 @@ pub fn process_pci_ids(auto_dir: &Path) -> Result<(), Box<dyn Error>> {
 
 struct PciMapping {
     name: String,
-    ids: Vec<String>,
+    ids: Vec<(String, String)>,
+}
+
+/// Chip families in `i965_pci_ids.h` that are driven by `crocus` (gen4 through gen7.5). Every other
+/// family there is gen8 or newer, and is driven by `iris`.
+const CROCUS_FAMILIES: &[&str] = &["i965", "g4x", "ilk", "snb", "ivb", "byt", "hsw"];
+
+/// Mesa no longer ships the `i965` driver, so split its table into the `crocus` and `iris` tables,
+/// the same way Mesa's own loader does.
+fn split_by_generation(mapping: PciMapping) -> Vec<PciMapping> {
+    if mapping.name != "i965_pci_ids" {
+        return vec![mapping];
+    }
+
+    let (crocus, iris) = mapping.ids.into_iter().partition(|(_, family)| {
+        CROCUS_FAMILIES
+            .iter()
+            .any(|f| family == f || family.starts_with(&format!("{}_", f)))
+    });
+
+    vec![
+        PciMapping {
+            name: "crocus_pci_ids".to_string(),
+            ids: crocus,
+        },
+        PciMapping {
+            name: "iris_pci_ids".to_string(),
+            ids: iris,
+        },
+    ]
 }
 
 fn process_pci_id(path: PathBuf) -> Result<PciMapping, Box<dyn Error>> {
@@ -50,8 +84,11 @@ fn process_pci_id(path: PathBuf) -> Result<PciMapping, Box<dyn Error>> {
         .lines()
         .filter_map(|l| {
             if l.len() > 8 && &l[0..8] == "CHIPSET(" {
-                let posn = memchr::memchr(b',', l.as_bytes())?;
-                Some(l[8..posn].to_string())
+                // CHIPSET(chip_id, family, ...)
+                let mut fields = l[8..].split(|c| c == ',' || c == ')').map(str::trim);
+                let id = fields.next()?.to_string();
+                let family = fields.next().unwrap_or("").to_string();
+                Some((id, family))
             } else {
                 None
             }
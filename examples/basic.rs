@@ -1,12 +1,11 @@
 // MIT/Apache2 License
 
-use breadglx::{GlConfigRule, GlContextRule, GlDisplay, GlVisualType, GlContext};
-use breadx::{
-    auto::xproto::ExposeEvent, ColormapAlloc, DisplayConnection, Event, EventMask, Pixmap, Result,
-    VisualClass, WindowClass, WindowParameters,
+use breadglx::{
+    FbConfigRequest, GlConfigRule, GlContext, GlContextRule, GlDisplay, GlVisualType, GlWindowHints,
 };
+use breadx::{DisplayConnection, Event, EventMask, Rectangle, Result};
 use log::LevelFilter;
-use std::{env, io::Write, mem, thread, time::Duration};
+use std::{env, io::Write, thread, time::Duration};
 
 // This code is a rough translate of the following:
 // https://www.khronos.org/opengl/wiki/Tutorial:_OpenGL_3.0_Context_Creation_(GLX)
@@ -39,55 +38,28 @@ fn main() -> Result<()> {
         GlConfigRule::XRenderable(1),
     ];
 
-    // note: choose_fbconfigs is an iterator and thus we can use iterator methods
-    //       to get the most optimal framebuffer configuration
-    let fbconfig = screen
-        .choose_fbconfigs(FBCONFIG_RULES)
-        .max_by_key(|fbconfig| fbconfig.samples)
-        .expect("Could not find valid framebuffer config");
-
-    // get the visual information associated with that fbconfig
-    let mut dpy = conn.display();
-    let vis = dpy
-        .visual_id_to_visual(fbconfig.visual_id as _)
-        .expect("Could not match visual to fbconfig");
-    let depth = dpy.depth_of_visual(vis.visual_id).unwrap();
-    let vis = vis.visual_id;
-
-    // create a colormap identified with the chosen visual style
-    let cmap = dpy.create_colormap(root, vis, ColormapAlloc::None)?;
-
-    // create a window
-    let wp = WindowParameters {
-        colormap: Some(cmap),
-        background_pixmap: Some(Pixmap::const_from_xid(0)),
-        border_pixel: Some(0),
-        event_mask: Some(
-            EventMask::STRUCTURE_NOTIFY | EventMask::EXPOSURE | EventMask::BUTTON_PRESS,
-        ),
+    // we'd like multisampling, but we can do without it
+    let request = FbConfigRequest::new()
+        .require_all(FBCONFIG_RULES)
+        .prefer(GlConfigRule::Samples(4));
+    let fbconfig = screen.choose_fbconfig(&request)?;
+
+    // create a window that can be rendered to with that fbconfig
+    let hints = GlWindowHints {
+        title: Some("BreadGLX Demonstration".to_string()),
+        event_mask: EventMask::STRUCTURE_NOTIFY | EventMask::EXPOSURE | EventMask::BUTTON_PRESS,
         ..Default::default()
     };
-    let win = dpy.create_window(
-        root,
-        WindowClass::InputOutput,
-        Some(depth),
-        Some(vis),
-        0,
-        0,
-        640,
-        400,
-        0,
-        wp,
-    )?;
-
-    // set up the window's properties
-    win.set_title(&mut *dpy, "BreadGLX Demonstration")?;
-    win.map(&mut *dpy)?;
-    let wdw = dpy.intern_atom_immediate("WM_DELETE_WINDOW".to_string(), false)?;
-    win.set_wm_protocols(&mut *dpy, &[wdw])?;
-
-    // drop the mutex lock
-    mem::drop(dpy);
+    let geometry = Rectangle {
+        x: 0,
+        y: 0,
+        width: 640,
+        height: 400,
+    };
+    let (win, _drawable) = screen.create_window(&conn, root, geometry, fbconfig, &hints)?;
+    let wdw = conn
+        .display()
+        .intern_atom_immediate("WM_DELETE_WINDOW".to_string(), false)?;
 
     // now that we have a window, establish a GlContext
     const CONTEXT_RULES: &[GlContextRule] = &[
@@ -1,6 +1,8 @@
 // MIT/Apache2 License
 
-use breadglx::{GlConfigRule, GlContext, GlContextRule, GlDisplay, GlScreen, GlVisualType};
+use breadglx::{
+    FbConfigRequest, GlConfigRule, GlContext, GlContextRule, GlDisplay, GlScreen, GlVisualType,
+};
 use breadx::{
     ColormapAlloc, DisplayConnection, Event, EventMask, Pixmap, Result, Window, WindowClass,
     WindowParameters,
@@ -45,16 +47,17 @@ fn main() -> Result {
         GlConfigRule::XRenderable(1),
     ];
 
-    let fbconfig = screen
-        .choose_fbconfigs(FBCONFIG_RULES)
-        .max_by_key(|fbconfig| fbconfig.samples)
-        .expect("Could not find valid framebuffer config");
-    let mut dpy = conn.display();
-    let vis = dpy
-        .visual_id_to_visual(fbconfig.visual_id as _)
+    // we'd like multisampling, but we can do without it
+    let request = FbConfigRequest::new()
+        .require_all(FBCONFIG_RULES)
+        .prefer(GlConfigRule::Samples(4));
+    let fbconfig = screen.choose_fbconfig(&request)?;
+    let visual = screen
+        .config_for_visual(&conn, fbconfig.visual_id as _)
         .expect("Could not match visual to fbconfig");
-    let depth = dpy.depth_of_visual(vis.visual_id).unwrap();
-    let vis = vis.visual_id;
+    let depth = visual.depth;
+    let vis = visual.visual_id();
+    let mut dpy = conn.display();
 
     let cmap = dpy.create_colormap(root, vis, ColormapAlloc::None)?;
     let wp = WindowParameters {
@@ -0,0 +1,60 @@
+// MIT/Apache2 License
+
+use breadglx::{GlConfig, GlDisplay};
+use breadx::{DisplayConnection, Result};
+use std::env;
+
+// A small subset of `glxinfo`, listing the visuals and framebuffer configs of every screen.
+// Pass `-s <index>` to only list a single screen.
+
+fn main() -> Result<()> {
+    env_logger::init();
+
+    let mut args = env::args().skip(1);
+    let only_screen = match (args.next().as_deref(), args.next()) {
+        (Some("-s"), Some(index)) => Some(index.parse::<usize>().expect("Invalid screen index")),
+        (None, _) => None,
+        _ => {
+            eprintln!("usage: glxinfo [-s <screen index>]");
+            return Ok(());
+        }
+    };
+
+    let conn = DisplayConnection::create(None, None)?;
+    let conn = GlDisplay::new(conn)?;
+    let screen_count = conn.display().screens().len();
+
+    println!(
+        "GLX version: {}.{}",
+        conn.major_version(),
+        conn.minor_version()
+    );
+
+    for index in 0..screen_count {
+        if only_screen.map_or(false, |only| only != index) {
+            continue;
+        }
+
+        let screen = conn.create_screen(index)?;
+
+        println!();
+        println!("screen {}: {} GLX visuals", index, screen.visuals().len());
+        print!("{}", GlConfig::table(screen.visuals()));
+
+        println!();
+        println!("screen {}: {} GLXFBConfigs", index, screen.fbconfigs().len());
+        print!("{}", GlConfig::table(screen.fbconfigs()));
+
+        // explain which configs can't be used for direct rendering
+        if let Some(diagnostics) = screen.dri_config_diagnostics() {
+            println!();
+            println!("screen {}: configs without a DRI driver config", index);
+            diagnostics
+                .iter()
+                .filter(|d| d.driver_config.is_none())
+                .for_each(|d| println!("{}", d));
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,15 @@
 // MIT/Apache2 License
 
+pub(crate) mod gl_registry {
+    include!(concat!(env!("OUT_DIR"), "/auto/gl_registry.rs"));
+}
+
+// run the build script's tests along with ours; only its parsing is used here
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../build/gl_registry.rs"]
+mod build_gl_registry;
+
 pub(crate) mod pci_ids {
     include!(concat!(env!("OUT_DIR"), "/auto/pci_ids.rs"));
 }
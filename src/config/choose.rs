@@ -0,0 +1,432 @@
+// MIT/Apache2 License
+
+use super::*;
+use std::{cmp::Ordering, mem};
+
+/// The glXChooseFBConfig defaults (GLX 1.4, section 3.3.3) that rule configs out. Every other
+/// attribute defaults to `GLX_DONT_CARE`, or to a minimum of zero.
+const CHOOSER_DEFAULTS: [GlConfigRule; 5] = [
+    GlConfigRule::StereoMode(0),
+    GlConfigRule::RenderType(RGBA_BIT),
+    GlConfigRule::DrawableType(WINDOW_BIT),
+    GlConfigRule::Level(0),
+    GlConfigRule::TransparentPixel(CONFIG_NONE),
+];
+
+impl GlConfig {
+    /// A template that every config matches, before any rules are applied to it.
+    #[inline]
+    fn unconstrained_template() -> GlConfig {
+        GlConfig {
+            double_buffer_mode: DONT_CARE as _,
+            stereo_mode: DONT_CARE as _,
+            render_type: DONT_CARE,
+            drawable_type: DONT_CARE,
+            level: DONT_CARE,
+            visual_rating: DONT_CARE,
+            transparent_pixel: DONT_CARE,
+            swap_method: GlSwapMethod::DontCare,
+            ..GlConfig::default()
+        }
+    }
+
+    /// Build the config that other configs are matched against, using the glXChooseFBConfig
+    /// defaults (GLX 1.4, section 3.3.3) for any attribute the rules don't mention.
+    #[inline]
+    pub(crate) fn chooser_template(rules: &[GlConfigRule]) -> GlConfig {
+        Self::unconstrained_template()
+            .with_rules(&CHOOSER_DEFAULTS)
+            .with_rules(rules)
+    }
+
+    /// The glXChooseFBConfig defaults that apply to a set of rules, i.e. the ones for attributes the
+    /// rules don't mention.
+    #[inline]
+    pub(crate) fn chooser_defaults(
+        rules: &[GlConfigRule],
+    ) -> impl Iterator<Item = GlConfigRule> + '_ {
+        CHOOSER_DEFAULTS.iter().copied().filter(move |default| {
+            rules
+                .iter()
+                .all(|rule| mem::discriminant(rule) != mem::discriminant(default))
+        })
+    }
+
+    /// Tell whether or not this config satisfies a single rule, using the same match criteria as
+    /// `matches_template`, and without applying any defaults.
+    #[inline]
+    pub(crate) fn matches_rule(&self, rule: GlConfigRule) -> bool {
+        self.matches_template(&Self::unconstrained_template().with_rules(&[rule]))
+    }
+
+    /// Tell whether or not this config satisfies the given template, using the per-attribute match
+    /// criteria from the GLX specification.
+    #[inline]
+    pub(crate) fn matches_template(&self, template: &GlConfig) -> bool {
+        // if an fbconfig ID is requested, every other attribute is ignored
+        if template.fbconfig_id != DONT_CARE {
+            return self.fbconfig_id == template.fbconfig_id;
+        }
+
+        #[inline]
+        fn exact<T: PartialEq>(requested: T, actual: T, dont_care: T) -> bool {
+            requested == dont_care || requested == actual
+        }
+
+        #[inline]
+        fn minimum(requested: c_int, actual: c_int) -> bool {
+            requested == DONT_CARE || requested <= actual
+        }
+
+        #[inline]
+        fn mask(requested: c_int, actual: c_int) -> bool {
+            requested == DONT_CARE || requested & actual != 0
+        }
+
+        let t = template;
+        let exact_matches = exact(
+            t.double_buffer_mode,
+            self.double_buffer_mode,
+            DONT_CARE as _,
+        ) && exact(t.stereo_mode, self.stereo_mode, DONT_CARE as _)
+            && exact(t.visual_type, self.visual_type, GlVisualType::DontCare)
+            && exact(t.visual_rating, self.visual_rating, DONT_CARE)
+            && exact(t.x_renderable, self.x_renderable, DONT_CARE)
+            && exact(t.swap_method, self.swap_method, GlSwapMethod::DontCare)
+            && exact(t.srgb_capable, self.srgb_capable, DONT_CARE)
+            && exact(t.bind_to_texture_rgb, self.bind_to_texture_rgb, DONT_CARE)
+            && exact(t.bind_to_texture_rgba, self.bind_to_texture_rgba, DONT_CARE)
+            && exact(
+                t.bind_to_mipmap_texture,
+                self.bind_to_mipmap_texture,
+                DONT_CARE,
+            )
+            && exact(t.y_inverted, self.y_inverted, DONT_CARE)
+            && exact(t.level, self.level, DONT_CARE);
+
+        let minimum_matches = minimum(t.rgb_bits, self.rgb_bits)
+            && minimum(t.red_bits, self.red_bits)
+            && minimum(t.green_bits, self.green_bits)
+            && minimum(t.blue_bits, self.blue_bits)
+            && minimum(t.alpha_bits, self.alpha_bits)
+            && minimum(t.num_aux_buffers, self.num_aux_buffers)
+            && minimum(t.depth_bits, self.depth_bits)
+            && minimum(t.stencil_bits, self.stencil_bits)
+            && minimum(t.accum_red_bits, self.accum_red_bits)
+            && minimum(t.accum_green_bits, self.accum_green_bits)
+            && minimum(t.accum_blue_bits, self.accum_blue_bits)
+            && minimum(t.accum_alpha_bits, self.accum_alpha_bits)
+            && minimum(t.sample_buffers, self.sample_buffers)
+            && minimum(t.samples, self.samples);
+
+        let mask_matches = mask(t.drawable_type, self.drawable_type)
+            && mask(t.render_type, self.render_type)
+            && mask(t.bind_to_texture_targets, self.bind_to_texture_targets);
+
+        exact_matches && minimum_matches && mask_matches && self.matches_transparency(t)
+    }
+
+    #[inline]
+    fn matches_transparency(&self, t: &GlConfig) -> bool {
+        match t.transparent_pixel {
+            DONT_CARE => true,
+            // some servers report 0 instead of GLX_NONE, so treat them the same
+            CONFIG_NONE | 0 => matches!(self.transparent_pixel, CONFIG_NONE | 0),
+            TRANSPARENT_RGB => {
+                self.transparent_pixel == TRANSPARENT_RGB
+                    && [
+                        (t.transparent_red, self.transparent_red),
+                        (t.transparent_green, self.transparent_green),
+                        (t.transparent_blue, self.transparent_blue),
+                        (t.transparent_alpha, self.transparent_alpha),
+                    ]
+                    .iter()
+                    .all(|(req, act)| *req == DONT_CARE || req == act)
+            }
+            TRANSPARENT_INDEX => {
+                self.transparent_pixel == TRANSPARENT_INDEX
+                    && (t.transparent_index == DONT_CARE
+                        || t.transparent_index == self.transparent_index)
+            }
+            other => other == self.transparent_pixel,
+        }
+    }
+
+    /// Compare two configs that both match the template, using the sort order from the GLX
+    /// specification. Like libGL, configs are sorted by their GLX_SGIX_visual_select_group before
+    /// any of the keys from the specification. Configs that compare as `Less` are better matches.
+    #[inline]
+    pub(crate) fn cmp_for_template(&self, other: &GlConfig, template: &GlConfig) -> Ordering {
+        self.visual_select_group
+            .cmp(&other.visual_select_group)
+            // caveats sort as GLX_NONE, GLX_SLOW_CONFIG, GLX_NON_CONFORMANT_CONFIG, which happens
+            // to be their numerical order
+            .then_with(|| self.visual_rating.cmp(&other.visual_rating))
+            // larger color buffers first, only counting the components that were asked for
+            .then_with(|| {
+                requested_color_bits(other, template).cmp(&requested_color_bits(self, template))
+            })
+            .then_with(|| self.rgb_bits.cmp(&other.rgb_bits))
+            // single buffered configs come first
+            .then_with(|| self.double_buffer_mode.cmp(&other.double_buffer_mode))
+            .then_with(|| self.num_aux_buffers.cmp(&other.num_aux_buffers))
+            .then_with(|| self.sample_buffers.cmp(&other.sample_buffers))
+            .then_with(|| self.samples.cmp(&other.samples))
+            .then_with(|| other.depth_bits.cmp(&self.depth_bits))
+            .then_with(|| self.stencil_bits.cmp(&other.stencil_bits))
+            .then_with(|| {
+                requested_accum_bits(other, template).cmp(&requested_accum_bits(self, template))
+            })
+            // GlVisualType is declared in the order the specification sorts visual types in
+            .then_with(|| self.visual_type.cmp(&other.visual_type))
+    }
+}
+
+/// Only count the bits of a component if the template asked for a positive amount of them. Since
+/// `DONT_CARE` is negative, this also skips components that the template doesn't care about.
+#[inline]
+fn requested(requested: c_int, actual: c_int) -> c_int {
+    if requested > 0 {
+        actual
+    } else {
+        0
+    }
+}
+
+#[inline]
+fn requested_color_bits(cfg: &GlConfig, template: &GlConfig) -> c_int {
+    requested(template.red_bits, cfg.red_bits)
+        + requested(template.green_bits, cfg.green_bits)
+        + requested(template.blue_bits, cfg.blue_bits)
+        + requested(template.alpha_bits, cfg.alpha_bits)
+}
+
+#[inline]
+fn requested_accum_bits(cfg: &GlConfig, template: &GlConfig) -> c_int {
+    requested(template.accum_red_bits, cfg.accum_red_bits)
+        + requested(template.accum_green_bits, cfg.accum_green_bits)
+        + requested(template.accum_blue_bits, cfg.accum_blue_bits)
+        + requested(template.accum_alpha_bits, cfg.accum_alpha_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba_config(fbconfig_id: c_int) -> GlConfig {
+        GlConfig {
+            fbconfig_id,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
+            rgb_bits: 24,
+            depth_bits: 24,
+            visual_type: GlVisualType::TrueColor,
+            render_type: RGBA_BIT,
+            drawable_type: WINDOW_BIT | PIXMAP_BIT,
+            x_renderable: 1,
+            ..GlConfig::default()
+        }
+    }
+
+    fn sorted(configs: &[GlConfig], rules: &[GlConfigRule]) -> Vec<c_int> {
+        let template = GlConfig::chooser_template(rules);
+        let mut configs: Vec<&GlConfig> = configs
+            .iter()
+            .filter(|fb| fb.matches_template(&template))
+            .collect();
+        configs.sort_by(|a, b| a.cmp_for_template(b, &template));
+        configs.iter().map(|fb| fb.fbconfig_id).collect()
+    }
+
+    #[test]
+    fn template_defaults() {
+        let template = GlConfig::chooser_template(&[]);
+        assert_eq!(template.render_type, RGBA_BIT);
+        assert_eq!(template.drawable_type, WINDOW_BIT);
+        assert_eq!(template.double_buffer_mode, DONT_CARE as c_uint);
+        assert_eq!(template.stereo_mode, 0);
+        assert_eq!(template.level, 0);
+        assert_eq!(template.visual_rating, DONT_CARE);
+        assert_eq!(template.visual_type, GlVisualType::DontCare);
+        assert_eq!(template.transparent_pixel, CONFIG_NONE);
+        assert_eq!(template.x_renderable, DONT_CARE);
+        assert_eq!(template.fbconfig_id, DONT_CARE);
+        assert_eq!(template.depth_bits, 0);
+
+        let template = GlConfig::chooser_template(&[GlConfigRule::RenderType(COLOR_INDEX_BIT)]);
+        assert_eq!(template.render_type, COLOR_INDEX_BIT);
+    }
+
+    #[test]
+    fn default_matches() {
+        let template = GlConfig::chooser_template(&[]);
+        assert!(rgba_config(1).matches_template(&template));
+
+        // color index only and pixmap only configs don't match the defaults
+        let color_index = GlConfig {
+            render_type: COLOR_INDEX_BIT,
+            ..rgba_config(2)
+        };
+        assert!(!color_index.matches_template(&template));
+        let pixmap = GlConfig {
+            drawable_type: PIXMAP_BIT,
+            ..rgba_config(3)
+        };
+        assert!(!pixmap.matches_template(&template));
+
+        // stereo is exact and defaults to false, overlays are exact and default to the main plane
+        let stereo = GlConfig {
+            stereo_mode: 1,
+            ..rgba_config(4)
+        };
+        assert!(!stereo.matches_template(&template));
+        let overlay = GlConfig {
+            level: 1,
+            ..rgba_config(5)
+        };
+        assert!(!overlay.matches_template(&template));
+    }
+
+    #[test]
+    fn match_criteria() {
+        let config = GlConfig {
+            double_buffer_mode: 1,
+            ..rgba_config(1)
+        };
+
+        // minimums
+        let matches =
+            |rules: &[GlConfigRule]| config.matches_template(&GlConfig::chooser_template(rules));
+        assert!(matches(&[GlConfigRule::DepthBits(16)]));
+        assert!(matches(&[GlConfigRule::DepthBits(24)]));
+        assert!(!matches(&[GlConfigRule::DepthBits(32)]));
+        assert!(matches(&[GlConfigRule::DepthBits(DONT_CARE)]));
+
+        // exact
+        assert!(matches(&[GlConfigRule::DoubleBufferMode(1)]));
+        assert!(!matches(&[GlConfigRule::DoubleBufferMode(0)]));
+        assert!(!matches(&[GlConfigRule::VisualType(
+            GlVisualType::DirectColor
+        )]));
+
+        // masks
+        assert!(matches(&[GlConfigRule::DrawableType(PIXMAP_BIT)]));
+        assert!(matches(&[GlConfigRule::DrawableType(
+            PIXMAP_BIT | PBUFFER_BIT
+        )]));
+        assert!(!matches(&[GlConfigRule::DrawableType(PBUFFER_BIT)]));
+
+        // an fbconfig ID overrides everything else
+        assert!(matches(&[
+            GlConfigRule::FbconfigId(1),
+            GlConfigRule::DepthBits(32)
+        ]));
+        assert!(!matches(&[GlConfigRule::FbconfigId(2)]));
+    }
+
+    #[test]
+    fn single_rules() {
+        let config = rgba_config(1);
+        assert!(config.matches_rule(GlConfigRule::DepthBits(24)));
+        assert!(!config.matches_rule(GlConfigRule::DepthBits(32)));
+        assert!(config.matches_rule(GlConfigRule::DepthBits(DONT_CARE)));
+        assert!(config.matches_rule(GlConfigRule::DrawableType(PIXMAP_BIT)));
+        assert!(!config.matches_rule(GlConfigRule::RenderType(COLOR_INDEX_BIT)));
+        assert!(!config.matches_rule(GlConfigRule::Level(1)));
+
+        // defaults aren't applied to single rules
+        let color_index = GlConfig {
+            render_type: COLOR_INDEX_BIT,
+            ..rgba_config(2)
+        };
+        assert!(color_index.matches_rule(GlConfigRule::DepthBits(24)));
+    }
+
+    #[test]
+    fn defaults_for_rules() {
+        assert_eq!(
+            GlConfig::chooser_defaults(&[]).collect::<Vec<_>>(),
+            CHOOSER_DEFAULTS
+        );
+        let defaults: Vec<_> = GlConfig::chooser_defaults(&[
+            GlConfigRule::RenderType(COLOR_INDEX_BIT),
+            GlConfigRule::DepthBits(24),
+        ])
+        .collect();
+        assert!(!defaults.contains(&GlConfigRule::RenderType(RGBA_BIT)));
+        assert!(defaults.contains(&GlConfigRule::DrawableType(WINDOW_BIT)));
+        assert_eq!(defaults.len(), CHOOSER_DEFAULTS.len() - 1);
+    }
+
+    #[test]
+    fn sort_order() {
+        // each config is worse than the one before it in exactly one sort key
+        let configs = [
+            rgba_config(1),
+            GlConfig {
+                visual_type: GlVisualType::DirectColor,
+                ..rgba_config(2)
+            },
+            GlConfig {
+                stencil_bits: 8,
+                ..rgba_config(3)
+            },
+            GlConfig {
+                depth_bits: 0,
+                stencil_bits: 8,
+                ..rgba_config(4)
+            },
+            GlConfig {
+                samples: 4,
+                sample_buffers: 1,
+                ..rgba_config(5)
+            },
+            GlConfig {
+                num_aux_buffers: 2,
+                ..rgba_config(6)
+            },
+            GlConfig {
+                double_buffer_mode: 1,
+                num_aux_buffers: 2,
+                ..rgba_config(7)
+            },
+            GlConfig {
+                visual_rating: SLOW_CONFIG,
+                ..rgba_config(8)
+            },
+            GlConfig {
+                visual_rating: NON_CONFORMANT_CONFIG,
+                ..rgba_config(9)
+            },
+            GlConfig {
+                visual_select_group: 1,
+                ..rgba_config(10)
+            },
+        ];
+        let mut reversed = configs.clone();
+        reversed.reverse();
+
+        let expected: Vec<c_int> = (1..=10).collect();
+        assert_eq!(sorted(&configs, &[]), expected);
+        assert_eq!(sorted(&reversed, &[]), expected);
+    }
+
+    #[test]
+    fn color_bits_sort_when_requested() {
+        let shallow = rgba_config(1);
+        let deep = GlConfig {
+            red_bits: 10,
+            green_bits: 10,
+            blue_bits: 10,
+            rgb_bits: 30,
+            ..rgba_config(2)
+        };
+        let configs = [shallow, deep];
+
+        // without asking for color bits, smaller buffers come first
+        assert_eq!(sorted(&configs, &[]), [1, 2]);
+        // once they're asked for, more bits come first
+        assert_eq!(sorted(&configs, &[GlConfigRule::RedBits(1)]), [2, 1]);
+    }
+}
@@ -138,225 +138,228 @@ impl GlConfig {
 
     #[inline]
     pub fn fulfills_rules(&self, rules: &[GlConfigRule]) -> bool {
-        // apply rules to matcher
-        let matcher = rules
-            .iter()
-            .fold(construct_matching_config(), |matcher, rule| match rule {
-                GlConfigRule::DoubleBufferMode(dbm) => GlConfig {
-                    double_buffer_mode: *dbm,
-                    ..matcher
-                },
-                GlConfigRule::StereoMode(sm) => GlConfig {
-                    stereo_mode: *sm,
-                    ..matcher
-                },
-                GlConfigRule::RedBits(r) => GlConfig {
-                    red_bits: *r,
-                    ..matcher
-                },
-                GlConfigRule::GreenBits(g) => GlConfig {
-                    green_bits: *g,
-                    ..matcher
-                },
-                GlConfigRule::BlueBits(b) => GlConfig {
-                    blue_bits: *b,
-                    ..matcher
-                },
-                GlConfigRule::AlphaBits(a) => GlConfig {
-                    alpha_bits: *a,
-                    ..matcher
-                },
-                GlConfigRule::RedMask(r) => GlConfig {
-                    red_mask: *r,
-                    ..matcher
-                },
-                GlConfigRule::GreenMask(g) => GlConfig {
-                    green_mask: *g,
-                    ..matcher
-                },
-                GlConfigRule::BlueMask(b) => GlConfig {
-                    blue_mask: *b,
-                    ..matcher
-                },
-                GlConfigRule::AlphaMask(a) => GlConfig {
-                    alpha_mask: *a,
-                    ..matcher
-                },
-                GlConfigRule::RedShift(r) => GlConfig {
-                    red_shift: *r,
-                    ..matcher
-                },
-                GlConfigRule::GreenShift(g) => GlConfig {
-                    green_shift: *g,
-                    ..matcher
-                },
-                GlConfigRule::BlueShift(b) => GlConfig {
-                    blue_shift: *b,
-                    ..matcher
-                },
-                GlConfigRule::AlphaShift(a) => GlConfig {
-                    alpha_shift: *a,
-                    ..matcher
-                },
-                GlConfigRule::RgbBits(rgb) => GlConfig {
-                    rgb_bits: *rgb,
-                    ..matcher
-                },
-                GlConfigRule::ColorIndex(ci) => GlConfig {
-                    color_index: *ci,
-                    ..matcher
-                },
-                GlConfigRule::AccumRedBits(r) => GlConfig {
-                    accum_red_bits: *r,
-                    ..matcher
-                },
-                GlConfigRule::AccumGreenBits(g) => GlConfig {
-                    accum_green_bits: *g,
-                    ..matcher
-                },
-                GlConfigRule::AccumBlueBits(b) => GlConfig {
-                    accum_blue_bits: *b,
-                    ..matcher
-                },
-                GlConfigRule::AccumAlphaBits(a) => GlConfig {
-                    accum_alpha_bits: *a,
-                    ..matcher
-                },
-                GlConfigRule::DepthBits(d) => GlConfig {
-                    depth_bits: *d,
-                    ..matcher
-                },
-                GlConfigRule::StencilBits(s) => GlConfig {
-                    stencil_bits: *s,
-                    ..matcher
-                },
-                GlConfigRule::VisualId(v) => GlConfig {
-                    visual_id: *v,
-                    ..matcher
-                },
-                GlConfigRule::VisualType(v) => GlConfig {
-                    visual_type: *v,
-                    ..matcher
-                },
-                GlConfigRule::VisualRating(v) => GlConfig {
-                    visual_rating: *v,
-                    ..matcher
-                },
-                GlConfigRule::TransparentPixel(t) => GlConfig {
-                    transparent_pixel: *t,
-                    ..matcher
-                },
-                GlConfigRule::TransparentRed(r) => GlConfig {
-                    transparent_red: *r,
-                    ..matcher
-                },
-                GlConfigRule::TransparentGreen(g) => GlConfig {
-                    transparent_green: *g,
-                    ..matcher
-                },
-                GlConfigRule::TransparentBlue(b) => GlConfig {
-                    transparent_blue: *b,
-                    ..matcher
-                },
-                GlConfigRule::TransparentAlpha(a) => GlConfig {
-                    transparent_alpha: *a,
-                    ..matcher
-                },
-                GlConfigRule::TransparentIndex(i) => GlConfig {
-                    transparent_index: *i,
-                    ..matcher
-                },
-                GlConfigRule::SampleBuffers(sb) => GlConfig {
-                    sample_buffers: *sb,
-                    ..matcher
-                },
-                GlConfigRule::Samples(s) => GlConfig {
-                    samples: *s,
-                    ..matcher
-                },
-                GlConfigRule::DrawableType(d) => GlConfig {
-                    drawable_type: *d,
-                    ..matcher
-                },
-                GlConfigRule::RenderType(r) => GlConfig {
-                    render_type: *r,
-                    ..matcher
-                },
-                GlConfigRule::XRenderable(xr) => GlConfig {
-                    x_renderable: *xr,
-                    ..matcher
-                },
-                GlConfigRule::FbconfigId(f) => GlConfig {
-                    fbconfig_id: *f,
-                    ..matcher
-                },
-                GlConfigRule::MaxPbufferWidth(w) => GlConfig {
-                    max_pbuffer_width: *w,
-                    ..matcher
-                },
-                GlConfigRule::MaxPbufferHeight(h) => GlConfig {
-                    max_pbuffer_height: *h,
-                    ..matcher
-                },
-                GlConfigRule::MaxPbufferPixels(p) => GlConfig {
-                    max_pbuffer_pixels: *p,
-                    ..matcher
-                },
-                GlConfigRule::OptimalPbufferWidth(w) => GlConfig {
-                    optimal_pbuffer_width: *w,
-                    ..matcher
-                },
-                GlConfigRule::OptimalPbufferHeight(h) => GlConfig {
-                    optimal_pbuffer_height: *h,
-                    ..matcher
-                },
-                GlConfigRule::VisualSelectGroup(vsg) => GlConfig {
-                    visual_select_group: *vsg,
-                    ..matcher
-                },
-                GlConfigRule::SwapMethod(sm) => GlConfig {
-                    swap_method: *sm,
-                    ..matcher
-                },
-                GlConfigRule::Screen(s) => GlConfig {
-                    screen: *s,
-                    ..matcher
-                },
-                GlConfigRule::BindToTextureRgb(r) => GlConfig {
-                    bind_to_texture_rgb: *r,
-                    ..matcher
-                },
-                GlConfigRule::BindToTextureRgba(r) => GlConfig {
-                    bind_to_texture_rgba: *r,
-                    ..matcher
-                },
-                GlConfigRule::BindToMipmapTexture(m) => GlConfig {
-                    bind_to_mipmap_texture: *m,
-                    ..matcher
-                },
-                GlConfigRule::BindToTextureTargets(tt) => GlConfig {
-                    bind_to_texture_targets: *tt,
-                    ..matcher
-                },
-                GlConfigRule::YInverted(y) => GlConfig {
-                    y_inverted: *y,
-                    ..matcher
-                },
-                GlConfigRule::SrgbCapable(s) => GlConfig {
-                    srgb_capable: *s,
-                    ..matcher
-                },
-                GlConfigRule::Level(l) => GlConfig {
-                    level: *l,
-                    ..matcher
-                },
-                GlConfigRule::NumAuxBuffers(nab) => GlConfig {
-                    num_aux_buffers: *nab,
-                    ..matcher
-                },
-            });
+        construct_matching_config()
+            .with_rules(rules)
+            .compatible_with(self)
+    }
 
-        matcher.compatible_with(self)
+    /// Override the values of this config with the values in a set of rules.
+    #[inline]
+    pub(crate) fn with_rules(self, rules: &[GlConfigRule]) -> GlConfig {
+        rules.iter().fold(self, |matcher, rule| match rule {
+            GlConfigRule::DoubleBufferMode(dbm) => GlConfig {
+                double_buffer_mode: *dbm,
+                ..matcher
+            },
+            GlConfigRule::StereoMode(sm) => GlConfig {
+                stereo_mode: *sm,
+                ..matcher
+            },
+            GlConfigRule::RedBits(r) => GlConfig {
+                red_bits: *r,
+                ..matcher
+            },
+            GlConfigRule::GreenBits(g) => GlConfig {
+                green_bits: *g,
+                ..matcher
+            },
+            GlConfigRule::BlueBits(b) => GlConfig {
+                blue_bits: *b,
+                ..matcher
+            },
+            GlConfigRule::AlphaBits(a) => GlConfig {
+                alpha_bits: *a,
+                ..matcher
+            },
+            GlConfigRule::RedMask(r) => GlConfig {
+                red_mask: *r,
+                ..matcher
+            },
+            GlConfigRule::GreenMask(g) => GlConfig {
+                green_mask: *g,
+                ..matcher
+            },
+            GlConfigRule::BlueMask(b) => GlConfig {
+                blue_mask: *b,
+                ..matcher
+            },
+            GlConfigRule::AlphaMask(a) => GlConfig {
+                alpha_mask: *a,
+                ..matcher
+            },
+            GlConfigRule::RedShift(r) => GlConfig {
+                red_shift: *r,
+                ..matcher
+            },
+            GlConfigRule::GreenShift(g) => GlConfig {
+                green_shift: *g,
+                ..matcher
+            },
+            GlConfigRule::BlueShift(b) => GlConfig {
+                blue_shift: *b,
+                ..matcher
+            },
+            GlConfigRule::AlphaShift(a) => GlConfig {
+                alpha_shift: *a,
+                ..matcher
+            },
+            GlConfigRule::RgbBits(rgb) => GlConfig {
+                rgb_bits: *rgb,
+                ..matcher
+            },
+            GlConfigRule::ColorIndex(ci) => GlConfig {
+                color_index: *ci,
+                ..matcher
+            },
+            GlConfigRule::AccumRedBits(r) => GlConfig {
+                accum_red_bits: *r,
+                ..matcher
+            },
+            GlConfigRule::AccumGreenBits(g) => GlConfig {
+                accum_green_bits: *g,
+                ..matcher
+            },
+            GlConfigRule::AccumBlueBits(b) => GlConfig {
+                accum_blue_bits: *b,
+                ..matcher
+            },
+            GlConfigRule::AccumAlphaBits(a) => GlConfig {
+                accum_alpha_bits: *a,
+                ..matcher
+            },
+            GlConfigRule::DepthBits(d) => GlConfig {
+                depth_bits: *d,
+                ..matcher
+            },
+            GlConfigRule::StencilBits(s) => GlConfig {
+                stencil_bits: *s,
+                ..matcher
+            },
+            GlConfigRule::VisualId(v) => GlConfig {
+                visual_id: *v,
+                ..matcher
+            },
+            GlConfigRule::VisualType(v) => GlConfig {
+                visual_type: *v,
+                ..matcher
+            },
+            GlConfigRule::VisualRating(v) => GlConfig {
+                visual_rating: *v,
+                ..matcher
+            },
+            GlConfigRule::TransparentPixel(t) => GlConfig {
+                transparent_pixel: *t,
+                ..matcher
+            },
+            GlConfigRule::TransparentRed(r) => GlConfig {
+                transparent_red: *r,
+                ..matcher
+            },
+            GlConfigRule::TransparentGreen(g) => GlConfig {
+                transparent_green: *g,
+                ..matcher
+            },
+            GlConfigRule::TransparentBlue(b) => GlConfig {
+                transparent_blue: *b,
+                ..matcher
+            },
+            GlConfigRule::TransparentAlpha(a) => GlConfig {
+                transparent_alpha: *a,
+                ..matcher
+            },
+            GlConfigRule::TransparentIndex(i) => GlConfig {
+                transparent_index: *i,
+                ..matcher
+            },
+            GlConfigRule::SampleBuffers(sb) => GlConfig {
+                sample_buffers: *sb,
+                ..matcher
+            },
+            GlConfigRule::Samples(s) => GlConfig {
+                samples: *s,
+                ..matcher
+            },
+            GlConfigRule::DrawableType(d) => GlConfig {
+                drawable_type: *d,
+                ..matcher
+            },
+            GlConfigRule::RenderType(r) => GlConfig {
+                render_type: *r,
+                ..matcher
+            },
+            GlConfigRule::XRenderable(xr) => GlConfig {
+                x_renderable: *xr,
+                ..matcher
+            },
+            GlConfigRule::FbconfigId(f) => GlConfig {
+                fbconfig_id: *f,
+                ..matcher
+            },
+            GlConfigRule::MaxPbufferWidth(w) => GlConfig {
+                max_pbuffer_width: *w,
+                ..matcher
+            },
+            GlConfigRule::MaxPbufferHeight(h) => GlConfig {
+                max_pbuffer_height: *h,
+                ..matcher
+            },
+            GlConfigRule::MaxPbufferPixels(p) => GlConfig {
+                max_pbuffer_pixels: *p,
+                ..matcher
+            },
+            GlConfigRule::OptimalPbufferWidth(w) => GlConfig {
+                optimal_pbuffer_width: *w,
+                ..matcher
+            },
+            GlConfigRule::OptimalPbufferHeight(h) => GlConfig {
+                optimal_pbuffer_height: *h,
+                ..matcher
+            },
+            GlConfigRule::VisualSelectGroup(vsg) => GlConfig {
+                visual_select_group: *vsg,
+                ..matcher
+            },
+            GlConfigRule::SwapMethod(sm) => GlConfig {
+                swap_method: *sm,
+                ..matcher
+            },
+            GlConfigRule::Screen(s) => GlConfig {
+                screen: *s,
+                ..matcher
+            },
+            GlConfigRule::BindToTextureRgb(r) => GlConfig {
+                bind_to_texture_rgb: *r,
+                ..matcher
+            },
+            GlConfigRule::BindToTextureRgba(r) => GlConfig {
+                bind_to_texture_rgba: *r,
+                ..matcher
+            },
+            GlConfigRule::BindToMipmapTexture(m) => GlConfig {
+                bind_to_mipmap_texture: *m,
+                ..matcher
+            },
+            GlConfigRule::BindToTextureTargets(tt) => GlConfig {
+                bind_to_texture_targets: *tt,
+                ..matcher
+            },
+            GlConfigRule::YInverted(y) => GlConfig {
+                y_inverted: *y,
+                ..matcher
+            },
+            GlConfigRule::SrgbCapable(s) => GlConfig {
+                srgb_capable: *s,
+                ..matcher
+            },
+            GlConfigRule::Level(l) => GlConfig {
+                level: *l,
+                ..matcher
+            },
+            GlConfigRule::NumAuxBuffers(nab) => GlConfig {
+                num_aux_buffers: *nab,
+                ..matcher
+            },
+        })
     }
 }
 
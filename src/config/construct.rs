@@ -2,8 +2,28 @@
 
 use super::*;
 use breadx::{auto::AsByteSequence, VisualClass};
+use dashmap::DashSet;
+use once_cell::sync::Lazy;
 use std::{convert::TryFrom, slice};
 
+/// Attribute tags we've already warned about. Servers send every attribute for every config, so
+/// warning each time would repeat the same message hundreds of times.
+static UNKNOWN_TAGS: Lazy<DashSet<u32>> = Lazy::new(DashSet::new);
+
+/// Report an attribute tag we don't know about. This is a warning the first time a tag shows up,
+/// since it means the server supports something breadglx doesn't, and a debug message after that.
+/// Returns whether this is the first time the tag was seen.
+#[inline]
+fn report_unknown_tag(tag: u32, val: c_int) -> bool {
+    if UNKNOWN_TAGS.insert(tag) {
+        log::warn!("Unknown config attribute: 0x{:X} = 0x{:X}", tag, val);
+        true
+    } else {
+        log::debug!("Unknown config attribute: 0x{:X} = 0x{:X}", tag, val);
+        false
+    }
+}
+
 impl GlConfig {
     #[inline]
     pub fn set_from_properties(
@@ -74,9 +94,13 @@ impl GlConfig {
             props
         };
 
+        // GLX_RENDER_TYPE carries the float bits, so it takes precedence over GLX_RGBA no matter
+        // which order the server sends them in
+        let mut render_type = None;
+
         // iterate over the properties
         let mut i = 0;
-        for _ in 0..props.len() / 2 {
+        while i < props.len() {
             // match on the tag and the value
             let tag = props[i];
             let val = props.get(i + 1).copied().unwrap_or(0) as c_int;
@@ -222,6 +246,11 @@ impl GlConfig {
                     self.visual_id = val;
                     true
                 }
+                // GLX_SCREEN
+                0x800C => {
+                    self.screen = val;
+                    true
+                }
                 // GLX_DRAWABLE_TYPE
                 0x8010 => {
                     self.drawable_type = val;
@@ -229,7 +258,7 @@ impl GlConfig {
                 }
                 // GLX_RENDER_TYPE
                 0x8011 => {
-                    self.render_type = val;
+                    render_type = Some(val);
                     true
                 }
                 // GLX_X_RENDERABLE
@@ -278,12 +307,12 @@ impl GlConfig {
                         GlSwapMethod::try_from(val).unwrap_or(GlSwapMethod::DontCare);
                     true
                 }
-                // GLX_SAMPLE_BUFFERS_SGIS
+                // GLX_SAMPLE_BUFFERS_SGIS, also GLX_SAMPLE_BUFFERS_ARB
                 100000 => {
                     self.sample_buffers = val;
                     true
                 }
-                // GLX_SAMPLES_SGIS
+                // GLX_SAMPLES_SGIS, also GLX_SAMPLES_ARB
                 100001 => {
                     self.samples = val;
                     true
@@ -303,7 +332,7 @@ impl GlConfig {
                     self.bind_to_mipmap_texture = val;
                     true
                 }
-                // GLX_BIND_TO_TEXTURE_TAGETES_EXT
+                // GLX_BIND_TO_TEXTURE_TARGETS_EXT
                 0x20D3 => {
                     self.bind_to_texture_targets = val;
                     true
@@ -313,23 +342,214 @@ impl GlConfig {
                     self.y_inverted = val;
                     true
                 }
-                // GLX_FRAMEBUFFER_SRGB_CAPABLE_EXT
+                // GLX_FRAMEBUFFER_SRGB_CAPABLE_EXT, also GLX_FRAMEBUFFER_SRGB_CAPABLE_ARB
                 0x20B2 => {
                     self.srgb_capable = val;
                     true
                 }
+                // GLX_FLOAT_COMPONENTS_NV
+                0x20B0 => {
+                    self.float_components = val;
+                    true
+                }
+                // GLX_COLOR_SAMPLES_NV
+                0x20B3 => {
+                    self.color_samples = val;
+                    true
+                }
                 // GLX_USE_GL
                 1 => fbconfig_style_tags,
                 // None
                 0 => break,
                 // Anything else is ignored
                 tag => {
-                    log::debug!("Unknown config values: 0x{:X} - 0x{:X}", tag, val);
+                    report_unknown_tag(tag, val);
                     true
                 }
             };
 
             i += if used_val { 2 } else { 1 };
         }
+
+        match render_type {
+            Some(render_type) if render_type != 0 && render_type != DONT_CARE => {
+                self.render_type = render_type;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // GetFBConfigs replies are lists of (tag, value) pairs, with the same number of pairs for each
+    // config. The fixtures below are synthetic, written by hand rather than captured from a server.
+    // They cover the attributes breadglx reads, in two different orders, including the extension
+    // attributes only some servers send.
+
+    /// A synthetic RGBA8 config with 24-bit depth and 4x MSAA, using the EXT and SGIS names for
+    /// sRGB and multisampling attributes.
+    const RGBA8_FBCONFIG: &[u32] = &[
+        0x800B, 0x21, // GLX_VISUAL_ID
+        0x8013, 0x7A, // GLX_FBCONFIG_ID
+        0x8012, 1, // GLX_X_RENDERABLE
+        4, 1, // GLX_RGBA
+        0x8011, 1, // GLX_RENDER_TYPE = GLX_RGBA_BIT
+        5, 1, // GLX_DOUBLEBUFFER
+        6, 0, // GLX_STEREO
+        2, 32, // GLX_BUFFER_SIZE
+        3, 0, // GLX_LEVEL
+        7, 0, // GLX_AUX_BUFFERS
+        8, 8, // GLX_RED_SIZE
+        9, 8, // GLX_GREEN_SIZE
+        10, 8, // GLX_BLUE_SIZE
+        11, 8, // GLX_ALPHA_SIZE
+        14, 0, // GLX_ACCUM_RED_SIZE
+        15, 0, // GLX_ACCUM_GREEN_SIZE
+        16, 0, // GLX_ACCUM_BLUE_SIZE
+        17, 0, // GLX_ACCUM_ALPHA_SIZE
+        12, 24, // GLX_DEPTH_SIZE
+        13, 8, // GLX_STENCIL_SIZE
+        0x22, 0x8002, // GLX_X_VISUAL_TYPE = GLX_TRUE_COLOR
+        0x20, 0x8000, // GLX_CONFIG_CAVEAT = GLX_NONE
+        0x23, 0x8000, // GLX_TRANSPARENT_TYPE = GLX_NONE
+        0x24, 0, // GLX_TRANSPARENT_INDEX_VALUE
+        0x8060, 0x8063, // GLX_SWAP_METHOD_OML = GLX_SWAP_UNDEFINED_OML
+        100001, 4, // GLX_SAMPLES_SGIS
+        100000, 1, // GLX_SAMPLE_BUFFERS_SGIS
+        0x8028, 0, // GLX_VISUAL_SELECT_GROUP_SGIX
+        0x8010, 0x7, // GLX_DRAWABLE_TYPE
+        0x20D0, 1, // GLX_BIND_TO_TEXTURE_RGB_EXT
+        0x20D1, 1, // GLX_BIND_TO_TEXTURE_RGBA_EXT
+        0x20D2, 0, // GLX_BIND_TO_MIPMAP_TEXTURE_EXT
+        0x20D3, 0x7, // GLX_BIND_TO_TEXTURE_TARGETS_EXT
+        0x20D4, 0xFFFFFFFF, // GLX_Y_INVERTED_EXT = GLX_DONT_CARE
+        0x20B2, 1, // GLX_FRAMEBUFFER_SRGB_CAPABLE_EXT
+        0x800C, 0, // GLX_SCREEN
+        0, 0, // padding
+    ];
+
+    /// A synthetic RGBA16F config with coverage sampling, using the ARB and NV attributes, with
+    /// GLX_RGBA after GLX_RENDER_TYPE and an attribute breadglx doesn't know about.
+    const RGBA16F_FBCONFIG: &[u32] = &[
+        0x8013, 0x1C3, // GLX_FBCONFIG_ID
+        0x800B, 0, // GLX_VISUAL_ID
+        0x8011, 0x4, // GLX_RENDER_TYPE = GLX_RGBA_FLOAT_BIT_ARB
+        4, 1, // GLX_RGBA
+        0x8012, 0, // GLX_X_RENDERABLE
+        8, 16, // GLX_RED_SIZE
+        9, 16, // GLX_GREEN_SIZE
+        10, 16, // GLX_BLUE_SIZE
+        11, 16, // GLX_ALPHA_SIZE
+        2, 64, // GLX_BUFFER_SIZE
+        12, 24, // GLX_DEPTH_SIZE
+        13, 8, // GLX_STENCIL_SIZE
+        100000, 1, // GLX_SAMPLE_BUFFERS_ARB
+        100001, 8, // GLX_SAMPLES_ARB
+        0x20B3, 4, // GLX_COLOR_SAMPLES_NV
+        0x20B0, 1, // GLX_FLOAT_COMPONENTS_NV
+        0x20B2, 0, // GLX_FRAMEBUFFER_SRGB_CAPABLE_ARB
+        0x800C, 1, // GLX_SCREEN
+        0x8010, 0x4, // GLX_DRAWABLE_TYPE = GLX_PBUFFER_BIT
+        0x20F0, 3, // an attribute breadglx doesn't know about
+    ];
+
+    #[test]
+    fn rgba8_config() {
+        let config = GlConfig::from_properties(RGBA8_FBCONFIG, true, true);
+        assert_eq!(config.visual_id, 0x21);
+        assert_eq!(config.fbconfig_id, 0x7A);
+        assert_eq!(config.render_type, RGBA_BIT);
+        assert_eq!(config.double_buffer_mode, 1);
+        assert_eq!(config.stereo_mode, 0);
+        assert_eq!(
+            (
+                config.red_bits,
+                config.green_bits,
+                config.blue_bits,
+                config.alpha_bits
+            ),
+            (8, 8, 8, 8)
+        );
+        assert_eq!(config.rgb_bits, 32);
+        assert_eq!((config.depth_bits, config.stencil_bits), (24, 8));
+        assert_eq!(config.visual_type, GlVisualType::TrueColor);
+        assert_eq!(config.visual_rating, CONFIG_NONE);
+        assert_eq!(config.swap_method, GlSwapMethod::Undefined);
+        assert_eq!((config.sample_buffers, config.samples), (1, 4));
+        assert_eq!(config.drawable_type, WINDOW_BIT | PIXMAP_BIT | PBUFFER_BIT);
+        assert_eq!(config.bind_to_texture_targets, 0x7);
+        assert_eq!(config.y_inverted, DONT_CARE);
+        assert_eq!(config.srgb_capable, 1);
+        assert_eq!(config.screen, 0);
+        assert_eq!(config.float_components, 0);
+        assert_eq!(config.color_samples, 0);
+    }
+
+    #[test]
+    fn rgba16f_config() {
+        let config = GlConfig::from_properties(RGBA16F_FBCONFIG, true, true);
+        assert_eq!(config.fbconfig_id, 0x1C3);
+        assert_eq!(config.float_components, 1);
+        assert_eq!(config.color_samples, 4);
+        assert_eq!(config.screen, 1);
+        assert_eq!((config.sample_buffers, config.samples), (1, 8));
+        assert_eq!(config.srgb_capable, 0);
+        assert_eq!(config.drawable_type, PBUFFER_BIT);
+        // the unknown attribute's value isn't mistaken for a tag
+        assert_eq!(config.rgb_bits, 64);
+    }
+
+    #[test]
+    fn render_type_takes_precedence_over_rgba() {
+        // GLX_RGBA can come after GLX_RENDER_TYPE, but mustn't clobber the float bit
+        let config = GlConfig::from_properties(RGBA16F_FBCONFIG, true, true);
+        assert_eq!(config.render_type, RGBA_FLOAT_BIT_ARB);
+
+        // or before it
+        let config = GlConfig::from_properties(&[4, 1, 0x8011, 0x8, 0, 0], true, true);
+        assert_eq!(config.render_type, RGBA_UNSIGNED_FLOAT_BIT_EXT);
+
+        // a GLX_DONT_CARE render type falls back to what GLX_RGBA said
+        let config = GlConfig::from_properties(&[0x8011, 0xFFFFFFFF, 4, 0, 0, 0], true, true);
+        assert_eq!(config.render_type, COLOR_INDEX_BIT);
+    }
+
+    #[test]
+    fn extension_aliases() {
+        // GLX_FRAMEBUFFER_SRGB_CAPABLE_EXT and _ARB share a tag, as do the SGIS and ARB sample tags,
+        // so the EXT/SGIS names and the ARB names land in the same fields
+        let ext = GlConfig::from_properties(RGBA8_FBCONFIG, true, true);
+        let arb = GlConfig::from_properties(RGBA16F_FBCONFIG, true, true);
+        assert_eq!((ext.srgb_capable, arb.srgb_capable), (1, 0));
+        assert_eq!((ext.sample_buffers, ext.samples), (1, 4));
+        assert_eq!((arb.sample_buffers, arb.samples), (1, 8));
+
+        // a server that doesn't send them leaves them at their defaults
+        let config = GlConfig::from_properties(&[8, 8, 0, 0], true, true);
+        assert_eq!(config.srgb_capable, DONT_CARE);
+        assert_eq!((config.sample_buffers, config.samples), (0, 0));
+    }
+
+    #[test]
+    fn multiple_configs() {
+        let mut props = RGBA8_FBCONFIG.to_vec();
+        props.extend_from_slice(RGBA8_FBCONFIG);
+        props[RGBA8_FBCONFIG.len() + 3] = 0x7B;
+
+        let configs = GlConfig::set_from_properties(&props, RGBA8_FBCONFIG.len(), 2, true);
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].fbconfig_id, 0x7A);
+        assert_eq!(configs[1].fbconfig_id, 0x7B);
+        assert_eq!(configs[1].samples, 4);
+    }
+
+    #[test]
+    fn unknown_tags_warn_once() {
+        assert!(report_unknown_tag(0xDEAD_BEEF, 1));
+        assert!(!report_unknown_tag(0xDEAD_BEEF, 1));
+        assert!(!report_unknown_tag(0xDEAD_BEEF, 2));
     }
 }
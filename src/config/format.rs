@@ -0,0 +1,198 @@
+// MIT/Apache2 License
+
+use super::*;
+use std::fmt;
+
+/// The column headers for the rows produced by `GlConfig`'s `Display` implementation.
+const TABLE_HEADER: &str = "    id visual cl  bf  r  g  b  a db st dpth stcl ms cav     drw sr\n\
+     ------ ------ -- --- -- -- -- -- -- -- ---- ---- -- ------- --- --";
+
+impl fmt::Display for GlConfig {
+    /// Write this config as a single row of a table, like `glxinfo -t`. See `GlConfig::table` for
+    /// a version with column headers.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>6} {:>6} {} {:>3} {:>2} {:>2} {:>2} {:>2} {:>2} {:>2} {:>4} {:>4} {:>2} {:<7} {} {:>2}",
+            XId(self.fbconfig_id),
+            XId(self.visual_id),
+            visual_class(self.visual_type),
+            self.rgb_bits,
+            self.red_bits,
+            self.green_bits,
+            self.blue_bits,
+            self.alpha_bits,
+            yes_no(self.double_buffer_mode as _),
+            yes_no(self.stereo_mode as _),
+            self.depth_bits,
+            self.stencil_bits,
+            self.samples,
+            caveat(self.visual_rating),
+            DrawableTypes(self.drawable_type),
+            yes_no(self.srgb_capable),
+        )
+    }
+}
+
+impl GlConfig {
+    /// Format a set of configs as a table with column headers, one config per row.
+    #[inline]
+    pub fn table(configs: &[GlConfig]) -> GlConfigTable<'_> {
+        GlConfigTable { configs }
+    }
+}
+
+/// A table of configs, created by `GlConfig::table`.
+#[derive(Debug, Copy, Clone)]
+pub struct GlConfigTable<'a> {
+    configs: &'a [GlConfig],
+}
+
+impl<'a> fmt::Display for GlConfigTable<'a> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", TABLE_HEADER)?;
+        self.configs
+            .iter()
+            .try_for_each(|config| writeln!(f, "{}", config))
+    }
+}
+
+/// The ID of an fbconfig or visual, or a placeholder if the config doesn't have one. Visuals have
+/// no fbconfig ID, and fbconfigs that can't be rendered to a window have no visual.
+struct XId(c_int);
+
+impl fmt::Display for XId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 | DONT_CARE => f.pad("-"),
+            id => f.pad(&format!("{:#06x}", id)),
+        }
+    }
+}
+
+struct DrawableTypes(c_int);
+
+impl fmt::Display for DrawableTypes {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        [(WINDOW_BIT, 'w'), (PIXMAP_BIT, 'p'), (PBUFFER_BIT, 'b')]
+            .iter()
+            .try_for_each(|(bit, c)| {
+                if self.0 & bit != 0 {
+                    write!(f, "{}", c)
+                } else {
+                    f.write_str(".")
+                }
+            })
+    }
+}
+
+#[inline]
+fn visual_class(vt: GlVisualType) -> &'static str {
+    match vt {
+        GlVisualType::TrueColor => "tc",
+        GlVisualType::DirectColor => "dc",
+        GlVisualType::PseudoColor => "pc",
+        GlVisualType::StaticColor => "sc",
+        GlVisualType::GrayScale => "gs",
+        GlVisualType::StaticGray => "sg",
+        GlVisualType::DontCare => " .",
+    }
+}
+
+#[inline]
+fn yes_no(val: c_int) -> &'static str {
+    match val {
+        0 => "n",
+        DONT_CARE => ".",
+        _ => "y",
+    }
+}
+
+#[inline]
+fn caveat(rating: c_int) -> &'static str {
+    match rating {
+        CONFIG_NONE => "none",
+        SLOW_CONFIG => "slow",
+        NON_CONFORMANT_CONFIG => "noncnf",
+        _ => ".",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The byte ranges of the runs of non-space characters in a line.
+    fn columns(line: &str) -> Vec<(usize, usize)> {
+        let mut columns = vec![];
+        let mut start = None;
+        for (i, c) in line.char_indices().chain(Some((line.len(), ' '))) {
+            match (c, start) {
+                (' ', Some(s)) => {
+                    columns.push((s, i));
+                    start = None;
+                }
+                (' ', None) => (),
+                (_, None) => start = Some(i),
+                (_, Some(_)) => (),
+            }
+        }
+        columns
+    }
+
+    #[test]
+    fn table_alignment() {
+        let fbconfig = GlConfig {
+            fbconfig_id: 0x1a2,
+            visual_id: 0x21,
+            visual_type: GlVisualType::TrueColor,
+            rgb_bits: 32,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
+            alpha_bits: 8,
+            double_buffer_mode: 1,
+            depth_bits: 24,
+            stencil_bits: 8,
+            samples: 16,
+            visual_rating: NON_CONFORMANT_CONFIG,
+            drawable_type: WINDOW_BIT | PIXMAP_BIT | PBUFFER_BIT,
+            srgb_capable: 1,
+            ..GlConfig::default()
+        };
+        let visual = GlConfig {
+            fbconfig_id: DONT_CARE,
+            visual_id: 0x7fff,
+            visual_type: GlVisualType::DirectColor,
+            drawable_type: WINDOW_BIT,
+            ..GlConfig::default()
+        };
+        let table = GlConfig::table(&[fbconfig, visual]).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+
+        // every field of every row, headers included, lines up with a column of dashes
+        let dashes = columns(lines[1]);
+        assert_eq!(dashes.len(), 16);
+        for line in [lines[0], lines[2], lines[3]].iter() {
+            let fields = columns(line);
+            assert_eq!(fields.len(), dashes.len(), "{:?}", line);
+            for (field, dash) in fields.iter().zip(dashes.iter()) {
+                assert!(
+                    dash.0 <= field.0 && field.1 <= dash.1,
+                    "{:?} doesn't line up with {:?}",
+                    line,
+                    lines[1]
+                );
+            }
+        }
+
+        assert!(lines[2].starts_with("0x01a2 0x0021 tc"));
+        // visuals have no fbconfig ID
+        assert!(lines[3].starts_with("     - 0x7fff dc"));
+    }
+}
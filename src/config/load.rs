@@ -2,7 +2,10 @@
 
 use super::GlConfig;
 use breadx::display::{Connection, Display};
-use std::convert::TryInto;
+use std::{
+    convert::TryInto,
+    os::raw::{c_int, c_uint},
+};
 
 const U32_NO_FIT: &str = "u32 doesn't fit in usize";
 
@@ -21,7 +24,7 @@ impl GlConfig {
         let vis = dpy.resolve_request(vis_tok)?;
         let fbs = dpy.resolve_request(fbs_tok)?;
 
-        let (vis, fbs) = (
+        let (mut vis, mut fbs) = (
             GlConfig::set_from_properties(
                 &vis.property_list,
                 vis.num_properties.try_into().expect(U32_NO_FIT),
@@ -36,6 +39,9 @@ impl GlConfig {
             ),
         );
 
+        set_color_masks(dpy, screen, &mut vis);
+        set_color_masks(dpy, screen, &mut fbs);
+
         Ok((vis, fbs))
     }
 
@@ -51,7 +57,7 @@ impl GlConfig {
         let vis = dpy.resolve_request_async(vis_tok).await?;
         let fbs = dpy.resolve_request_async(fbs_tok).await?;
 
-        Ok((
+        let (mut vis, mut fbs) = (
             GlConfig::set_from_properties(
                 &vis.property_list,
                 vis.num_properties.try_into().expect(U32_NO_FIT),
@@ -64,6 +70,53 @@ impl GlConfig {
                 fbs.num_fb_configs.try_into().expect(U32_NO_FIT),
                 true,
             ),
-        ))
+        );
+        set_color_masks(dpy, screen, &mut vis);
+        set_color_masks(dpy, screen, &mut fbs);
+
+        Ok((vis, fbs))
     }
 }
+
+/// The server doesn't send color masks along with the GLX configs, but DRI drivers need them to
+/// tell apart formats with the same channel sizes (e.g. XRGB2101010 and XBGR2101010). Take them
+/// from the X visual associated with each config instead. Configs without a visual keep their masks
+/// zeroed, which is taken to mean "unknown".
+#[inline]
+fn set_color_masks<Conn>(dpy: &Display<Conn>, screen: usize, configs: &mut [GlConfig]) {
+    let root = match dpy.setup().roots.get(screen) {
+        Some(root) => root,
+        None => return,
+    };
+
+    configs.iter_mut().for_each(|config| {
+        let visual = root
+            .allowed_depths
+            .iter()
+            .flat_map(|depth| depth.visuals.iter())
+            .find(|visual| visual.visual_id as c_int == config.visual_id);
+        let visual = match visual {
+            // color index visuals don't have masks
+            Some(visual) if visual.red_mask != 0 => visual,
+            _ => return,
+        };
+
+        config.red_mask = visual.red_mask;
+        config.green_mask = visual.green_mask;
+        config.blue_mask = visual.blue_mask;
+        config.red_shift = visual.red_mask.trailing_zeros();
+        config.green_shift = visual.green_mask.trailing_zeros();
+        config.blue_shift = visual.blue_mask.trailing_zeros();
+
+        // the alpha channel is whatever's left over in the pixel
+        if config.alpha_bits > 0 {
+            let pixel_mask = match config.rgb_bits {
+                bits if bits >= 32 => c_uint::MAX,
+                bits => (1 << bits) - 1,
+            };
+            config.alpha_mask =
+                pixel_mask & !(visual.red_mask | visual.green_mask | visual.blue_mask);
+            config.alpha_shift = config.alpha_mask.trailing_zeros() % 32;
+        }
+    });
+}
@@ -9,11 +9,16 @@ use std::{
     ptr::{self, addr_of_mut as raw_mut},
 };
 
+mod choose;
 mod compatible;
 mod construct;
+mod format;
 mod load;
+mod request;
 mod rules;
 mod values;
+pub use format::*;
+pub use request::*;
 pub use rules::*;
 pub use values::*;
 
@@ -90,6 +95,11 @@ pub struct GlConfig {
 
     pub y_inverted: c_int,
     pub srgb_capable: c_int,
+
+    /// Whether the color buffer holds floating point components (NV_float_buffer).
+    pub float_components: c_int,
+    /// Number of color samples per pixel, for coverage sampling (NV_multisample_coverage).
+    pub color_samples: c_int,
 }
 
 impl Default for GlConfig {
@@ -157,6 +167,21 @@ impl TryFrom<c_int> for GlVisualType {
     }
 }
 
+impl From<GlVisualType> for c_int {
+    #[inline]
+    fn from(vt: GlVisualType) -> c_int {
+        match vt {
+            GlVisualType::TrueColor => TRUE_COLOR,
+            GlVisualType::DirectColor => DIRECT_COLOR,
+            GlVisualType::PseudoColor => PSEUDO_COLOR,
+            GlVisualType::StaticColor => STATIC_COLOR,
+            GlVisualType::GrayScale => GRAY_SCALE,
+            GlVisualType::StaticGray => STATIC_GRAY,
+            GlVisualType::DontCare => DONT_CARE,
+        }
+    }
+}
+
 /// Swap method.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum GlSwapMethod {
@@ -184,3 +209,15 @@ impl TryFrom<c_int> for GlSwapMethod {
         })
     }
 }
+
+impl From<GlSwapMethod> for c_int {
+    #[inline]
+    fn from(sm: GlSwapMethod) -> c_int {
+        match sm {
+            GlSwapMethod::Exchange => SWAP_EXCHANGE_OML,
+            GlSwapMethod::Copy => SWAP_COPY_OML,
+            GlSwapMethod::Undefined => SWAP_UNDEFINED_OML,
+            GlSwapMethod::DontCare => DONT_CARE,
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// MIT/Apache2 License
+
+use super::{GlConfig, GlConfigRule};
+use std::{cmp::Ordering, error::Error, fmt};
+
+/// A description of the framebuffer config an application wants, split into hard requirements that
+/// every candidate must fulfill and soft preferences that are used to rank the candidates.
+///
+/// Requirements are matched like the attributes passed to `glXChooseFBConfig`, including its
+/// defaults for attributes they don't mention, e.g. `RenderType(RGBA_BIT)` and
+/// `DrawableType(WINDOW_BIT)`. Preferences are matched with the same criteria, but without any
+/// defaults.
+///
+/// For instance, an application that wants 4x MSAA but can live without it would `require` a depth
+/// buffer and double buffering, and `prefer` `GlConfigRule::Samples(4)`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FbConfigRequest {
+    requirements: Vec<GlConfigRule>,
+    preferences: Vec<FbConfigPreference>,
+}
+
+/// A soft preference in a `FbConfigRequest`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FbConfigPreference {
+    /// The rule that a config should fulfill.
+    pub rule: GlConfigRule,
+    /// How much fulfilling the rule adds to a config's score.
+    pub weight: u32,
+}
+
+impl FbConfigRequest {
+    /// Create a new request with no requirements or preferences, which any config fulfills.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule that every chosen config must fulfill.
+    #[inline]
+    pub fn require(mut self, rule: GlConfigRule) -> Self {
+        self.requirements.push(rule);
+        self
+    }
+
+    /// Add a set of rules that every chosen config must fulfill.
+    #[inline]
+    pub fn require_all(mut self, rules: &[GlConfigRule]) -> Self {
+        self.requirements.extend_from_slice(rules);
+        self
+    }
+
+    /// Add a rule that configs should fulfill if possible, with a weight of one.
+    #[inline]
+    pub fn prefer(self, rule: GlConfigRule) -> Self {
+        self.prefer_weighted(rule, 1)
+    }
+
+    /// Add a rule that configs should fulfill if possible. Configs that fulfill it have `weight`
+    /// added to their score.
+    #[inline]
+    pub fn prefer_weighted(mut self, rule: GlConfigRule, weight: u32) -> Self {
+        self.preferences.push(FbConfigPreference { rule, weight });
+        self
+    }
+
+    /// The rules that every chosen config must fulfill.
+    #[inline]
+    pub fn requirements(&self) -> &[GlConfigRule] {
+        &self.requirements
+    }
+
+    /// The rules that configs should fulfill if possible.
+    #[inline]
+    pub fn preferences(&self) -> &[FbConfigPreference] {
+        &self.preferences
+    }
+
+    /// The requirements that a config does not fulfill. This includes the `glXChooseFBConfig`
+    /// defaults that the config doesn't fulfill.
+    #[inline]
+    pub fn missing(&self, config: &GlConfig) -> Vec<GlConfigRule> {
+        if self.accepts(config) {
+            return vec![];
+        }
+
+        self.requirements
+            .iter()
+            .copied()
+            .chain(GlConfig::chooser_defaults(&self.requirements))
+            .filter(|rule| !config.matches_rule(*rule))
+            .collect()
+    }
+
+    /// Tell whether or not a config fulfills every requirement.
+    #[inline]
+    pub fn accepts(&self, config: &GlConfig) -> bool {
+        config.matches_template(&GlConfig::chooser_template(&self.requirements))
+    }
+
+    /// The sum of the weights of every preference that a config fulfills.
+    #[inline]
+    pub fn score(&self, config: &GlConfig) -> u32 {
+        self.preferences
+            .iter()
+            .filter(|pref| config.matches_rule(pref.rule))
+            .map(|pref| pref.weight)
+            .sum()
+    }
+
+    /// Get every config that fulfills the requirements, sorted from best to worst. Configs are ranked
+    /// by their score, and configs with the same score are ranked using the `glXChooseFBConfig`
+    /// sort order.
+    #[inline]
+    pub fn rank<'a>(&self, configs: &'a [GlConfig]) -> Vec<&'a GlConfig> {
+        let template = GlConfig::chooser_template(&self.requirements);
+        let mut ranked: Vec<(u32, &'a GlConfig)> = configs
+            .iter()
+            .filter(|config| config.matches_template(&template))
+            .map(|config| (self.score(config), config))
+            .collect();
+        ranked.sort_by(|(score_a, a), (score_b, b)| match score_b.cmp(score_a) {
+            Ordering::Equal => a.cmp_for_template(b, &template),
+            ordering => ordering,
+        });
+        ranked.into_iter().map(|(_, config)| config).collect()
+    }
+
+    /// Choose the best config that fulfills the requirements. If no config does, the error lists
+    /// the requirements that the closest config was missing.
+    #[inline]
+    pub fn choose<'a>(&self, configs: &'a [GlConfig]) -> Result<&'a GlConfig, FbConfigError> {
+        if let Some(best) = self.rank(configs).into_iter().next() {
+            return Ok(best);
+        }
+
+        // find the config that misses the fewest requirements, and report what it lacks
+        let missing = configs
+            .iter()
+            .map(|config| self.missing(config))
+            .min_by_key(|missing| missing.len())
+            .unwrap_or_else(|| self.requirements.clone());
+
+        Err(FbConfigError {
+            candidates: configs.len(),
+            missing,
+        })
+    }
+}
+
+/// The error returned when no config fulfills the requirements of a `FbConfigRequest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FbConfigError {
+    /// The number of configs that were considered.
+    pub candidates: usize,
+    /// The requirements missing from the config that came closest to fulfilling the request. If
+    /// there were no configs to consider, this contains every requirement.
+    pub missing: Vec<GlConfigRule>,
+}
+
+impl fmt::Display for FbConfigError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "None of the {} framebuffer configs fulfill the request; the closest is missing {:?}",
+            self.candidates, self.missing
+        )
+    }
+}
+
+impl Error for FbConfigError {}
+
+impl From<FbConfigError> for breadx::BreadError {
+    #[inline]
+    fn from(fce: FbConfigError) -> breadx::BreadError {
+        breadx::BreadError::Msg(fce.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        GlVisualType, COLOR_INDEX_BIT, DONT_CARE, PIXMAP_BIT, RGBA_BIT, SLOW_CONFIG, WINDOW_BIT,
+    };
+
+    fn config(fbconfig_id: i32, samples: i32, srgb_capable: i32) -> GlConfig {
+        GlConfig {
+            fbconfig_id,
+            red_bits: 8,
+            green_bits: 8,
+            blue_bits: 8,
+            rgb_bits: 24,
+            depth_bits: 24,
+            double_buffer_mode: 1,
+            sample_buffers: (samples > 0) as i32,
+            samples,
+            srgb_capable,
+            visual_type: GlVisualType::TrueColor,
+            render_type: RGBA_BIT,
+            drawable_type: WINDOW_BIT,
+            ..GlConfig::default()
+        }
+    }
+
+    fn ids(configs: &[&GlConfig]) -> Vec<i32> {
+        configs.iter().map(|config| config.fbconfig_id).collect()
+    }
+
+    #[test]
+    fn requirements_use_chooser_defaults() {
+        let request = FbConfigRequest::new().require(GlConfigRule::DepthBits(16));
+        assert!(request.accepts(&config(1, 0, 0)));
+
+        // an unspecified render type means RGBA, like glXChooseFBConfig
+        let color_index = GlConfig {
+            render_type: COLOR_INDEX_BIT,
+            ..config(2, 0, 0)
+        };
+        assert!(!request.accepts(&color_index));
+        assert_eq!(
+            request.missing(&color_index),
+            [GlConfigRule::RenderType(RGBA_BIT)]
+        );
+        let request = request.require(GlConfigRule::RenderType(COLOR_INDEX_BIT));
+        assert!(request.accepts(&color_index));
+
+        // and so does accepting every config
+        let configs = [config(3, 0, 0), color_index];
+        assert_eq!(ids(&FbConfigRequest::new().rank(&configs)), [3]);
+    }
+
+    #[test]
+    fn dont_care() {
+        let pixmap = GlConfig {
+            drawable_type: PIXMAP_BIT,
+            ..config(1, 0, 0)
+        };
+        let request = FbConfigRequest::new()
+            .require(GlConfigRule::DrawableType(DONT_CARE))
+            .prefer(GlConfigRule::DoubleBufferMode(DONT_CARE as _));
+        assert!(request.accepts(&pixmap));
+        assert_eq!(request.score(&pixmap), 1);
+    }
+
+    #[test]
+    fn missing() {
+        let request = FbConfigRequest::new()
+            .require(GlConfigRule::DepthBits(32))
+            .require(GlConfigRule::StencilBits(8))
+            .require(GlConfigRule::DoubleBufferMode(1));
+        assert_eq!(
+            request.missing(&config(1, 0, 0)),
+            [GlConfigRule::DepthBits(32), GlConfigRule::StencilBits(8)]
+        );
+        let deep = GlConfig {
+            depth_bits: 32,
+            stencil_bits: 8,
+            ..config(2, 0, 0)
+        };
+        assert!(request.missing(&deep).is_empty());
+    }
+
+    #[test]
+    fn score() {
+        let request = FbConfigRequest::new()
+            .prefer_weighted(GlConfigRule::Samples(4), 4)
+            .prefer(GlConfigRule::SrgbCapable(1));
+        assert_eq!(request.score(&config(1, 0, 0)), 0);
+        assert_eq!(request.score(&config(2, 0, 1)), 1);
+        assert_eq!(request.score(&config(3, 4, 0)), 4);
+        assert_eq!(request.score(&config(4, 8, 1)), 5);
+    }
+
+    #[test]
+    fn rank() {
+        let configs = [
+            config(1, 0, 0),
+            GlConfig {
+                visual_rating: SLOW_CONFIG,
+                ..config(2, 4, 0)
+            },
+            config(3, 4, 0),
+            config(4, 0, 1),
+            config(5, 8, 1),
+            GlConfig {
+                depth_bits: 0,
+                ..config(6, 8, 1)
+            },
+        ];
+        let request = FbConfigRequest::new()
+            .require(GlConfigRule::DepthBits(1))
+            .prefer_weighted(GlConfigRule::Samples(4), 2)
+            .prefer(GlConfigRule::SrgbCapable(1));
+
+        // by score first, then in glXChooseFBConfig order, and the config without depth is out
+        assert_eq!(ids(&request.rank(&configs)), [5, 3, 2, 4, 1]);
+    }
+
+    #[test]
+    fn choose() {
+        let configs = [config(1, 0, 0), config(2, 4, 0)];
+        let request = FbConfigRequest::new().prefer(GlConfigRule::Samples(4));
+        assert_eq!(request.choose(&configs).unwrap().fbconfig_id, 2);
+
+        // the error lists what the closest config is missing
+        let request = request
+            .require(GlConfigRule::StencilBits(8))
+            .require(GlConfigRule::Samples(2));
+        assert_eq!(
+            request.choose(&configs),
+            Err(FbConfigError {
+                candidates: 2,
+                missing: vec![GlConfigRule::StencilBits(8)],
+            })
+        );
+        // and without any candidates, every requirement is missing
+        assert_eq!(
+            request.choose(&[]),
+            Err(FbConfigError {
+                candidates: 0,
+                missing: vec![GlConfigRule::StencilBits(8), GlConfigRule::Samples(2)],
+            })
+        );
+    }
+}
@@ -59,43 +59,293 @@ pub enum GlConfigRule {
     Level(c_int),
 }
 
-/*
 impl GlConfigRule {
+    /// Convert a GLX attribute and its value into a rule, as they would appear in the attribute list
+    /// passed to `glXChooseFBConfig`.
     #[inline]
-    pub(crate) fn into_raw_glx_pair(self) -> (c_int, c_int) {
-match self {
-Self::RgbBits(rgb) => (BUFFER_SIZE, rgb),
-Self::Level(l) => (LEVEL, l),
-Self::DoubleBufferMode(dbm) => (DOUBLEBFUFER_MODE, dbm),
-Self::StereoMode(s) => (STEREO_MODE, s),
-Self::NumAuxBuffers(nab) => (AUX_BUFFERS, nab),
-Self::RedBits(r) => (RED_SIZE, r),
-Self::GreenBits(g) => (GREEN_SIZE, g),
-Self::BlueBits(b) => (BLUE_SIZE, b),
-Self::AlphaBits(a) => (ALPHA_SIZE, a),
-Self::DepthBits(d) => (DEPTH_SIZE, d),
-Self::StencilBits(s) => (STENCIL_SIZE, s),
-Self::AccumRedBits(r) => (ACCUM_RED_SIZE, r),
-Self::AccumGreenBits(g) => (ACCUM_GREEN_SIZE, g),
-Self::AccumBlueBits(b) => (ACCUM_BLUE_SIZE, b),
-Self::AccumAlphaSize(a) => (ACCUM_ALPHA_SIZE, a),
-Self::VisualRating(vr) => (VISUAL_CAVEAT_EXT, vr),
-Self::VisualType(v) => (VISUAL_TYPE, match v {
-  GlVisualType::TrueColor => TRUE_COLOR,
-  GlVisualType::DirectColor => DIRECT_COLOR,
-  GlVisualType::PseudoColor => PSEUDO_COLOR,
-  GlVisualType::StaticColor => STATIC_COLOR,
-  GlVisualType::GrayScale => GRAY_SCALE,
-  GlVisualType::StaticGray => STATIC_GRAY,
-  GlVisualType::DontCare => DONT_CARE,
-}),
-Self::TransparentPixel(tp) => (TRANSPARENT_TYPE, tp),
-Self::TransparentIndex(ti) => (TRANSPARENT_INDEX, ti),
-Self::TransparentRed(tr) => (TRANSPARENT_RED, tr),
-Self::TransparentGreen(tg) => (TRANSPARENT_GREEN, tg),
-Self::TransparentBlue(tb) => (TRANSPARENT_BLUE, tb),
-Self::TransparentAlpha(ta) => (TRANSPARENT_ALPHA, ta),
+    pub fn from_raw_glx_pair(attrib: c_int, value: c_int) -> breadx::Result<Self> {
+        Ok(match attrib {
+            BUFFER_SIZE => Self::RgbBits(value),
+            LEVEL => Self::Level(value),
+            DOUBLEBUFFER_MODE => Self::DoubleBufferMode(glx_bool(attrib, value)? as _),
+            STEREO_MODE => Self::StereoMode(glx_bool(attrib, value)? as _),
+            AUX_BUFFERS => Self::NumAuxBuffers(value),
+            RED_SIZE => Self::RedBits(value),
+            GREEN_SIZE => Self::GreenBits(value),
+            BLUE_SIZE => Self::BlueBits(value),
+            ALPHA_SIZE => Self::AlphaBits(value),
+            DEPTH_SIZE => Self::DepthBits(value),
+            STENCIL_SIZE => Self::StencilBits(value),
+            ACCUM_RED_SIZE => Self::AccumRedBits(value),
+            ACCUM_GREEN_SIZE => Self::AccumGreenBits(value),
+            ACCUM_BLUE_SIZE => Self::AccumBlueBits(value),
+            ACCUM_ALPHA_SIZE => Self::AccumAlphaBits(value),
+            VISUAL_CAVEAT_EXT => match value {
+                CONFIG_NONE | SLOW_CONFIG | NON_CONFORMANT_CONFIG | DONT_CARE => {
+                    Self::VisualRating(value)
+                }
+                value => return Err(bad_value(attrib, value)),
+            },
+            VISUAL_TYPE => Self::VisualType(
+                GlVisualType::try_from(value).map_err(|value| bad_value(attrib, value))?,
+            ),
+            TRANSPARENT_TYPE => match value {
+                CONFIG_NONE | TRANSPARENT_RGB | TRANSPARENT_INDEX | DONT_CARE => {
+                    Self::TransparentPixel(value)
+                }
+                value => return Err(bad_value(attrib, value)),
+            },
+            TRANSPARENT_INDEX_ID => Self::TransparentIndex(value),
+            TRANSPARENT_RED => Self::TransparentRed(value),
+            TRANSPARENT_GREEN => Self::TransparentGreen(value),
+            TRANSPARENT_BLUE => Self::TransparentBlue(value),
+            TRANSPARENT_ALPHA => Self::TransparentAlpha(value),
+            VISUAL_ID => Self::VisualId(value),
+            SCREEN => Self::Screen(value),
+            DRAWABLE_TYPE => Self::DrawableType(value),
+            RENDER_TYPE => Self::RenderType(value),
+            X_RENDERABLE => Self::XRenderable(glx_bool(attrib, value)?),
+            GLX_FBCONFIG_ID => Self::FbconfigId(value),
+            MAX_PBUFFER_WIDTH => Self::MaxPbufferWidth(value),
+            MAX_PBUFFER_HEIGHT => Self::MaxPbufferHeight(value),
+            MAX_PBUFFER_PIXELS => Self::MaxPbufferPixels(value),
+            OPTIMAL_PBUFFER_WIDTH_SGIX => Self::OptimalPbufferWidth(value),
+            OPTIMAL_PBUFFER_HEIGHT_SGIX => Self::OptimalPbufferHeight(value),
+            VISUAL_SELECT_GROUP_SGIX => Self::VisualSelectGroup(value),
+            SWAP_METHOD_OML => Self::SwapMethod(
+                GlSwapMethod::try_from(value).map_err(|value| bad_value(attrib, value))?,
+            ),
+            SAMPLE_BUFFERS => Self::SampleBuffers(value),
+            SAMPLES => Self::Samples(value),
+            FRAMEBUFFER_SRGB_CAPABLE_EXT => Self::SrgbCapable(glx_bool(attrib, value)?),
+            BIND_TO_TEXTURE_RGB_EXT => Self::BindToTextureRgb(glx_bool(attrib, value)?),
+            BIND_TO_TEXTURE_RGBA_EXT => Self::BindToTextureRgba(glx_bool(attrib, value)?),
+            BIND_TO_MIPMAP_TEXTURE_EXT => Self::BindToMipmapTexture(glx_bool(attrib, value)?),
+            BIND_TO_TEXTURE_TARGETS_EXT => Self::BindToTextureTargets(value),
+            Y_INVERTED_EXT => Self::YInverted(glx_bool(attrib, value)?),
+            attrib => {
+                return Err(breadx::BreadError::Msg(format!(
+                    "Unknown GLX attribute: {:#X}",
+                    attrib
+                )))
+            }
+        })
+    }
+
+    /// Convert this rule into a GLX attribute and its value. Fails for rules that only exist in
+    /// Mesa's internal config representation and have no GLX attribute, like the color masks.
+    #[inline]
+    pub fn into_raw_glx_pair(self) -> breadx::Result<(c_int, c_int)> {
+        Ok(match self {
+            Self::RgbBits(rgb) => (BUFFER_SIZE, rgb),
+            Self::Level(l) => (LEVEL, l),
+            Self::DoubleBufferMode(dbm) => (DOUBLEBUFFER_MODE, dbm as _),
+            Self::StereoMode(s) => (STEREO_MODE, s as _),
+            Self::NumAuxBuffers(nab) => (AUX_BUFFERS, nab),
+            Self::RedBits(r) => (RED_SIZE, r),
+            Self::GreenBits(g) => (GREEN_SIZE, g),
+            Self::BlueBits(b) => (BLUE_SIZE, b),
+            Self::AlphaBits(a) => (ALPHA_SIZE, a),
+            Self::DepthBits(d) => (DEPTH_SIZE, d),
+            Self::StencilBits(s) => (STENCIL_SIZE, s),
+            Self::AccumRedBits(r) => (ACCUM_RED_SIZE, r),
+            Self::AccumGreenBits(g) => (ACCUM_GREEN_SIZE, g),
+            Self::AccumBlueBits(b) => (ACCUM_BLUE_SIZE, b),
+            Self::AccumAlphaBits(a) => (ACCUM_ALPHA_SIZE, a),
+            Self::VisualRating(vr) => (VISUAL_CAVEAT_EXT, vr),
+            Self::VisualType(v) => (VISUAL_TYPE, v.into()),
+            Self::TransparentPixel(tp) => (TRANSPARENT_TYPE, tp),
+            Self::TransparentIndex(ti) => (TRANSPARENT_INDEX_ID, ti),
+            Self::TransparentRed(tr) => (TRANSPARENT_RED, tr),
+            Self::TransparentGreen(tg) => (TRANSPARENT_GREEN, tg),
+            Self::TransparentBlue(tb) => (TRANSPARENT_BLUE, tb),
+            Self::TransparentAlpha(ta) => (TRANSPARENT_ALPHA, ta),
+            Self::VisualId(v) => (VISUAL_ID, v),
+            Self::Screen(s) => (SCREEN, s),
+            Self::DrawableType(d) => (DRAWABLE_TYPE, d),
+            Self::RenderType(r) => (RENDER_TYPE, r),
+            Self::XRenderable(xr) => (X_RENDERABLE, xr),
+            Self::FbconfigId(f) => (GLX_FBCONFIG_ID, f),
+            Self::MaxPbufferWidth(w) => (MAX_PBUFFER_WIDTH, w),
+            Self::MaxPbufferHeight(h) => (MAX_PBUFFER_HEIGHT, h),
+            Self::MaxPbufferPixels(p) => (MAX_PBUFFER_PIXELS, p),
+            Self::OptimalPbufferWidth(w) => (OPTIMAL_PBUFFER_WIDTH_SGIX, w),
+            Self::OptimalPbufferHeight(h) => (OPTIMAL_PBUFFER_HEIGHT_SGIX, h),
+            Self::VisualSelectGroup(vsg) => (VISUAL_SELECT_GROUP_SGIX, vsg),
+            Self::SwapMethod(sm) => (SWAP_METHOD_OML, sm.into()),
+            Self::SampleBuffers(sb) => (SAMPLE_BUFFERS, sb),
+            Self::Samples(s) => (SAMPLES, s),
+            Self::SrgbCapable(s) => (FRAMEBUFFER_SRGB_CAPABLE_EXT, s),
+            Self::BindToTextureRgb(r) => (BIND_TO_TEXTURE_RGB_EXT, r),
+            Self::BindToTextureRgba(r) => (BIND_TO_TEXTURE_RGBA_EXT, r),
+            Self::BindToMipmapTexture(m) => (BIND_TO_MIPMAP_TEXTURE_EXT, m),
+            Self::BindToTextureTargets(tt) => (BIND_TO_TEXTURE_TARGETS_EXT, tt),
+            Self::YInverted(y) => (Y_INVERTED_EXT, y),
+            Self::RedMask(_)
+            | Self::GreenMask(_)
+            | Self::BlueMask(_)
+            | Self::AlphaMask(_)
+            | Self::RedShift(_)
+            | Self::GreenShift(_)
+            | Self::BlueShift(_)
+            | Self::AlphaShift(_)
+            | Self::ColorIndex(_) => {
+                return Err(breadx::BreadError::Msg(format!(
+                    "{:?} has no GLX attribute",
+                    self
+                )))
+            }
+        })
+    }
+
+    /// Parse a raw GLX attribute list, as passed to `glXChooseFBConfig`. The list is made up of
+    /// attribute/value pairs, and may be terminated by `None` (zero).
+    #[inline]
+    pub fn from_raw_glx_list(list: &[c_int]) -> breadx::Result<Vec<Self>> {
+        // everything past the terminator is ignored, like libGL does
+        let list = match list.chunks(2).position(|pair| pair[0] == 0) {
+            Some(end) => &list[..end * 2],
+            None if !list.len().is_multiple_of(2) => {
+                return Err(breadx::BreadError::StaticMsg(
+                    "GLX attribute list has an attribute without a value",
+                ))
+            }
+            None => list,
+        };
+
+        list.chunks_exact(2)
+            .map(|pair| Self::from_raw_glx_pair(pair[0], pair[1]))
+            .collect()
+    }
+
+    /// Serialize a set of rules into a raw GLX attribute list, terminated by `None` (zero) so that it
+    /// can be handed directly to C code.
+    #[inline]
+    pub fn to_raw_glx_list(rules: &[Self]) -> breadx::Result<Vec<c_int>> {
+        let mut list = Vec::with_capacity(rules.len() * 2 + 1);
+        for rule in rules {
+            let (attrib, value) = rule.into_raw_glx_pair()?;
+            list.push(attrib);
+            list.push(value);
+        }
+        list.push(0);
+        Ok(list)
+    }
+}
+
+/// Make sure a value is a valid GLX boolean, i.e. `True`, `False` or `GLX_DONT_CARE`.
+#[inline]
+fn glx_bool(attrib: c_int, value: c_int) -> breadx::Result<c_int> {
+    match value {
+        0 | 1 | DONT_CARE => Ok(value),
+        value => Err(bad_value(attrib, value)),
+    }
+}
+
+#[inline]
+fn bad_value(attrib: c_int, value: c_int) -> breadx::BreadError {
+    breadx::BreadError::Msg(format!(
+        "Invalid value for GLX attribute {:#X}: {:#X}",
+        attrib, value
+    ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list() {
+        let rules = GlConfigRule::from_raw_glx_list(&[
+            RED_SIZE,
+            8,
+            DOUBLEBUFFER_MODE,
+            1,
+            VISUAL_TYPE,
+            TRUE_COLOR,
+            SWAP_METHOD_OML,
+            SWAP_COPY_OML,
+            0,
+        ])
+        .unwrap();
+        assert_eq!(
+            rules,
+            [
+                GlConfigRule::RedBits(8),
+                GlConfigRule::DoubleBufferMode(1),
+                GlConfigRule::VisualType(GlVisualType::TrueColor),
+                GlConfigRule::SwapMethod(GlSwapMethod::Copy),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let list = [
+            DEPTH_SIZE,
+            24,
+            X_RENDERABLE,
+            DONT_CARE,
+            VISUAL_CAVEAT_EXT,
+            SLOW_CONFIG,
+            VISUAL_TYPE,
+            DIRECT_COLOR,
+            0,
+        ];
+        let rules = GlConfigRule::from_raw_glx_list(&list).unwrap();
+        assert_eq!(GlConfigRule::to_raw_glx_list(&rules).unwrap(), list);
+    }
+
+    #[test]
+    fn terminator() {
+        // the terminator is optional, and anything after it is ignored
+        assert_eq!(
+            GlConfigRule::from_raw_glx_list(&[DEPTH_SIZE, 24]).unwrap(),
+            [GlConfigRule::DepthBits(24)]
+        );
+        assert_eq!(
+            GlConfigRule::from_raw_glx_list(&[DEPTH_SIZE, 24, 0, 0xDEAD]).unwrap(),
+            [GlConfigRule::DepthBits(24)]
+        );
+        assert!(GlConfigRule::from_raw_glx_list(&[]).unwrap().is_empty());
+        assert!(GlConfigRule::from_raw_glx_list(&[0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn odd_length() {
+        assert!(GlConfigRule::from_raw_glx_list(&[DEPTH_SIZE]).is_err());
+        assert!(GlConfigRule::from_raw_glx_list(&[RED_SIZE, 8, DEPTH_SIZE]).is_err());
+    }
+
+    #[test]
+    fn unknown_attribute() {
+        assert!(GlConfigRule::from_raw_glx_pair(0xDEAD, 1).is_err());
+        assert!(GlConfigRule::from_raw_glx_list(&[RED_SIZE, 8, 0xDEAD, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn invalid_values() {
+        let invalid = [
+            (DOUBLEBUFFER_MODE, 2),
+            (X_RENDERABLE, -2),
+            (VISUAL_CAVEAT_EXT, 1),
+            (VISUAL_TYPE, 0x1234),
+            (TRANSPARENT_TYPE, 1),
+            (SWAP_METHOD_OML, 0),
+        ];
+        for &(attrib, value) in invalid.iter() {
+            assert!(
+                GlConfigRule::from_raw_glx_pair(attrib, value).is_err(),
+                "{:#X} accepted {:#X}",
+                attrib,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rules_without_attributes() {
+        assert!(GlConfigRule::to_raw_glx_list(&[GlConfigRule::RedMask(0xFF)]).is_err());
+        assert!(GlConfigRule::to_raw_glx_list(&[GlConfigRule::ColorIndex(8)]).is_err());
     }
 }
-*/
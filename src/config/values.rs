@@ -14,6 +14,8 @@ pub const TRANSPARENT_RGB: c_int = 0x8008;
 pub const TRANSPARENT_INDEX: c_int = 0x8009;
 pub const RGBA_TYPE: c_int = 0x8014;
 pub const COLOR_INDEX_TYPE: c_int = 0x8015;
+pub const RGBA_FLOAT_TYPE_ARB: c_int = 0x20B9;
+pub const RGBA_UNSIGNED_FLOAT_TYPE_EXT: c_int = 0x20B1;
 pub const NON_CONFORMANT_CONFIG: c_int = 0x800D;
 pub const SLOW_CONFIG: c_int = 0x8001;
 pub const RGBA_FLOAT_BIT_ARB: c_int = 0x4;
@@ -46,9 +48,27 @@ pub const TRANSPARENT_GREEN: c_int = 0x26;
 pub const TRANSPARENT_BLUE: c_int = 0x27;
 pub const TRANSPARENT_ALPHA: c_int = 0x28;
 pub const VISUAL_ID: c_int = 0x800B;
+pub const SHARE_CONTEXT_EXT: c_int = 0x800A;
+pub const VISUAL_ID_EXT: c_int = 0x800B;
+pub const SCREEN: c_int = 0x800C;
 pub const DRAWABLE_TYPE: c_int = 0x8010;
 pub const RENDER_TYPE: c_int = 0x8011;
 pub const X_RENDERABLE: c_int = 0x8012;
+pub const MAX_PBUFFER_WIDTH: c_int = 0x8016;
+pub const MAX_PBUFFER_HEIGHT: c_int = 0x8017;
+pub const MAX_PBUFFER_PIXELS: c_int = 0x8018;
+pub const OPTIMAL_PBUFFER_WIDTH_SGIX: c_int = 0x8019;
+pub const OPTIMAL_PBUFFER_HEIGHT_SGIX: c_int = 0x801A;
+pub const VISUAL_SELECT_GROUP_SGIX: c_int = 0x8028;
+pub const SWAP_METHOD_OML: c_int = 0x8060;
+pub const SAMPLE_BUFFERS: c_int = 100000;
+pub const SAMPLES: c_int = 100001;
+pub const FRAMEBUFFER_SRGB_CAPABLE_EXT: c_int = 0x20B2;
+pub const BIND_TO_TEXTURE_RGB_EXT: c_int = 0x20D0;
+pub const BIND_TO_TEXTURE_RGBA_EXT: c_int = 0x20D1;
+pub const BIND_TO_MIPMAP_TEXTURE_EXT: c_int = 0x20D2;
+pub const BIND_TO_TEXTURE_TARGETS_EXT: c_int = 0x20D3;
+pub const Y_INVERTED_EXT: c_int = 0x20D4;
 
 pub const TRUE_COLOR: c_int = 0x8002;
 pub const DIRECT_COLOR: c_int = 0x8003;
@@ -57,6 +77,10 @@ pub const STATIC_COLOR: c_int = 0x8005;
 pub const GRAY_SCALE: c_int = 0x8006;
 pub const STATIC_GRAY: c_int = 0x8007;
 
+pub const SWAP_EXCHANGE_OML: c_int = 0x8061;
+pub const SWAP_COPY_OML: c_int = 0x8062;
+pub const SWAP_UNDEFINED_OML: c_int = 0x8063;
+
 pub const MAJOR_VERSION_ARB: c_int = 0x2091;
 pub const MINOR_VERSION_ARB: c_int = 0x2092;
 pub const FLAGS_ARB: c_int = 0x2094;
@@ -1,12 +1,16 @@
 // MIT/Apache2 License
 
 use crate::config::{
-    COMPAT_PROFILE_BIT_ARB, CORE_PROFILE_BIT_ARB, ES_PROFILE_BIT_ARB, FLAGS_ARB,
+    COLOR_INDEX_TYPE, COMPAT_PROFILE_BIT_ARB, CORE_PROFILE_BIT_ARB, ES_PROFILE_BIT_ARB, FLAGS_ARB,
     FLUSH_RELEASE_BEHAVIOR_ARB, LOSE_CONTEXT_RESET_NOTIFICATION, MAJOR_VERSION_ARB,
     MINOR_VERSION_ARB, NONE_RELEASE_BEHAVIOR_ARB, NO_ERROR_ARB, NO_RESET_NOTIFICATION,
     PROFILE_MASK_ARB, RELEASE_BEHAVIOR_ARB, RENDER_TYPE_ARB, RESET_NOTIFICATION_STRATEGY,
+    RGBA_FLOAT_TYPE_ARB, RGBA_TYPE, RGBA_UNSIGNED_FLOAT_TYPE_EXT,
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    os::raw::c_int,
 };
-use std::{convert::TryInto, os::raw::c_int};
 use tinyvec::{ArrayVec, TinyVec};
 
 const DOESNT_FIT: &str = "value can't fit in c_int";
@@ -32,6 +36,106 @@ pub enum Profile {
     Es,
 }
 
+/// The client API that a context implements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GlApi {
+    /// Desktop OpenGL, using the compatibility profile.
+    OpenGl,
+    /// Desktop OpenGL, using the core profile.
+    OpenGlCore,
+    /// OpenGL ES 1.x.
+    Gles1,
+    /// OpenGL ES 2.0.
+    Gles2,
+    /// OpenGL ES 3.x.
+    Gles3,
+}
+
+impl GlApi {
+    /// Whether or not this API is a version of OpenGL ES.
+    #[inline]
+    pub fn is_es(self) -> bool {
+        matches!(self, Self::Gles1 | Self::Gles2 | Self::Gles3)
+    }
+}
+
+/// The kind of color buffer a context renders to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GlRenderType {
+    /// RGBA colors with normalized components.
+    Rgba,
+    /// Colors indexing into a colormap.
+    ColorIndex,
+    /// RGBA colors with floating point components, from GLX_ARB_fbconfig_float.
+    RgbaFloat,
+    /// RGBA colors with unsigned floating point components, from
+    /// GLX_EXT_fbconfig_packed_float.
+    RgbaUnsignedFloat,
+}
+
+impl TryFrom<u32> for GlRenderType {
+    type Error = u32;
+
+    #[inline]
+    fn try_from(i: u32) -> Result<Self, u32> {
+        Ok(match i as c_int {
+            RGBA_TYPE => Self::Rgba,
+            COLOR_INDEX_TYPE => Self::ColorIndex,
+            RGBA_FLOAT_TYPE_ARB => Self::RgbaFloat,
+            RGBA_UNSIGNED_FLOAT_TYPE_EXT => Self::RgbaUnsignedFloat,
+            _ => return Err(i),
+        })
+    }
+}
+
+impl From<GlRenderType> for u32 {
+    #[inline]
+    fn from(rt: GlRenderType) -> u32 {
+        (match rt {
+            GlRenderType::Rgba => RGBA_TYPE,
+            GlRenderType::ColorIndex => COLOR_INDEX_TYPE,
+            GlRenderType::RgbaFloat => RGBA_FLOAT_TYPE_ARB,
+            GlRenderType::RgbaUnsignedFloat => RGBA_UNSIGNED_FLOAT_TYPE_EXT,
+        }) as u32
+    }
+}
+
+/// Information about a context, derived from the rules it was created with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ContextInfo {
+    pub(crate) api: GlApi,
+    pub(crate) major: i32,
+    pub(crate) minor: i32,
+    pub(crate) render_type: GlRenderType,
+}
+
+impl ContextInfo {
+    #[inline]
+    pub(crate) fn from_rules(rules: &[GlContextRule]) -> breadx::Result<Self> {
+        let mut info = ContextInfo {
+            api: GlContextRule::requested_api(rules)?,
+            major: 1,
+            minor: 0,
+            render_type: GlRenderType::Rgba,
+        };
+
+        for rule in rules {
+            match rule {
+                GlContextRule::MajorVersion(mv) => info.major = *mv,
+                GlContextRule::MinorVersion(mv) => info.minor = *mv,
+                GlContextRule::RenderType(rt) => {
+                    info.render_type = GlRenderType::try_from(*rt).map_err(|_| {
+                        breadx::BreadError::StaticMsg("Invalid context render type")
+                    })?;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(info)
+    }
+}
+
 /// Reset notification strategy for the context.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum ResetNotificationStrategy {
@@ -47,6 +151,34 @@ pub enum ReleaseBehavior {
 }
 
 impl GlContextRule {
+    /// Determine the client API a set of context rules asks for. This follows the version rules set
+    /// out by GLX_ARB_create_context_profile and GLX_EXT_create_context_es2_profile.
+    #[inline]
+    pub fn requested_api(rules: &[GlContextRule]) -> breadx::Result<GlApi> {
+        let (mut major, mut minor, mut profile) = (1, 0, Profile::Core);
+        rules.iter().for_each(|rule| match rule {
+            Self::MajorVersion(mv) => major = *mv,
+            Self::MinorVersion(mv) => minor = *mv,
+            Self::Profile(p) => profile = *p,
+            _ => (),
+        });
+
+        Ok(match (profile, major, minor) {
+            (Profile::Core, major, minor) if major > 3 || (major == 3 && minor >= 2) => {
+                GlApi::OpenGlCore
+            }
+            (Profile::Core, _, _) | (Profile::Compatibility, _, _) => GlApi::OpenGl,
+            (Profile::Es, 1, 0) | (Profile::Es, 1, 1) => GlApi::Gles1,
+            (Profile::Es, 2, 0) => GlApi::Gles2,
+            (Profile::Es, 3, minor) if (0..=2).contains(&minor) => GlApi::Gles3,
+            (Profile::Es, _, _) => {
+                return Err(breadx::BreadError::StaticMsg(
+                    "Invalid version for an OpenGL ES context",
+                ))
+            }
+        })
+    }
+
     /// Convert a set of context rules into classical OpenGL
     #[inline]
     pub(crate) fn convert_ctx_attrib_to_classic(rules: &[GlContextRule]) -> TinyVec<[c_int; 4]> {
@@ -91,3 +223,76 @@ impl GlContextRule {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline]
+    fn api(profile: Profile, major: i32, minor: i32) -> breadx::Result<GlApi> {
+        GlContextRule::requested_api(&[
+            GlContextRule::Profile(profile),
+            GlContextRule::MajorVersion(major),
+            GlContextRule::MinorVersion(minor),
+        ])
+    }
+
+    #[test]
+    fn desktop_gl() {
+        assert_eq!(GlContextRule::requested_api(&[]).unwrap(), GlApi::OpenGl);
+        assert_eq!(api(Profile::Core, 3, 1).unwrap(), GlApi::OpenGl);
+        assert_eq!(api(Profile::Core, 3, 2).unwrap(), GlApi::OpenGlCore);
+        assert_eq!(api(Profile::Core, 4, 6).unwrap(), GlApi::OpenGlCore);
+        assert_eq!(api(Profile::Compatibility, 4, 6).unwrap(), GlApi::OpenGl);
+    }
+
+    #[test]
+    fn gles() {
+        assert_eq!(api(Profile::Es, 1, 0).unwrap(), GlApi::Gles1);
+        assert_eq!(api(Profile::Es, 1, 1).unwrap(), GlApi::Gles1);
+        assert_eq!(api(Profile::Es, 2, 0).unwrap(), GlApi::Gles2);
+        assert_eq!(api(Profile::Es, 3, 0).unwrap(), GlApi::Gles3);
+        assert_eq!(api(Profile::Es, 3, 2).unwrap(), GlApi::Gles3);
+        assert!(api(Profile::Es, 1, 0).unwrap().is_es());
+        assert!(!api(Profile::Core, 4, 6).unwrap().is_es());
+    }
+
+    #[test]
+    fn invalid_gles_versions() {
+        assert!(api(Profile::Es, 1, 2).is_err());
+        assert!(api(Profile::Es, 2, 1).is_err());
+        assert!(api(Profile::Es, 3, 3).is_err());
+        assert!(api(Profile::Es, 4, 0).is_err());
+    }
+
+    #[test]
+    fn context_info_from_rules() {
+        let info = ContextInfo::from_rules(&[
+            GlContextRule::Profile(Profile::Es),
+            GlContextRule::MajorVersion(3),
+            GlContextRule::MinorVersion(1),
+        ])
+        .unwrap();
+        assert_eq!(info.api, GlApi::Gles3);
+        assert_eq!((info.major, info.minor), (3, 1));
+        assert_eq!(info.render_type, GlRenderType::Rgba);
+    }
+
+    #[test]
+    fn render_types() {
+        for rt in &[
+            GlRenderType::Rgba,
+            GlRenderType::ColorIndex,
+            GlRenderType::RgbaFloat,
+            GlRenderType::RgbaUnsignedFloat,
+        ] {
+            assert_eq!(GlRenderType::try_from(u32::from(*rt)), Ok(*rt));
+        }
+        assert_eq!(GlRenderType::try_from(0x1234), Err(0x1234));
+
+        let info = ContextInfo::from_rules(&[GlContextRule::RenderType(RGBA_FLOAT_TYPE_ARB as _)])
+            .unwrap();
+        assert_eq!(info.render_type, GlRenderType::RgbaFloat);
+        assert!(ContextInfo::from_rules(&[GlContextRule::RenderType(0x1234)]).is_err());
+    }
+}
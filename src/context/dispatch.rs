@@ -7,10 +7,11 @@ use crate::{
     indirect,
 };
 use breadx::{
+    auto::glx,
     display::{Connection, Display},
     Drawable,
 };
-use std::ffi::CStr;
+use std::{ffi::CStr, os::raw::c_int};
 
 #[cfg(feature = "async")]
 use crate::{context::AsyncGlInternalContext, util::GenericFuture};
@@ -108,6 +109,23 @@ where
             Self::Dri3(d3) => d3.get_proc_address(name),
         }
     }
+
+    #[inline]
+    fn query(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> breadx::Result<Option<c_int>> {
+        match self {
+            Self::Placeholder => unreachable!("Invalid placeholder"),
+            Self::Indirect(i) => i.query(dpy, xid, attrib),
+            #[cfg(feature = "dri")]
+            Self::Dri2(d2) => d2.query(dpy, xid, attrib),
+            #[cfg(feature = "dri3")]
+            Self::Dri3(d3) => d3.query(dpy, xid, attrib),
+        }
+    }
 }
 
 #[cfg(feature = "async")]
@@ -166,4 +184,25 @@ where
             Self::Dri3(d3) => d3.get_proc_address_async(name),
         }
     }
+
+    #[inline]
+    fn query_async<'future, 'a, 'b>(
+        &'a self,
+        dpy: &'b GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> GenericFuture<'future, breadx::Result<Option<c_int>>>
+    where
+        'a: 'future,
+        'b: 'future,
+    {
+        match self {
+            Self::Placeholder => unreachable!("Invalid placeholder"),
+            Self::Indirect(i) => i.query_async(dpy, xid, attrib),
+            #[cfg(feature = "dri")]
+            Self::Dri2(d2) => d2.query_async(dpy, xid, attrib),
+            #[cfg(feature = "dri3")]
+            Self::Dri3(d3) => d3.query_async(dpy, xid, attrib),
+        }
+    }
 }
@@ -1,7 +1,7 @@
 // MIT/Apache2 License
 
 use super::{
-    config::GlConfig,
+    config::{GlConfig, SCREEN, SHARE_CONTEXT_EXT},
     display::{DisplayLike, GlDisplay},
     screen::GlScreen,
 };
@@ -12,8 +12,10 @@ use breadx::{
 };
 use std::{
     any::Any,
+    convert::TryInto,
     ffi::{c_void, CStr},
     mem,
+    os::raw::c_int,
     ptr::NonNull,
     sync::Arc,
 };
@@ -33,6 +35,8 @@ mod attrib;
 pub use attrib::*;
 pub(crate) mod dispatch;
 pub(crate) use dispatch::ContextDispatch;
+mod share;
+pub use share::*;
 
 #[cfg(not(feature = "async"))]
 use once_cell::sync::OnceCell;
@@ -82,8 +86,17 @@ pub(crate) struct InnerGlContext<Dpy> {
     screen: usize,
     // framebuffer config associated with this context
     fbconfig: GlConfig,
+    // the context this context shares objects with
+    share: glx::Context,
+    // the group of contexts this context shares objects with
+    share_group: ShareGroup<Dpy>,
+    // information derived from the rules used to create this context
+    info: ContextInfo,
     // inner mechanism
     inner: ContextDispatch<Dpy>,
+    // keeps the share context alive for as long as this context lives; declared after the dispatch
+    // so that this context is torn down before the one it shares objects with
+    share_parent: Option<GlContext<Dpy>>,
 }
 
 pub(crate) trait GlInternalContext<Dpy> {
@@ -99,6 +112,14 @@ pub(crate) trait GlInternalContext<Dpy> {
 
     /// Get the proc address for the given function.
     fn get_proc_address(&self, name: &CStr) -> Option<ProcAddress>;
+
+    /// Query an attribute of this context. Returns `None` if the attribute is unknown.
+    fn query(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> breadx::Result<Option<c_int>>;
 }
 
 #[cfg(feature = "async")]
@@ -121,6 +142,15 @@ pub(crate) trait AsyncGlInternalContext<Dpy> {
     where
         'a: 'future,
         'b: 'future;
+    fn query_async<'future, 'a, 'b>(
+        &'a self,
+        dpy: &'b GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> GenericFuture<'future, breadx::Result<Option<c_int>>>
+    where
+        'a: 'future,
+        'b: 'future;
 }
 
 impl<Dpy> GlContext<Dpy> {
@@ -130,12 +160,24 @@ impl<Dpy> GlContext<Dpy> {
     }
 
     #[inline]
-    pub(crate) fn new(xid: glx::Context, screen: usize, fbconfig: GlConfig) -> Self {
+    pub(crate) fn new(
+        xid: glx::Context,
+        screen: usize,
+        fbconfig: GlConfig,
+        share: glx::Context,
+        share_parent: Option<GlContext<Dpy>>,
+        share_group: ShareGroup<Dpy>,
+        info: ContextInfo,
+    ) -> Self {
         Self {
             inner: Arc::new(InnerGlContext {
                 xid,
                 screen,
                 fbconfig,
+                share,
+                share_parent,
+                share_group,
+                info,
                 inner: ContextDispatch::Placeholder,
             }),
         }
@@ -153,6 +195,75 @@ impl<Dpy> GlContext<Dpy> {
         self.inner.xid
     }
 
+    /// The client API that this context implements.
+    #[inline]
+    pub fn api(&self) -> GlApi {
+        self.inner.info.api
+    }
+
+    /// The major and minor version of the API that was requested when this context was created.
+    /// Drivers are free to create any later version that's compatible with it, so this is a lower
+    /// bound on the version the context implements.
+    #[inline]
+    pub fn requested_version(&self) -> (i32, i32) {
+        (self.inner.info.major, self.inner.info.minor)
+    }
+
+    /// The render type this context was created with.
+    #[inline]
+    pub fn render_type(&self) -> GlRenderType {
+        self.inner.info.render_type
+    }
+
+    /// The framebuffer config this context was created with.
+    #[inline]
+    pub fn fbconfig(&self) -> &GlConfig {
+        &self.inner.fbconfig
+    }
+
+    /// The index of the screen this context was created on.
+    #[inline]
+    pub fn screen_index(&self) -> usize {
+        self.inner.screen
+    }
+
+    /// The context this context shares objects with, if any.
+    #[inline]
+    pub fn share_context(&self) -> Option<Context> {
+        match self.inner.share {
+            Context { xid: 0 } => None,
+            share => Some(share),
+        }
+    }
+
+    /// The context this context shares objects with, if it was created by breadglx. Imported
+    /// contexts don't know about the context they share objects with.
+    #[inline]
+    pub fn share_parent(&self) -> Option<&GlContext<Dpy>> {
+        self.inner.share_parent.as_ref()
+    }
+
+    /// The group of contexts this context shares objects with.
+    #[inline]
+    pub fn share_group(&self) -> &ShareGroup<Dpy> {
+        &self.inner.share_group
+    }
+
+    /// Query attributes that don't depend on the backing implementation.
+    #[inline]
+    fn query_common(&self, attrib: c_int) -> Option<c_int> {
+        match attrib {
+            SCREEN => Some(
+                self.inner
+                    .screen
+                    .try_into()
+                    .expect("screen doesn't fit in c_int"),
+            ),
+            SHARE_CONTEXT_EXT => Some(self.inner.share.xid as _),
+            _ => None,
+        }
+    }
+
     #[inline]
     pub(crate) fn get() -> RwLockReadGuard<'static, Option<AnyArc>> {
         get_current_context()
@@ -228,6 +339,20 @@ where
     pub(crate) fn get_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
         self.inner.inner.get_proc_address(name)
     }
+
+    /// Query an attribute of this context, like `glXQueryContext`. Indirect contexts ask the server,
+    /// while direct contexts answer using the state they were created with.
+    #[inline]
+    pub fn query(&self, dpy: &GlDisplay<Dpy>, attrib: c_int) -> breadx::Result<c_int> {
+        match self.query_common(attrib) {
+            Some(val) => Ok(val),
+            None => self
+                .inner
+                .inner
+                .query(dpy, self.inner.xid, attrib)?
+                .ok_or(breadx::BreadError::StaticMsg("Unknown context attribute")),
+        }
+    }
 }
 
 #[cfg(feature = "async")]
@@ -281,6 +406,38 @@ where
         let draw = draw.into();
         self.bind_internal_async(dpy, Some(draw), Some(draw))
     }
+
+    /// Unbind the current context, async redox.
+    #[inline]
+    pub async fn unbind_async() -> breadx::Result {
+        let old_gc = take_current_context_async().await;
+        let old_gc = old_gc.and_then(|m| promote_anyarc::<Dpy>(m));
+
+        if let Some(old_gc) = old_gc {
+            old_gc.inner.inner.unbind_async().await
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    pub(crate) async fn get_proc_address_async(&self, name: &CStr) -> Option<ProcAddress> {
+        self.inner.inner.get_proc_address_async(name).await
+    }
+
+    /// Query an attribute of this context, async redox.
+    #[inline]
+    pub async fn query_async(&self, dpy: &GlDisplay<Dpy>, attrib: c_int) -> breadx::Result<c_int> {
+        match self.query_common(attrib) {
+            Some(val) => Ok(val),
+            None => self
+                .inner
+                .inner
+                .query_async(dpy, self.inner.xid, attrib)
+                .await?
+                .ok_or(breadx::BreadError::StaticMsg("Unknown context attribute")),
+        }
+    }
 }
 
 pub(crate) type AnyArc = Arc<dyn Any + Send + Sync + 'static>;
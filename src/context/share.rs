@@ -0,0 +1,268 @@
+// MIT/Apache2 License
+
+use super::{GlContext, InnerGlContext};
+use crate::display::GlDisplay;
+use std::{
+    fmt,
+    sync::{Arc, Mutex, Weak},
+};
+
+/// The rendering backend that a context or screen uses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Backend {
+    Indirect,
+    Dri2,
+    Dri3,
+}
+
+/// A group of contexts that share objects (textures, buffers, display lists, et cetera) with one
+/// another. Every context belongs to exactly one share group; contexts created without a share
+/// context start a new one.
+///
+/// Contexts in a share group must live on the same display and screen, and use the same backend.
+/// A context keeps the context it shares objects with alive, so the shared objects survive as long
+/// as any member of the group does.
+pub struct ShareGroup<Dpy> {
+    inner: Arc<ShareGroupInner<Dpy>>,
+}
+
+struct ShareGroupInner<Dpy> {
+    display: GlDisplay<Dpy>,
+    screen: usize,
+    backend: Backend,
+    direct: bool,
+    members: Mutex<Vec<Weak<InnerGlContext<Dpy>>>>,
+}
+
+impl<Dpy> Clone for ShareGroup<Dpy> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<Dpy> fmt::Debug for ShareGroup<Dpy> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShareGroup")
+            .field("screen", &self.inner.screen)
+            .field("backend", &self.inner.backend)
+            .field("direct", &self.inner.direct)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+const FAILED_MEMBERS: &str = "Failed to acquire lock on share group members";
+
+impl<Dpy> ShareGroup<Dpy> {
+    #[inline]
+    pub(crate) fn new(
+        display: GlDisplay<Dpy>,
+        screen: usize,
+        backend: Backend,
+        direct: bool,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ShareGroupInner {
+                display,
+                screen,
+                backend,
+                direct,
+                members: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Make sure that a context created with these parameters can share objects with this group.
+    #[inline]
+    pub(crate) fn validate(
+        &self,
+        display: &GlDisplay<Dpy>,
+        screen: usize,
+        backend: Backend,
+        direct: bool,
+    ) -> breadx::Result {
+        if !self.inner.display.ptr_eq(display) {
+            Err(breadx::BreadError::StaticMsg(
+                "Share context belongs to a different display",
+            ))
+        } else if self.inner.screen != screen {
+            Err(breadx::BreadError::StaticMsg(
+                "Share context belongs to a different screen",
+            ))
+        } else if self.inner.backend != backend {
+            Err(breadx::BreadError::StaticMsg(
+                "Share context uses a different rendering backend",
+            ))
+        } else if self.inner.direct != direct {
+            Err(breadx::BreadError::StaticMsg(
+                "Direct and indirect contexts cannot share objects",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Add a context to this share group.
+    #[inline]
+    pub(crate) fn join(&self, ctx: &GlContext<Dpy>) {
+        let mut members = self.inner.members.lock().expect(FAILED_MEMBERS);
+        members.retain(|member| member.strong_count() > 0);
+        members.push(Arc::downgrade(&ctx.inner));
+    }
+
+    /// The index of the screen that the contexts in this group live on.
+    #[inline]
+    pub fn screen_index(&self) -> usize {
+        self.inner.screen
+    }
+
+    /// The rendering backend the contexts in this group use.
+    #[cfg(feature = "glvnd")]
+    #[inline]
+    pub(crate) fn backend(&self) -> Backend {
+        self.inner.backend
+    }
+
+    /// Whether or not the contexts in this group use direct rendering.
+    #[inline]
+    pub fn is_direct(&self) -> bool {
+        self.inner.direct
+    }
+
+    /// The contexts in this group that are still alive.
+    #[inline]
+    pub fn members(&self) -> Vec<GlContext<Dpy>> {
+        self.inner
+            .members
+            .lock()
+            .expect(FAILED_MEMBERS)
+            .iter()
+            .filter_map(|member| member.upgrade())
+            .map(|inner| GlContext { inner })
+            .collect()
+    }
+
+    /// The number of contexts in this group that are still alive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner
+            .members
+            .lock()
+            .expect(FAILED_MEMBERS)
+            .iter()
+            .filter(|member| member.strong_count() > 0)
+            .count()
+    }
+
+    /// Whether or not this group has no living contexts.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether or not the given context belongs to this share group.
+    #[inline]
+    pub fn contains(&self, ctx: &GlContext<Dpy>) -> bool {
+        Arc::ptr_eq(&self.inner, &ctx.share_group().inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::GlConfig,
+        context::{ContextInfo, GlApi, GlRenderType},
+        util::fake_server,
+    };
+    use breadx::{auto::glx, Display};
+    use std::os::unix::net::UnixStream;
+
+    type Dpy = Display<UnixStream>;
+
+    fn context(
+        group: &ShareGroup<Dpy>,
+        xid: u32,
+        share_parent: Option<GlContext<Dpy>>,
+    ) -> GlContext<Dpy> {
+        let share = share_parent
+            .as_ref()
+            .map_or(glx::Context::const_from_xid(0), |parent| parent.xid());
+        let info = ContextInfo {
+            api: GlApi::OpenGl,
+            major: 1,
+            minor: 0,
+            render_type: GlRenderType::Rgba,
+        };
+        let ctx = GlContext::new(
+            glx::Context::const_from_xid(xid),
+            group.screen_index(),
+            GlConfig::default(),
+            share,
+            share_parent,
+            group.clone(),
+            info,
+        );
+        group.join(&ctx);
+        ctx
+    }
+
+    #[test]
+    fn validate() {
+        let (dpy, server) = fake_server();
+        let display = GlDisplay::indirect_placeholder(dpy);
+        let (other_dpy, other_server) = fake_server();
+        let other_display = GlDisplay::indirect_placeholder(other_dpy);
+
+        let group = ShareGroup::new(display.clone(), 0, Backend::Dri3, true);
+        assert!(group.validate(&display, 0, Backend::Dri3, true).is_ok());
+        assert!(group
+            .validate(&other_display, 0, Backend::Dri3, true)
+            .is_err());
+        assert!(group.validate(&display, 1, Backend::Dri3, true).is_err());
+        assert!(group.validate(&display, 0, Backend::Dri2, true).is_err());
+        assert!(group.validate(&display, 0, Backend::Dri3, false).is_err());
+
+        // imported contexts are indirect, so direct contexts can't share with them
+        let imported = ShareGroup::new(display.clone(), 0, Backend::Indirect, false);
+        assert!(imported.validate(&display, 0, Backend::Dri3, true).is_err());
+
+        drop((group, imported, display, other_display));
+        server.join().unwrap();
+        other_server.join().unwrap();
+    }
+
+    #[test]
+    fn join() {
+        let (dpy, server) = fake_server();
+        let display = GlDisplay::indirect_placeholder(dpy);
+        let group = ShareGroup::new(display.clone(), 0, Backend::Dri3, true);
+        let other_group = ShareGroup::new(display.clone(), 0, Backend::Dri3, true);
+        assert!(group.is_empty());
+
+        let first = context(&group, 1, None);
+        let second = context(&group, 2, Some(first.clone()));
+        let outsider = context(&other_group, 3, None);
+        assert_eq!(group.len(), 2);
+        assert!(group.contains(&first) && group.contains(&second));
+        assert!(!group.contains(&outsider));
+        assert_eq!(second.share_context(), Some(first.xid()));
+
+        // the second context keeps the one it shares objects with alive
+        let first_xid = first.xid();
+        drop(first);
+        assert_eq!(group.len(), 2);
+        assert_eq!(second.share_parent().map(GlContext::xid), Some(first_xid));
+
+        drop(second);
+        assert!(group.is_empty());
+        assert!(group.members().is_empty());
+
+        drop((group, other_group, outsider, display));
+        server.join().unwrap();
+    }
+}
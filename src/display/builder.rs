@@ -0,0 +1,87 @@
+// MIT/Apache2 License
+
+use super::{DisplayLike, GlDisplay, GlStats};
+use breadx::display::Connection;
+
+#[cfg(feature = "async")]
+use breadx::display::AsyncConnection;
+
+/// Options for creating a `GlDisplay`. Options that aren't set here are taken from the usual Mesa
+/// environment variables.
+#[derive(Debug, Default, Clone)]
+pub struct GlDisplayBuilder {
+    driver_override: Option<String>,
+    prime: Option<String>,
+    #[cfg(feature = "glvnd")]
+    vendor_override: Option<String>,
+}
+
+impl GlDisplayBuilder {
+    /// Create a new builder with the default options.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the DRI driver with this name (e.g. `"iris"`, `"zink"` or `"kms_swrast"`) instead of
+    /// the one matching the GPU. This takes precedence over `MESA_LOADER_DRIVER_OVERRIDE`.
+    #[inline]
+    pub fn driver_override<S: Into<String>>(mut self, driver: S) -> Self {
+        self.driver_override = Some(driver.into());
+        self
+    }
+
+    /// Render on a GPU other than the one driving the display, like `DRI_PRIME`. This is either an
+    /// index (`"1"` selects the first other GPU), a PCI tag (`"pci-0000_01_00_0"`) or a vendor and
+    /// device ID pair (`"10de:1f82"`). This takes precedence over `DRI_PRIME`.
+    #[inline]
+    pub fn prime<S: Into<String>>(mut self, gpu: S) -> Self {
+        self.prime = Some(gpu.into());
+        self
+    }
+
+    /// Use the GLX vendor library with this name (e.g. `"mesa"` or `"nvidia"`) for every screen,
+    /// instead of the vendors the server names. This takes precedence over
+    /// `__GLX_VENDOR_LIBRARY_NAME`.
+    #[cfg(feature = "glvnd")]
+    #[inline]
+    pub fn glx_vendor<S: Into<String>>(mut self, vendor: S) -> Self {
+        self.vendor_override = Some(vendor.into());
+        self
+    }
+
+    #[inline]
+    fn stats(self) -> GlStats {
+        let mut stats = GlStats::get();
+        if let Some(driver) = self.driver_override {
+            stats.driver_override = Some(driver);
+        }
+        if let Some(prime) = self.prime {
+            stats.prime = Some(prime);
+        }
+        #[cfg(feature = "glvnd")]
+        if let Some(vendor) = self.vendor_override {
+            stats.vendor_override = Some(vendor);
+        }
+        stats
+    }
+
+    /// Create a `GlDisplay` with these options.
+    #[inline]
+    pub fn build<Dpy: DisplayLike>(self, dpy: Dpy) -> breadx::Result<GlDisplay<Dpy>>
+    where
+        Dpy::Connection: Connection,
+    {
+        GlDisplay::from_stats(dpy, self.stats())
+    }
+
+    /// Create a `GlDisplay` with these options, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn build_async<Dpy: DisplayLike>(self, dpy: Dpy) -> breadx::Result<GlDisplay<Dpy>>
+    where
+        Dpy::Connection: AsyncConnection + Send,
+    {
+        GlDisplay::from_stats_async(dpy, self.stats()).await
+    }
+}
@@ -1,25 +1,32 @@
 // MIT/Apache2 License
 
 use crate::{
-    config::GlConfig,
-    context::{promote_anyarc_ref, GlContext},
-    cstr::{const_cstr, ConstCstr},
+    config::{
+        GlConfig, COMPAT_PROFILE_BIT_ARB, CORE_PROFILE_BIT_ARB, ES_PROFILE_BIT_ARB,
+        GLX_FBCONFIG_ID, MAJOR_VERSION_ARB, MINOR_VERSION_ARB, PROFILE_MASK_ARB, RENDER_TYPE,
+        RGBA_TYPE, SCREEN, SHARE_CONTEXT_EXT, VISUAL_ID_EXT,
+    },
+    context::{
+        promote_anyarc_ref, Backend, ContextInfo, GlContext, GlContextRule, ProcAddress, Profile,
+        ShareGroup,
+    },
     dri, indirect, mesa,
     screen::GlScreen,
     util::env_to_boolean,
 };
 use breadx::{
+    auto::glx::{self, QueryContextRequest},
     display::{Connection, Display, DisplayLike as DpyLikeBase},
     Drawable, Visualtype,
 };
 use dashmap::DashMap;
+use once_cell::sync::OnceCell;
 use std::{
     collections::HashMap,
+    env,
     ffi::{c_void, CStr, CString},
-    marker::PhantomData,
-    ops::{Deref, DerefMut, Range},
-    os::raw::c_char,
-    ptr,
+    ops::{Deref, DerefMut},
+    os::raw::c_int,
     sync::Arc,
 };
 
@@ -33,9 +40,16 @@ use breadx::display::AsyncConnection;
 #[cfg(feature = "async")]
 use futures_lite::future;
 
+mod builder;
 mod dispatch;
+mod proc_address;
+#[cfg(feature = "glvnd")]
+mod vendor;
+
+pub use builder::*;
 
 pub(crate) use dispatch::DisplayDispatch;
+use proc_address::{glapi_proc_address, glx_proc_address, EntryPoint};
 
 /// Things that can go inside of a GlDisplay. Since it is shoved into a static variable
 /// at one point, it needs to be Send + Sync + 'static.
@@ -62,6 +76,16 @@ struct InnerGlDisplay<Dpy> {
 
     // cache that maps the drawables to a map of their properties
     drawable_properties: DashMap<Drawable, HashMap<u32, u32>>,
+
+    // the default screen, created the first time a function is looked up with no context current,
+    // or `None` if it couldn't be created
+    default_screen: OnceCell<Option<GlScreen<Dpy>>>,
+
+    // the GLX vendor library for each screen, and the vendor to use instead of the server's choice
+    #[cfg(feature = "glvnd")]
+    vendors: DashMap<usize, Option<&'static crate::glvnd::GlxVendor>>,
+    #[cfg(feature = "glvnd")]
+    vendor_override: Option<String>,
 }
 
 /// Represents a lock on the inner display.
@@ -148,6 +172,117 @@ impl<Dpy> GlDisplay<Dpy> {
     pub(crate) fn dispatch(&self) -> &dispatch::DisplayDispatch<Dpy> {
         &self.inner.context
     }
+
+    /// Whether or not two handles refer to the same display.
+    #[inline]
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<Dpy: DisplayLike> GlDisplay<Dpy> {
+    /// Build an imported context out of the attributes the server reported for it.
+    #[inline]
+    fn imported_context(
+        &self,
+        xid: glx::Context,
+        attribs: &HashMap<u32, u32>,
+        screen: usize,
+        visuals: &[GlConfig],
+        fbconfigs: &[GlConfig],
+    ) -> breadx::Result<GlContext<Dpy>> {
+        let attrib = |name: c_int| attribs.get(&(name as u32)).map(|val| *val as c_int);
+
+        // older servers only report the visual, so fall back to that if we don't have an fbconfig
+        let fbconfig = match attrib(GLX_FBCONFIG_ID) {
+            Some(fbid) => fbconfigs.iter().find(|fb| fb.fbconfig_id == fbid),
+            None => attrib(VISUAL_ID_EXT)
+                .and_then(|visid| visuals.iter().find(|vis| vis.visual_id == visid)),
+        }
+        .ok_or(breadx::BreadError::StaticMsg(
+            "Unable to find the imported context's config",
+        ))?;
+
+        let info = ContextInfo::from_rules(&imported_context_rules(attribs))?;
+        let share = glx::Context::const_from_xid(attrib(SHARE_CONTEXT_EXT).unwrap_or(0) as _);
+
+        // we don't own the share context, so the imported context starts its own share group. it's
+        // indirect, so no context we create can ever join it
+        let share_group = ShareGroup::new(self.clone(), screen, Backend::Indirect, false);
+
+        let mut ctx = GlContext::new(
+            xid,
+            screen,
+            fbconfig.clone(),
+            share,
+            None,
+            share_group,
+            info,
+        );
+        ctx.set_dispatch(indirect::IndirectContext::new(self.clone(), &info).into());
+        ctx.share_group().join(&ctx);
+        Ok(ctx)
+    }
+}
+
+/// Turn a list of alternating keys and values, as the server sends them, into a map. A trailing key
+/// without a value is ignored.
+#[inline]
+fn attrib_pairs(list: &[u32]) -> HashMap<u32, u32> {
+    list.chunks_exact(2).map(|kv| (kv[0], kv[1])).collect()
+}
+
+/// Rebuild the rules an imported context was created with from the attributes the server reported.
+/// Servers that don't report the version or profile get the defaults, which describe a legacy
+/// OpenGL 1.0 context.
+#[inline]
+fn imported_context_rules(attribs: &HashMap<u32, u32>) -> Vec<GlContextRule> {
+    let attrib = |name: c_int| attribs.get(&(name as u32)).map(|val| *val as c_int);
+
+    let mut rules = vec![GlContextRule::RenderType(
+        attrib(RENDER_TYPE).unwrap_or(RGBA_TYPE) as _,
+    )];
+    if let Some(major) = attrib(MAJOR_VERSION_ARB) {
+        rules.push(GlContextRule::MajorVersion(major));
+    }
+    if let Some(minor) = attrib(MINOR_VERSION_ARB) {
+        rules.push(GlContextRule::MinorVersion(minor));
+    }
+    match attrib(PROFILE_MASK_ARB) {
+        Some(mask) if mask & ES_PROFILE_BIT_ARB != 0 => {
+            rules.push(GlContextRule::Profile(Profile::Es))
+        }
+        Some(mask) if mask & COMPAT_PROFILE_BIT_ARB != 0 => {
+            rules.push(GlContextRule::Profile(Profile::Compatibility))
+        }
+        Some(mask) if mask & CORE_PROFILE_BIT_ARB != 0 => {
+            rules.push(GlContextRule::Profile(Profile::Core))
+        }
+        _ => (),
+    }
+    rules
+}
+
+/// Get the screen an imported context belongs to.
+#[inline]
+fn imported_context_screen(attribs: &HashMap<u32, u32>) -> breadx::Result<usize> {
+    attribs
+        .get(&(SCREEN as u32))
+        .map(|screen| *screen as usize)
+        .ok_or(breadx::BreadError::StaticMsg(
+            "Server did not report the imported context's screen",
+        ))
+}
+
+#[inline]
+fn proc_address_result(function: &CStr, f: Option<ProcAddress>) -> breadx::Result<*const c_void> {
+    match f {
+        Some(f) => Ok(f.into_inner().as_ptr() as *const _),
+        None => Err(breadx::BreadError::Msg(format!(
+            "Unable to find OpenGL function: {:?}",
+            function
+        ))),
+    }
 }
 
 impl<Dpy: DisplayLike> GlDisplay<Dpy> {
@@ -187,6 +322,10 @@ struct GlStats {
     accel: bool,
     no_dri3: bool,
     no_dri2: bool,
+    driver_override: Option<String>,
+    prime: Option<String>,
+    #[cfg(feature = "glvnd")]
+    vendor_override: Option<String>,
 }
 
 impl GlStats {
@@ -197,13 +336,18 @@ impl GlStats {
             accel: !env_to_boolean("LIBGL_ALWAYS_SOFTWARE", false),
             no_dri3: env_to_boolean("LIBGL_DRI3_DISABLE", false),
             no_dri2: env_to_boolean("LIBGL_DRI2_DISABLE", false),
+            driver_override: env::var("MESA_LOADER_DRIVER_OVERRIDE")
+                .ok()
+                .filter(|driver| !driver.is_empty()),
+            prime: env::var("DRI_PRIME").ok().filter(|prime| !prime.is_empty()),
+            #[cfg(feature = "glvnd")]
+            vendor_override: env::var(crate::glvnd::VENDOR_OVERRIDE_VAR)
+                .ok()
+                .filter(|vendor| !vendor.is_empty()),
         }
     }
 }
 
-const GLAPI_GET_PROC_ADDRESS: ConstCstr<'static> = const_cstr(&*b"_glapi_get_proc_address\0");
-type GlapiGetProcAddress = unsafe extern "C" fn(*const c_char) -> *mut c_void;
-
 impl<Dpy: DisplayLike> GlDisplay<Dpy>
 where
     Dpy::Connection: Connection,
@@ -234,7 +378,7 @@ where
                 let repl = self
                     .display()
                     .get_drawable_properties_immediate(drawable.into())?;
-                let propmap: HashMap<u32, u32> = repl.chunks(2).map(|kv| (kv[0], kv[1])).collect();
+                let propmap = attrib_pairs(&repl);
                 self.inner.drawable_properties.insert(drawable, propmap);
                 self.inner
                     .drawable_properties
@@ -246,42 +390,96 @@ where
         Ok(map.get(&property).copied())
     }
 
+    /// Query the server for the attributes of a context.
+    #[inline]
+    pub(crate) fn query_context(&self, context: glx::Context) -> breadx::Result<HashMap<u32, u32>> {
+        let mut dpy = self.display();
+        let tok = dpy.send_request(QueryContextRequest {
+            context,
+            ..Default::default()
+        })?;
+        let repl = dpy.resolve_request(tok)?;
+        Ok(attrib_pairs(&repl.attribs))
+    }
+
+    /// Import a context created by another client, using GLX_EXT_import_context, so that its
+    /// config, screen, API and version can be queried.
+    ///
+    /// The context lives on the server, so the imported context is always indirect. breadglx can't
+    /// bind indirect contexts, and contexts breadglx creates are direct and can't share objects with
+    /// one on the server, so binding the imported context or passing it as a share context returns
+    /// an error.
+    #[inline]
+    pub fn import_context(&self, xid: glx::Context) -> breadx::Result<GlContext<Dpy>> {
+        let attribs = self.query_context(xid)?;
+        let screen = imported_context_screen(&attribs)?;
+        let (visuals, fbconfigs) =
+            GlConfig::get_visuals_and_fbconfigs(&mut *self.display(), screen)?;
+        self.imported_context(xid, &attribs, screen, &visuals, &fbconfigs)
+    }
+
+    /// Get the default screen, which functions are looked up through when no context is current.
+    /// The screen is created the first time it's needed.
+    #[inline]
+    fn default_screen(&self) -> Option<&GlScreen<Dpy>> {
+        self.inner
+            .default_screen
+            .get_or_init(|| {
+                let index = self.display().default_screen_index();
+                match self.create_screen(index) {
+                    Ok(screen) => Some(screen),
+                    Err(e) => {
+                        log::debug!(
+                            "Unable to create default screen to look up functions: {:?}",
+                            e
+                        );
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    /// Look up a GL function in the driver of the default screen. Without a current context, this
+    /// is the only driver there is to ask.
+    #[inline]
+    fn default_screen_proc_address(&self, function: &CStr) -> Option<ProcAddress> {
+        self.default_screen()?.driver_proc_address(function)
+    }
+
     /// Get the address of the desired function, but takes a C String.
     #[inline]
     pub fn get_proc_address_cstr(&self, function: &CStr) -> breadx::Result<*const c_void> {
-        let bytes = function.to_bytes();
-        if bytes[0] == b'g' && bytes[1] == b'l' && bytes[2] != b'X' {
-            // try to call _glapi_get_proc_address to get the address
-            let glapi = mesa::glapi()?;
-            let glapi_get_proc_address: GlapiGetProcAddress =
-                unsafe { glapi.function(&*GLAPI_GET_PROC_ADDRESS) }
-                    .expect("_glapi_proc not present");
-            let mut f = unsafe { glapi_get_proc_address(function.as_ptr()) };
-
-            // if that failed, get the current context (if possible)
-            if f.is_null() {
-                if let Some(ref ctx) = GlContext::<Dpy>::get()
-                    .as_ref()
-                    .and_then(|m| promote_anyarc_ref::<Dpy>(m))
-                {
-                    f = match ctx.get_proc_address(function) {
-                        Some(p) => p.into_inner().as_ptr(),
-                        None => ptr::null_mut(),
-                    };
-                }
-            }
+        if EntryPoint::classify(function)? == EntryPoint::Glx {
+            return glx_proc_address(self, function, proc_address::get_proc_address::<Dpy>);
+        }
 
-            if f.is_null() {
-                Err(breadx::BreadError::Msg(format!(
-                    "Unable to find OpenGL function: {:?}",
-                    function
-                )))
-            } else {
-                Ok(f as *const _)
+        // on libglvnd systems, the vendor library driving the screen knows the right address
+        #[cfg(feature = "glvnd")]
+        if let Some(f) = self.vendor_proc_address(function) {
+            return proc_address_result(function, Some(f));
+        }
+
+        // try to call _glapi_get_proc_address to get the address
+        let mut f = match mesa::glapi() {
+            Ok(glapi) => glapi_proc_address(glapi, function),
+            Err(e) => {
+                log::debug!("Unable to load glapi, falling back to the context: {:?}", e);
+                None
             }
-        } else {
-            Err(breadx::BreadError::StaticMsg("Invalid GL function name"))
+        };
+
+        // if that failed, ask the current context's driver, or the default screen's driver if no
+        // context is current
+        if f.is_none() {
+            let ctx = GlContext::<Dpy>::get();
+            f = match ctx.as_ref().and_then(|m| promote_anyarc_ref::<Dpy>(m)) {
+                Some(ctx) => ctx.get_proc_address(function),
+                None => self.default_screen_proc_address(function),
+            };
         }
+
+        proc_address_result(function, f)
     }
 
     /// Get the address of the desired function.
@@ -289,14 +487,18 @@ where
     pub fn get_proc_address(&self, function: &str) -> breadx::Result<*const c_void> {
         let function = CString::new(function)
             .map_err(|_| breadx::BreadError::StaticMsg("string has a zero?"))?;
-        self.get_proc_address_cstr(&*function)
+        self.get_proc_address_cstr(&function)
     }
 
+    /// Create a new `GlDisplay` from a display connection, using the default options. See
+    /// `GlDisplayBuilder` for more options.
     #[inline]
-    pub fn new(mut dpy: Dpy) -> breadx::Result<Self> {
-        // create the basic display
-        let stats = GlStats::get();
+    pub fn new(dpy: Dpy) -> breadx::Result<Self> {
+        GlDisplayBuilder::new().build(dpy)
+    }
 
+    #[inline]
+    fn from_stats(mut dpy: Dpy, stats: GlStats) -> breadx::Result<Self> {
         // get the major and minor version
         let (major_version, minor_version) = dpy.display_mut().query_glx_version_immediate(1, 1)?;
         if major_version != 1 || minor_version < 1 {
@@ -312,7 +514,11 @@ where
         if stats.direct && stats.accel {
             #[cfg(feature = "dri3")]
             if !stats.no_dri3 {
-                context = match dri::dri3::Dri3Display::new(dpy.display_mut()) {
+                context = match dri::dri3::Dri3Display::new(
+                    dpy.display_mut(),
+                    stats.driver_override.clone(),
+                    stats.prime.clone(),
+                ) {
                     Ok(ctx) => Some(ctx.into()),
                     Err(e) => {
                         log::error!("Unable to create DRI3 context: {:?}", e);
@@ -337,6 +543,8 @@ where
             log::info!("Skipping DRI3/DRI2 Initialization");
         }
 
+        // without direct rendering, configs can still be queried and contexts imported, but
+        // creating contexts will fail
         let context = match context {
             Some(context) => context,
             None => indirect::IndirectDisplay::new(dpy.display_mut())?.into(),
@@ -351,6 +559,11 @@ where
             accel: stats.accel,
             context,
             drawable_properties: DashMap::new(),
+            default_screen: OnceCell::new(),
+            #[cfg(feature = "glvnd")]
+            vendors: DashMap::new(),
+            #[cfg(feature = "glvnd")]
+            vendor_override: stats.vendor_override,
             major_version,
             minor_version,
         };
@@ -361,17 +574,51 @@ where
     }
 }
 
+#[cfg(test)]
+impl<Dpy> GlDisplay<Dpy> {
+    /// A display that renders indirectly, without asking the server anything.
+    #[inline]
+    pub(crate) fn indirect_placeholder(dpy: Dpy) -> Self {
+        let this = InnerGlDisplay {
+            #[cfg(not(feature = "async"))]
+            display: sync::Mutex::new(dpy),
+            #[cfg(feature = "async")]
+            display: async_lock::Mutex::new(dpy),
+            direct: false,
+            accel: false,
+            context: indirect::IndirectDisplay::placeholder().into(),
+            drawable_properties: DashMap::new(),
+            default_screen: OnceCell::new(),
+            #[cfg(feature = "glvnd")]
+            vendors: DashMap::new(),
+            #[cfg(feature = "glvnd")]
+            vendor_override: None,
+            major_version: 1,
+            minor_version: 4,
+        };
+
+        Self {
+            inner: Arc::new(this),
+        }
+    }
+}
+
 #[cfg(feature = "async")]
 impl<Dpy: DisplayLike> GlDisplay<Dpy>
 where
     Dpy::Connection: AsyncConnection + Send,
 {
+    /// Create a screen, async redox.
     #[inline]
-    pub async fn create_screen_async(&mut self, index: usize) -> breadx::Result<GlScreen<Dpy>> {
-        self.inner
+    pub async fn create_screen_async(&self, index: usize) -> breadx::Result<GlScreen<Dpy>> {
+        log::trace!("Creating screen...");
+        let scr = self
+            .inner
             .context
             .create_screen_async(&mut *self.display_async().await, index)
-            .await
+            .await?;
+        log::trace!("Created screen.");
+        Ok(scr)
     }
 
     /// Load a drawable's property, async redox.
@@ -394,7 +641,7 @@ where
 
                 self.inner
                     .drawable_properties
-                    .insert(drawable, repl.chunks(2).map(|kv| (kv[0], kv[1])).collect());
+                    .insert(drawable, attrib_pairs(&repl));
                 self.inner
                     .drawable_properties
                     .get(&drawable)
@@ -405,11 +652,116 @@ where
         Ok(map.get(&property).copied())
     }
 
+    /// Query the server for the attributes of a context, async redox.
+    #[inline]
+    pub(crate) async fn query_context_async(
+        &self,
+        context: glx::Context,
+    ) -> breadx::Result<HashMap<u32, u32>> {
+        let mut dpy = self.display_async().await;
+        let tok = dpy
+            .send_request_async(QueryContextRequest {
+                context,
+                ..Default::default()
+            })
+            .await?;
+        let repl = dpy.resolve_request_async(tok).await?;
+        Ok(attrib_pairs(&repl.attribs))
+    }
+
+    /// Import a context created by another client, async redox.
+    #[inline]
+    pub async fn import_context_async(&self, xid: glx::Context) -> breadx::Result<GlContext<Dpy>> {
+        let attribs = self.query_context_async(xid).await?;
+        let screen = imported_context_screen(&attribs)?;
+        let (visuals, fbconfigs) =
+            GlConfig::get_visuals_and_fbconfigs_async(&mut *self.display_async().await, screen)
+                .await?;
+        self.imported_context(xid, &attribs, screen, &visuals, &fbconfigs)
+    }
+
+    /// Get the default screen, async redox.
+    #[inline]
+    async fn default_screen_async(&self) -> Option<&GlScreen<Dpy>> {
+        if self.inner.default_screen.get().is_none() {
+            let index = self.display_async().await.default_screen_index();
+            let screen = match self.create_screen_async(index).await {
+                Ok(screen) => Some(screen),
+                Err(e) => {
+                    log::debug!(
+                        "Unable to create default screen to look up functions: {:?}",
+                        e
+                    );
+                    None
+                }
+            };
+
+            // if another task created it first, use theirs instead
+            let _ = self.inner.default_screen.set(screen);
+        }
+
+        self.inner.default_screen.get()?.as_ref()
+    }
+
+    /// Look up a GL function in the driver of the default screen, async redox.
+    #[inline]
+    async fn default_screen_proc_address_async(&self, function: &CStr) -> Option<ProcAddress> {
+        self.default_screen_async()
+            .await?
+            .driver_proc_address(function)
+    }
+
+    /// Get the address of the desired function, but takes a C String, async redox.
     #[inline]
-    pub async fn new_async(mut dpy: Dpy) -> breadx::Result<Self> {
-        // create the basic display
-        let stats = GlStats::get();
+    pub async fn get_proc_address_cstr_async(
+        &self,
+        function: &CStr,
+    ) -> breadx::Result<*const c_void> {
+        if EntryPoint::classify(function)? == EntryPoint::Glx {
+            return glx_proc_address(self, function, proc_address::get_proc_address_async::<Dpy>);
+        }
+
+        // addresses are kept as ProcAddress until the end, so no raw pointer is held across an await
+        #[cfg(feature = "glvnd")]
+        if let Some(f) = self.vendor_proc_address_async(function).await {
+            return proc_address_result(function, Some(f));
+        }
+
+        let mut f = match mesa::glapi_async().await {
+            Ok(glapi) => glapi_proc_address(glapi, function),
+            Err(e) => {
+                log::debug!("Unable to load glapi, falling back to the context: {:?}", e);
+                None
+            }
+        };
 
+        if f.is_none() {
+            let ctx = GlContext::<Dpy>::get_async().await;
+            f = match ctx.as_ref().and_then(|m| promote_anyarc_ref::<Dpy>(m)) {
+                Some(ctx) => ctx.get_proc_address_async(function).await,
+                None => self.default_screen_proc_address_async(function).await,
+            };
+        }
+
+        proc_address_result(function, f)
+    }
+
+    /// Get the address of the desired function, async redox.
+    #[inline]
+    pub async fn get_proc_address_async(&self, function: &str) -> breadx::Result<*const c_void> {
+        let function = CString::new(function)
+            .map_err(|_| breadx::BreadError::StaticMsg("string has a zero?"))?;
+        self.get_proc_address_cstr_async(&function).await
+    }
+
+    /// Create a new `GlDisplay` from a display connection, using the default options, async redox.
+    #[inline]
+    pub async fn new_async(dpy: Dpy) -> breadx::Result<Self> {
+        GlDisplayBuilder::new().build_async(dpy).await
+    }
+
+    #[inline]
+    async fn from_stats_async(mut dpy: Dpy, stats: GlStats) -> breadx::Result<Self> {
         let (major_version, minor_version) = dpy
             .display_mut()
             .query_glx_version_immediate_async(1, 1)
@@ -427,18 +779,30 @@ where
         if stats.direct && stats.accel {
             #[cfg(feature = "dri3")]
             if !stats.no_dri3 {
-                context = dri::dri3::Dri3Display::new_async(dpy.display_mut())
-                    .await
-                    .ok()
-                    .map(|x| x.into());
+                context = match dri::dri3::Dri3Display::new_async(
+                    dpy.display_mut(),
+                    stats.driver_override.clone(),
+                    stats.prime.clone(),
+                )
+                .await
+                {
+                    Ok(ctx) => Some(ctx.into()),
+                    Err(e) => {
+                        log::error!("Unable to create DRI3 context: {:?}", e);
+                        None
+                    }
+                };
             }
 
             // try again with dri2 if we can't do dri3
             if context.is_none() && !stats.no_dri2 {
-                context = dri::dri2::Dri2Display::new_async(dpy.display_mut())
-                    .await
-                    .ok()
-                    .map(|x| x.into());
+                context = match dri::dri2::Dri2Display::new_async(dpy.display_mut()).await {
+                    Ok(ctx) => Some(ctx.into()),
+                    Err(e) => {
+                        log::error!("Unable to create DRI2 context: {:?}", e);
+                        None
+                    }
+                };
             }
         }
 
@@ -455,6 +819,11 @@ where
             accel: stats.accel,
             context,
             drawable_properties: DashMap::new(),
+            default_screen: OnceCell::new(),
+            #[cfg(feature = "glvnd")]
+            vendors: DashMap::new(),
+            #[cfg(feature = "glvnd")]
+            vendor_override: stats.vendor_override,
             major_version,
             minor_version,
         };
@@ -464,3 +833,66 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::COLOR_INDEX_TYPE,
+        context::{GlApi, GlRenderType},
+    };
+
+    #[test]
+    fn odd_attrib_lists() {
+        let attribs = attrib_pairs(&[1, 2, 3, 4, 5]);
+        assert_eq!(attribs.len(), 2);
+        assert_eq!(attribs.get(&1), Some(&2));
+        assert_eq!(attribs.get(&3), Some(&4));
+        assert_eq!(attribs.get(&5), None);
+    }
+
+    #[test]
+    fn imported_context_versions() {
+        let info = |attribs: &[u32]| {
+            ContextInfo::from_rules(&imported_context_rules(&attrib_pairs(attribs))).unwrap()
+        };
+
+        // servers without GLX_ARB_create_context only report the render type
+        let legacy = info(&[RENDER_TYPE as _, RGBA_TYPE as _]);
+        assert_eq!(legacy.api, GlApi::OpenGl);
+        assert_eq!((legacy.major, legacy.minor), (1, 0));
+        assert_eq!(legacy.render_type, GlRenderType::Rgba);
+
+        let core = info(&[
+            MAJOR_VERSION_ARB as _,
+            4,
+            MINOR_VERSION_ARB as _,
+            5,
+            PROFILE_MASK_ARB as _,
+            CORE_PROFILE_BIT_ARB as _,
+        ]);
+        assert_eq!(core.api, GlApi::OpenGlCore);
+        assert_eq!((core.major, core.minor), (4, 5));
+
+        let compat = info(&[
+            MAJOR_VERSION_ARB as _,
+            4,
+            PROFILE_MASK_ARB as _,
+            COMPAT_PROFILE_BIT_ARB as _,
+        ]);
+        assert_eq!(compat.api, GlApi::OpenGl);
+
+        let es = info(&[
+            MAJOR_VERSION_ARB as _,
+            3,
+            MINOR_VERSION_ARB as _,
+            1,
+            PROFILE_MASK_ARB as _,
+            ES_PROFILE_BIT_ARB as _,
+        ]);
+        assert_eq!(es.api, GlApi::Gles3);
+
+        let color_index = info(&[RENDER_TYPE as _, COLOR_INDEX_TYPE as _]);
+        assert_eq!(color_index.render_type, GlRenderType::ColorIndex);
+    }
+}
@@ -0,0 +1,228 @@
+// MIT/Apache2 License
+
+//! Resolving function names to addresses. GL functions are looked up in glapi's dispatch table,
+//! then in the current context's driver. GLX functions can't be dispatched through the driver, so
+//! the ones breadglx implements itself are handed out directly. Both kinds of names are checked
+//! against the Khronos registry first. The `glXGetProcAddress` handed out to C code looks functions
+//! up through the display it came from.
+
+use super::{DisplayLike, GlDisplay, InnerGlDisplay};
+use crate::{
+    auto::gl_registry::{GLX_ENTRY_POINTS, GL_ENTRY_POINTS},
+    context::ProcAddress,
+    mesa::GlapiFunctions,
+};
+use breadx::display::Connection;
+use once_cell::sync::OnceCell;
+use std::{
+    any::Any,
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr::{self, NonNull},
+    sync::{Arc, Mutex, Weak},
+};
+
+#[cfg(feature = "async")]
+use breadx::display::AsyncConnection;
+#[cfg(feature = "async")]
+use futures_lite::future;
+
+/// The API an entry point belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum EntryPoint {
+    /// An OpenGL or OpenGL ES function, like `glClear`.
+    Gl,
+    /// A GLX function, like `glXSwapBuffers`.
+    Glx,
+}
+
+impl EntryPoint {
+    /// Figure out which API a function belongs to, according to the Khronos registry. Fails if the
+    /// function isn't in the registry.
+    #[inline]
+    pub(crate) fn classify(function: &CStr) -> breadx::Result<EntryPoint> {
+        let name = function
+            .to_str()
+            .map_err(|_| breadx::BreadError::StaticMsg("Invalid GL function name"))?;
+
+        // OpenGL and OpenGL ES entry points live in the same dispatch table, so GLES functions
+        // (including the OES-suffixed ones) are resolved the same way desktop ones are
+        if GL_ENTRY_POINTS.binary_search(&name).is_ok() {
+            Ok(EntryPoint::Gl)
+        } else if GLX_ENTRY_POINTS.binary_search(&name).is_ok() {
+            Ok(EntryPoint::Glx)
+        } else {
+            Err(breadx::BreadError::Msg(format!(
+                "{} is not an OpenGL or GLX function",
+                name
+            )))
+        }
+    }
+}
+
+/// Look up a function in glapi's dispatch table.
+#[inline]
+pub(crate) fn glapi_proc_address(glapi: &GlapiFunctions, function: &CStr) -> Option<ProcAddress> {
+    NonNull::new(unsafe { (glapi.get_proc_address)(function.as_ptr()) }).map(ProcAddress::from)
+}
+
+/// The signature of `glXGetProcAddress`.
+pub(crate) type GetProcAddress = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+
+/// The display that most recently handed out `glXGetProcAddress`. C callers can't say which display
+/// they mean, so functions are looked up through this one.
+static PROC_ADDRESS_DISPLAY: OnceCell<Mutex<Option<Weak<dyn Any + Send + Sync>>>> = OnceCell::new();
+
+#[inline]
+fn proc_address_display_slot() -> &'static Mutex<Option<Weak<dyn Any + Send + Sync>>> {
+    PROC_ADDRESS_DISPLAY.get_or_init(|| Mutex::new(None))
+}
+
+/// Get the display that `glXGetProcAddress` looks functions up through, if it's still alive.
+#[inline]
+fn proc_address_display<Dpy: DisplayLike>() -> Option<GlDisplay<Dpy>> {
+    let display = proc_address_display_slot()
+        .lock()
+        .ok()?
+        .as_ref()?
+        .upgrade()?;
+    let inner = display.downcast::<InnerGlDisplay<Dpy>>().ok()?;
+    Some(GlDisplay { inner })
+}
+
+/// Get breadglx's own implementation of a GLX function. Most of GLX is exposed through the
+/// `GlDisplay` and `GlContext` APIs instead, since it needs to know which display it's talking to.
+/// `get_proc_address` is the `glXGetProcAddress` to hand out, which looks functions up through
+/// `display`.
+#[inline]
+pub(crate) fn glx_proc_address<Dpy: DisplayLike>(
+    display: &GlDisplay<Dpy>,
+    function: &CStr,
+    get_proc_address: GetProcAddress,
+) -> breadx::Result<*const c_void> {
+    match function.to_bytes() {
+        b"glXGetProcAddress" | b"glXGetProcAddressARB" => {
+            let inner: Weak<dyn Any + Send + Sync> = Arc::downgrade(&display.inner) as _;
+            if let Ok(mut slot) = proc_address_display_slot().lock() {
+                *slot = Some(inner);
+            }
+            Ok(get_proc_address as *const c_void)
+        }
+        _ => Err(breadx::BreadError::Msg(format!(
+            "breadglx does not implement {:?}, use the GlDisplay API instead",
+            function
+        ))),
+    }
+}
+
+/// Resolve a function passed to `glXGetProcAddress` through the display that handed it out.
+#[inline]
+unsafe fn resolve_through_display<Dpy: DisplayLike>(
+    name: *const c_char,
+    resolve: impl FnOnce(&GlDisplay<Dpy>, &CStr) -> breadx::Result<*const c_void>,
+) -> *mut c_void {
+    if name.is_null() {
+        return ptr::null_mut();
+    }
+
+    let function = unsafe { CStr::from_ptr(name) };
+    let display = match proc_address_display::<Dpy>() {
+        Some(display) => display,
+        None => {
+            log::error!(
+                "The display that provided glXGetProcAddress is gone, unable to resolve {:?}",
+                function
+            );
+            return ptr::null_mut();
+        }
+    };
+
+    match resolve(&display, function) {
+        Ok(f) => f as *mut c_void,
+        Err(e) => {
+            log::error!("{:?}", e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Implementation of `glXGetProcAddress` for code that expects to load GL functions through it.
+/// Functions are looked up the same way `GlDisplay::get_proc_address` looks them up.
+pub(crate) unsafe extern "C" fn get_proc_address<Dpy: DisplayLike>(
+    name: *const c_char,
+) -> *mut c_void
+where
+    Dpy::Connection: Connection,
+{
+    unsafe { resolve_through_display::<Dpy>(name, |dpy, f| dpy.get_proc_address_cstr(f)) }
+}
+
+/// Implementation of `glXGetProcAddress` for displays with async connections, which blocks on
+/// `GlDisplay::get_proc_address_async`.
+#[cfg(feature = "async")]
+pub(crate) unsafe extern "C" fn get_proc_address_async<Dpy: DisplayLike>(
+    name: *const c_char,
+) -> *mut c_void
+where
+    Dpy::Connection: AsyncConnection + Send,
+{
+    unsafe {
+        resolve_through_display::<Dpy>(name, |dpy, f| {
+            future::block_on(dpy.get_proc_address_cstr_async(f))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fake_server;
+
+    #[test]
+    fn classify() {
+        let classify = |name: &[u8]| EntryPoint::classify(CStr::from_bytes_with_nul(name).unwrap());
+
+        assert_eq!(classify(b"glClear\0").unwrap(), EntryPoint::Gl);
+        // GLES extensions share the GL dispatch table
+        assert_eq!(
+            classify(b"glEGLImageTargetTexture2DOES\0").unwrap(),
+            EntryPoint::Gl
+        );
+        assert_eq!(classify(b"glXSwapBuffers\0").unwrap(), EntryPoint::Glx);
+        assert_eq!(
+            classify(b"glXGetProcAddressARB\0").unwrap(),
+            EntryPoint::Glx
+        );
+
+        assert!(classify(b"glNotAFunction\0").is_err());
+        assert!(classify(b"glclear\0").is_err());
+        assert!(classify(b"\0").is_err());
+        assert!(classify(b"gl\xffClear\0").is_err());
+    }
+
+    #[test]
+    fn exported_get_proc_address() {
+        let (dpy, server) = fake_server();
+        let display = GlDisplay::indirect_placeholder(dpy);
+
+        let get_proc_address = display.get_proc_address("glXGetProcAddressARB").unwrap();
+        assert!(!get_proc_address.is_null());
+        let get_proc_address: GetProcAddress = unsafe { std::mem::transmute(get_proc_address) };
+
+        // names go through the display that handed out the function
+        let resolve = |name: &[u8]| unsafe { get_proc_address(name.as_ptr() as *const c_char) };
+        assert_eq!(
+            resolve(b"glXGetProcAddress\0") as *const c_void,
+            get_proc_address as *const c_void
+        );
+        assert!(resolve(b"glNotAFunction\0").is_null());
+        assert!(resolve(b"glXSwapBuffers\0").is_null());
+        assert!(unsafe { get_proc_address(ptr::null()) }.is_null());
+
+        // once the display is gone, nothing can be looked up
+        drop(display);
+        assert!(resolve(b"glXGetProcAddress\0").is_null());
+
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+// MIT/Apache2 License
+
+use super::{DisplayLike, GlDisplay};
+use crate::{
+    context::{promote_anyarc_ref, Backend, GlContext, ProcAddress},
+    glvnd::{self, GlxVendor, GLX_VENDOR_NAMES_EXT},
+};
+use breadx::{
+    auto::glx::QueryServerStringRequest,
+    display::{Connection, Display},
+};
+use std::ffi::CStr;
+
+#[cfg(feature = "async")]
+use breadx::display::AsyncConnection;
+
+/// Only use a vendor's functions with the backends they work with.
+#[inline]
+fn supported_vendor(vendor: &'static GlxVendor, backend: Backend) -> Option<&'static GlxVendor> {
+    if vendor.supports_backend(backend) {
+        Some(vendor)
+    } else {
+        log::debug!(
+            "Not using GLX vendor {} for {:?} contexts",
+            vendor.name(),
+            backend
+        );
+        None
+    }
+}
+
+impl<Dpy> GlDisplay<Dpy> {
+    /// Remember the vendor chosen for a screen.
+    #[inline]
+    fn cache_vendor(&self, screen: usize, vendor: Option<&'static GlxVendor>) {
+        if let Some(vendor) = vendor {
+            log::info!("Using GLX vendor {} for screen {}", vendor.name(), screen);
+        }
+        self.inner.vendors.insert(screen, vendor);
+    }
+}
+
+impl<Dpy: DisplayLike> GlDisplay<Dpy>
+where
+    Dpy::Connection: Connection,
+{
+    /// Ask the server which GLX vendors drive a screen, using GLX_EXT_libglvnd. Servers without the
+    /// extension don't name any.
+    #[inline]
+    fn query_vendor_names(dpy: &mut Display<Dpy::Connection>, screen: usize) -> String {
+        let res = dpy
+            .send_request(QueryServerStringRequest {
+                screen: screen as _,
+                name: GLX_VENDOR_NAMES_EXT,
+                ..Default::default()
+            })
+            .and_then(|tok| dpy.resolve_request(tok));
+
+        match res {
+            Ok(repl) => repl.string,
+            Err(e) => {
+                log::debug!("Unable to query GLX vendors for screen {}: {:?}", screen, e);
+                String::new()
+            }
+        }
+    }
+
+    /// Get the GLX vendor library that drives a screen. `__GLX_VENDOR_LIBRARY_NAME` overrides the
+    /// vendors the server asks for.
+    #[inline]
+    pub(crate) fn screen_vendor(&self, screen: usize) -> Option<&'static GlxVendor> {
+        if let Some(vendor) = self.inner.vendors.get(&screen) {
+            return *vendor;
+        }
+
+        let server_names = match self.inner.vendor_override {
+            Some(_) => String::new(),
+            None => Self::query_vendor_names(&mut self.display(), screen),
+        };
+        let names = glvnd::vendor_names(self.inner.vendor_override.as_deref(), &server_names);
+
+        let vendor = glvnd::choose_vendor(&names);
+        self.cache_vendor(screen, vendor);
+        vendor
+    }
+
+    /// Look up a GL function in the vendor library of the current context's screen, or the default
+    /// screen if there is no current context. Returns `None` if the vendor's functions wouldn't
+    /// work with the context's or default screen's backend.
+    #[inline]
+    pub(crate) fn vendor_proc_address(&self, function: &CStr) -> Option<ProcAddress> {
+        let current = GlContext::<Dpy>::get()
+            .as_ref()
+            .and_then(|m| promote_anyarc_ref::<Dpy>(m))
+            .map(|ctx| (ctx.screen_index(), ctx.share_group().backend()));
+        let (screen, backend) = match current {
+            Some(current) => current,
+            None => {
+                let screen = self.default_screen()?;
+                (screen.screen_index(), screen.backend())
+            }
+        };
+
+        supported_vendor(self.screen_vendor(screen)?, backend)?.proc_address(function)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Dpy: DisplayLike> GlDisplay<Dpy>
+where
+    Dpy::Connection: AsyncConnection + Send,
+{
+    /// Ask the server which GLX vendors drive a screen, async redox.
+    #[inline]
+    async fn query_vendor_names_async(dpy: &mut Display<Dpy::Connection>, screen: usize) -> String {
+        let res = match dpy
+            .send_request_async(QueryServerStringRequest {
+                screen: screen as _,
+                name: GLX_VENDOR_NAMES_EXT,
+                ..Default::default()
+            })
+            .await
+        {
+            Ok(tok) => dpy.resolve_request_async(tok).await,
+            Err(e) => Err(e),
+        };
+
+        match res {
+            Ok(repl) => repl.string,
+            Err(e) => {
+                log::debug!("Unable to query GLX vendors for screen {}: {:?}", screen, e);
+                String::new()
+            }
+        }
+    }
+
+    /// Get the GLX vendor library that drives a screen, async redox.
+    #[inline]
+    pub(crate) async fn screen_vendor_async(&self, screen: usize) -> Option<&'static GlxVendor> {
+        if let Some(vendor) = self.inner.vendors.get(&screen) {
+            return *vendor;
+        }
+
+        let server_names = match self.inner.vendor_override {
+            Some(_) => String::new(),
+            None => Self::query_vendor_names_async(&mut *self.display_async().await, screen).await,
+        };
+        let names = glvnd::vendor_names(self.inner.vendor_override.as_deref(), &server_names);
+
+        let vendor = blocking::unblock(move || glvnd::choose_vendor(&names)).await;
+        self.cache_vendor(screen, vendor);
+        vendor
+    }
+
+    /// Look up a GL function in the vendor library of the current context's screen, async redox.
+    #[inline]
+    pub(crate) async fn vendor_proc_address_async(&self, function: &CStr) -> Option<ProcAddress> {
+        let current = GlContext::<Dpy>::get_async()
+            .await
+            .as_ref()
+            .and_then(|m| promote_anyarc_ref::<Dpy>(m))
+            .map(|ctx| (ctx.screen_index(), ctx.share_group().backend()));
+        let (screen, backend) = match current {
+            Some(current) => current,
+            None => {
+                let screen = self.default_screen_async().await?;
+                (screen.screen_index(), screen.backend())
+            }
+        };
+
+        supported_vendor(self.screen_vendor_async(screen).await?, backend)?.proc_address(function)
+    }
+}
@@ -1,19 +1,17 @@
 // MIT/Apache2 License
 
-use dashmap::DashMap;
-use libloading::{Library, Symbol};
+use libloading::Library;
 use std::{
-    ffi::{c_void, CStr, OsStr},
+    ffi::{c_void, OsStr},
     fmt, mem,
-    ptr::NonNull,
+    ops::Deref,
+    path::Path,
 };
 
 /// A pointer to a dynamically loaded library.
 #[derive(Debug)]
 pub struct Dll {
     lib: Library,
-    // TODO: probably not an efficient way of doing this. cache locality, et cetera
-    funcs: DashMap<Box<CStr>, NonNull<c_void>>,
 }
 
 unsafe impl Send for Dll {}
@@ -25,38 +23,265 @@ impl Dll {
     pub fn load<A: AsRef<OsStr>>(dbg_libname: &'static str, paths: &[A]) -> breadx::Result<Self> {
         let lib = match paths
             .iter()
-            .find_map(|path| unsafe { Library::new(path) }.ok())
-        {
+            .find_map(|path| match unsafe { Library::new(path) } {
+                Ok(lib) => {
+                    log::debug!(
+                        "Loaded {} library from {}",
+                        dbg_libname,
+                        Path::new(path).display()
+                    );
+                    Some(lib)
+                }
+                Err(e) => {
+                    log::trace!("Unable to load {}: {}", Path::new(path).display(), e);
+                    None
+                }
+            }) {
             Some(lib) => lib,
-            None => return Err(breadx::BreadError::LoadLibraryFailed(dbg_libname)),
+            None => {
+                log::error!(
+                    "Unable to load {} library, tried: {}",
+                    dbg_libname,
+                    paths
+                        .iter()
+                        .map(|path| Path::new(path).display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                return Err(breadx::BreadError::LoadLibraryFailed(dbg_libname));
+            }
         };
 
-        Ok(Self {
-            lib,
-            funcs: DashMap::new(),
-        })
+        Ok(Self { lib })
     }
 
-    /// Load a function.
+    /// Look up a symbol in the library. `name` must be null-terminated.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the actual type of the symbol.
     #[inline]
-    pub unsafe fn function<T>(&self, name: &CStr) -> Option<T> {
-        if mem::size_of::<T>() != mem::size_of::<NonNull<c_void>>() {
-            panic!("Object is not the size of a pointer");
+    pub unsafe fn symbol<T: Symbol>(&self, name: &[u8]) -> Option<T> {
+        let sym = unsafe { self.lib.get::<*mut c_void>(name) }.ok()?;
+        let sym: *mut c_void = *sym;
+        if sym.is_null() {
+            None
+        } else {
+            // SAFETY: Symbol is only implemented for pointer-sized types
+            Some(unsafe { mem::transmute_copy::<*mut c_void, T>(&sym) })
         }
+    }
+}
 
-        match self.funcs.get(name) {
-            Some(func) => Some(mem::transmute_copy::<_, T>(&*func)),
-            None => {
-                // load symbol from library
-                let sym: NonNull<c_void> = unsafe {
-                    self.lib
-                        .get::<'_, Option<NonNull<c_void>>>(name.to_bytes_with_nul())
-                }
-                .ok()
-                .and_then(|t| *t)?;
-                self.funcs.insert(name.into(), sym);
-                Some(mem::transmute_copy::<_, T>(&sym))
+/// A type that a symbol can be loaded as. This is only implemented for function pointers and raw
+/// pointers, so a symbol can never be loaded into something that isn't the size of a pointer.
+///
+/// # Safety
+///
+/// Implementors must have the same size and layout as a pointer, and any non-null pointer must be a
+/// valid value of the type, since `Dll::symbol` transmutes the symbol's address into it.
+pub unsafe trait Symbol: Copy {}
+
+unsafe impl<T> Symbol for *const T {}
+unsafe impl<T> Symbol for *mut T {}
+
+macro_rules! impl_symbol_for_fn {
+    ($($arg: ident),*) => {
+        unsafe impl<R, $($arg),*> Symbol for unsafe extern "C" fn($($arg),*) -> R {}
+    };
+}
+
+impl_symbol_for_fn!();
+impl_symbol_for_fn!(A);
+impl_symbol_for_fn!(A, B);
+impl_symbol_for_fn!(A, B, C);
+impl_symbol_for_fn!(A, B, C, D);
+impl_symbol_for_fn!(A, B, C, D, E);
+impl_symbol_for_fn!(A, B, C, D, E, F);
+
+/// A table of functions that is resolved from a library as soon as the library is loaded.
+pub trait FunctionTable: Sized {
+    /// Resolve the functions in the table. If any required symbols are missing, their names are
+    /// pushed onto `missing` and `None` is returned.
+    fn resolve(dll: &Dll, missing: &mut Vec<&'static str>) -> Option<Self>;
+}
+
+/// Declare a struct containing a library's functions, and implement `FunctionTable` for it. Functions
+/// in the `optional` block are stored as `Option`s, and don't cause resolution to fail if they're
+/// missing.
+macro_rules! function_table {
+    (
+        $(#[$meta: meta])*
+        $vis: vis struct $name: ident {
+            $($(#[$fmeta: meta])* $field: ident: $fty: ty = $sym: literal,)*
+        }
+        $(optional {
+            $($(#[$ometa: meta])* $ofield: ident: $oty: ty = $osym: literal,)*
+        })?
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$fmeta])* pub $field: $fty,)*
+            $($($(#[$ometa])* pub $ofield: Option<$oty>,)*)?
+        }
+
+        impl $crate::dll::FunctionTable for $name {
+            #[inline]
+            fn resolve(
+                dll: &$crate::dll::Dll,
+                missing: &mut Vec<&'static str>,
+            ) -> Option<Self> {
+                $(
+                    let $field = unsafe { dll.symbol::<$fty>(concat!($sym, "\0").as_bytes()) };
+                    if $field.is_none() {
+                        missing.push($sym);
+                    }
+                )*
+                $($(
+                    let $ofield = unsafe { dll.symbol::<$oty>(concat!($osym, "\0").as_bytes()) };
+                )*)?
+
+                Some(Self {
+                    $($field: $field?,)*
+                    $($($ofield,)*)?
+                })
             }
         }
+    };
+}
+
+/// A library, along with the table of functions resolved from it. The library is kept loaded for
+/// as long as the table is around.
+pub struct LoadedDll<T> {
+    functions: T,
+    dll: Dll,
+}
+
+impl<T: FunctionTable> LoadedDll<T> {
+    /// Load a library and resolve its function table. Fails if any required functions are missing,
+    /// listing all of them.
+    #[inline]
+    pub fn load<A: AsRef<OsStr>>(dbg_libname: &'static str, paths: &[A]) -> breadx::Result<Self> {
+        let dll = Dll::load(dbg_libname, paths)?;
+
+        let mut missing = vec![];
+        match T::resolve(&dll, &mut missing) {
+            Some(functions) => Ok(Self { functions, dll }),
+            None => Err(breadx::BreadError::Msg(format!(
+                "{} library is missing required symbols: {}",
+                dbg_libname,
+                missing.join(", ")
+            ))),
+        }
+    }
+}
+
+impl<T> Deref for LoadedDll<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.functions
+    }
+}
+
+impl<T> fmt::Debug for LoadedDll<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.dll, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mesa::{GlapiFunctions, XshmfenceFunctions},
+        util::TempDir,
+    };
+    use std::{fs, os::raw::c_char};
+
+    #[cfg(feature = "dri")]
+    use crate::dri::load::DrmFunctions;
+
+    function_table! {
+        struct LibcFunctions {
+            strlen: unsafe extern "C" fn(*const c_char) -> usize = "strlen",
+        }
+        optional {
+            missing: unsafe extern "C" fn() = "breadglx_no_such_function",
+        }
+    }
+
+    function_table! {
+        // never resolves, so its fields are never read
+        #[allow(dead_code)]
+        struct MissingFunctions {
+            strlen: unsafe extern "C" fn(*const c_char) -> usize = "strlen",
+            missing: unsafe extern "C" fn() = "breadglx_no_such_function",
+        }
+    }
+
+    const LIBC: &str = "libc.so.6";
+
+    /// A library directory with files named after the libraries breadglx loads, none of which are
+    /// actually libraries.
+    fn fake_lib_dir() -> TempDir {
+        let dir = TempDir::new("breadglx-libs");
+        for name in &["libdrm.so.2", "libxshmfence.so.1", "libglapi.so.0"] {
+            fs::write(dir.path().join(name), b"not a shared library").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn missing_library() {
+        let dir = TempDir::new("breadglx-libs");
+        let res =
+            LoadedDll::<GlapiFunctions>::load("LibGLAPI", &[dir.path().join("libglapi.so.0")]);
+        assert!(matches!(
+            res,
+            Err(breadx::BreadError::LoadLibraryFailed("LibGLAPI"))
+        ));
+    }
+
+    #[test]
+    fn invalid_library() {
+        let dir = fake_lib_dir();
+        let path = |name: &str| dir.path().join(name);
+
+        #[cfg(feature = "dri")]
+        assert!(LoadedDll::<DrmFunctions>::load("LibDRM", &[path("libdrm.so.2")]).is_err());
+        assert!(LoadedDll::<XshmfenceFunctions>::load(
+            "LibXShmFence",
+            &[path("libxshmfence.so.1")]
+        )
+        .is_err());
+        assert!(LoadedDll::<GlapiFunctions>::load("LibGLAPI", &[path("libglapi.so.0")]).is_err());
+    }
+
+    #[test]
+    fn later_paths_are_tried() {
+        let dir = fake_lib_dir();
+        let paths = [
+            dir.path().join("libglapi.so.0").into_os_string(),
+            LIBC.into(),
+        ];
+        assert!(LoadedDll::<LibcFunctions>::load("libc", &paths).is_ok());
+    }
+
+    #[test]
+    fn missing_symbols() {
+        let libc = LoadedDll::<LibcFunctions>::load("libc", &[LIBC]).unwrap();
+        assert!(libc.missing.is_none());
+        assert_eq!(
+            unsafe { (libc.strlen)(b"breadglx\0".as_ptr() as *const c_char) },
+            8
+        );
+
+        match LoadedDll::<MissingFunctions>::load("libc", &[LIBC]) {
+            Err(breadx::BreadError::Msg(msg)) => assert!(msg.contains("breadglx_no_such_function")),
+            res => panic!("Expected missing symbols, got {:?}", res.map(|_| ())),
+        }
     }
 }
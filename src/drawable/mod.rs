@@ -1,3 +1,211 @@
 // MIT/Apache2 License
 
-pub struct GlDrawable {}
+use crate::{
+    config::GlConfig,
+    display::{DisplayLike, GlDisplay},
+    screen::GlVisual,
+};
+use breadx::{
+    auto::xproto::{Colormap, FreeColormapRequest},
+    display::{Connection, Display},
+    Drawable, EventMask, Window,
+};
+use std::fmt;
+
+#[cfg(feature = "async")]
+use crate::offload;
+#[cfg(feature = "async")]
+use breadx::display::AsyncConnection;
+
+/// A window created by `GlScreen::create_window`, along with the resources created for it. The
+/// colormap created for the window is freed when this is dropped. The window itself is not
+/// destroyed, since the application may have already destroyed it (or still be using it).
+pub struct GlDrawable<Dpy> {
+    display: GlDisplay<Dpy>,
+    window: Window,
+    colormap: Colormap,
+    visual: GlVisual,
+    dropper: fn(&mut GlDrawable<Dpy>),
+}
+
+impl<Dpy> fmt::Debug for GlDrawable<Dpy> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlDrawable")
+            .field("window", &self.window)
+            .field("colormap", &self.colormap)
+            .field("visual", &self.visual)
+            .finish()
+    }
+}
+
+/// Optional properties for windows created by `GlScreen::create_window`.
+#[derive(Debug, Clone)]
+pub struct GlWindowHints {
+    /// The events that the window should receive.
+    pub event_mask: EventMask,
+    /// The title of the window.
+    pub title: Option<String>,
+    /// Ask the window manager to send a `WM_DELETE_WINDOW` client message when the window is
+    /// closed, instead of killing the connection.
+    pub delete_window_protocol: bool,
+    /// Set `_NET_WM_BYPASS_COMPOSITOR`, asking the compositor to unredirect the window while it is
+    /// fullscreen.
+    pub bypass_compositor: bool,
+    /// Map the window once it's created.
+    pub map: bool,
+}
+
+impl Default for GlWindowHints {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            event_mask: EventMask::STRUCTURE_NOTIFY | EventMask::EXPOSURE,
+            title: None,
+            delete_window_protocol: true,
+            bypass_compositor: false,
+            map: true,
+        }
+    }
+}
+
+impl<Dpy> GlDrawable<Dpy> {
+    /// The window that was created.
+    #[inline]
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// The colormap created for the window.
+    #[inline]
+    pub fn colormap(&self) -> Colormap {
+        self.colormap
+    }
+
+    /// The visual that the window was created with.
+    #[inline]
+    pub fn visual(&self) -> &GlVisual {
+        &self.visual
+    }
+
+    /// The config that contexts rendering to this window should be created with.
+    #[inline]
+    pub fn config(&self) -> &GlConfig {
+        &self.visual.config
+    }
+}
+
+impl<Dpy> From<&GlDrawable<Dpy>> for Drawable {
+    #[inline]
+    fn from(drawable: &GlDrawable<Dpy>) -> Drawable {
+        drawable.window.into()
+    }
+}
+
+impl<Dpy: DisplayLike> GlDrawable<Dpy>
+where
+    Dpy::Connection: Connection,
+{
+    #[inline]
+    pub(crate) fn new(
+        display: GlDisplay<Dpy>,
+        window: Window,
+        colormap: Colormap,
+        visual: GlVisual,
+    ) -> Self {
+        Self {
+            display,
+            window,
+            colormap,
+            visual,
+            dropper: Dropper::<Dpy>::sync_dropper,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Dpy: DisplayLike> GlDrawable<Dpy>
+where
+    Dpy::Connection: AsyncConnection + Send,
+{
+    #[inline]
+    pub(crate) fn new_async(
+        display: GlDisplay<Dpy>,
+        window: Window,
+        colormap: Colormap,
+        visual: GlVisual,
+    ) -> Self {
+        Self {
+            display,
+            window,
+            colormap,
+            visual,
+            dropper: Dropper::<Dpy>::async_dropper,
+        }
+    }
+}
+
+struct Dropper<Dpy>(Dpy);
+
+impl<Dpy: DisplayLike> Dropper<Dpy>
+where
+    Dpy::Connection: Connection,
+{
+    fn sync_dropper(this: &mut GlDrawable<Dpy>) {
+        if let Err(e) = free_colormap(&mut this.display.display(), this.colormap) {
+            log::error!("Failed to free colormap: {:?}", e);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Dpy: DisplayLike> Dropper<Dpy>
+where
+    Dpy::Connection: AsyncConnection + Send,
+{
+    fn async_dropper(this: &mut GlDrawable<Dpy>) {
+        let display = this.display.clone();
+        let cmap = this.colormap;
+
+        offload::offload(async move {
+            let res = free_colormap_async(&mut *display.display_async().await, cmap).await;
+            if let Err(e) = res {
+                log::error!("Failed to free colormap: {:?}", e);
+            }
+        });
+    }
+}
+
+#[inline]
+pub(crate) fn free_colormap<Conn: Connection>(
+    dpy: &mut Display<Conn>,
+    cmap: Colormap,
+) -> breadx::Result {
+    let tok = dpy.send_request(FreeColormapRequest {
+        cmap,
+        ..Default::default()
+    })?;
+    dpy.resolve_request(tok)
+}
+
+#[cfg(feature = "async")]
+#[inline]
+pub(crate) async fn free_colormap_async<Conn: AsyncConnection + Send>(
+    dpy: &mut Display<Conn>,
+    cmap: Colormap,
+) -> breadx::Result {
+    let tok = dpy
+        .send_request_async(FreeColormapRequest {
+            cmap,
+            ..Default::default()
+        })
+        .await?;
+    dpy.resolve_request_async(tok).await
+}
+
+impl<Dpy> Drop for GlDrawable<Dpy> {
+    #[inline]
+    fn drop(&mut self) {
+        (self.dropper)(self)
+    }
+}
@@ -9,19 +9,70 @@ use crate::{
     },
     dri::ExtensionContainer,
 };
-use ahash::AHasher;
 use std::{
     collections::HashMap,
-    hash::{Hash, Hasher},
-    os::raw::c_uint,
+    fmt,
+    os::raw::{c_int, c_uint},
     ptr::{self, NonNull},
 };
 
+/// Explains how a GLX config was matched against the configs the DRI driver supports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriConfigDiagnostic {
+    /// The fbconfig ID of the GLX config.
+    pub fbconfig_id: c_int,
+    /// The visual ID of the GLX config.
+    pub visual_id: c_int,
+    /// The index of the driver config that the GLX config matched, if any.
+    pub driver_config: Option<usize>,
+    /// If the GLX config didn't match anything, where it diverged from the closest driver config.
+    pub mismatch: Option<DriConfigMismatch>,
+}
+
+/// The first attribute that differs between a GLX config and a DRI driver config.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DriConfigMismatch {
+    /// The index of the driver config.
+    pub driver_config: usize,
+    /// The `__DRI_ATTRIB_*` value of the attribute that differs.
+    pub attrib: c_uint,
+    /// The name of the attribute that differs.
+    pub attrib_name: &'static str,
+    /// The driver config's value for the attribute.
+    pub driver_value: c_uint,
+    /// The number of attributes that matched before this one.
+    pub matched_attribs: usize,
+}
+
+impl fmt::Display for DriConfigDiagnostic {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GLX config (fbconfig {:#x}, visual {:#x}) ",
+            self.fbconfig_id, self.visual_id
+        )?;
+
+        match (self.driver_config, self.mismatch) {
+            (Some(dc), _) => write!(f, "matched driver config {}", dc),
+            (None, Some(m)) => write!(
+                f,
+                "matched no driver config; closest was driver config {}, which has {} = {:#x}",
+                m.driver_config, m.attrib_name, m.driver_value
+            ),
+            (None, None) => f.write_str("matched no driver config; the driver has no configs"),
+        }
+    }
+}
+
+/// Match GLX configs with driver configs, returning the driver config for each GLX config, in the
+/// same order as the GLX configs. A diagnostic is pushed for every GLX config, matched or not.
 pub(crate) unsafe fn convert_configs(
     core_extension: ExtensionContainer,
     configs: &[GlConfig],
     driver_configs: *mut *const ffi::__DRIconfig,
-) -> impl Iterator<Item = (u64, NonNull<ffi::__DRIconfig>)> + '_ {
+    diagnostics: &mut Vec<DriConfigDiagnostic>,
+) -> Vec<Option<NonNull<ffi::__DRIconfig>>> {
     #[repr(transparent)]
     struct DriverConfigsIterator(*mut *const ffi::__DRIconfig);
 
@@ -41,30 +92,53 @@ pub(crate) unsafe fn convert_configs(
         }
     }
 
-    configs.iter().filter_map(move |c| {
-        DriverConfigsIterator(driver_configs).find_map(|dc| {
-            if configs_equal(core_extension, c, dc.as_ptr() as *const _) {
-                Some((
-                    {
-                        let mut hasher = AHasher::default();
-                        c.hash(&mut hasher);
-                        hasher.finish()
-                    },
-                    dc,
-                ))
-            } else {
-                None
+    configs
+        .iter()
+        .map(|c| {
+            // keep track of the driver config that got the furthest before it diverged
+            let mut closest: Option<DriConfigMismatch> = None;
+            let found =
+                DriverConfigsIterator(driver_configs)
+                    .enumerate()
+                    .find_map(|(index, dc)| {
+                        match compare_configs(core_extension, c, dc.as_ptr() as *const _, index) {
+                            Ok(()) => Some((index, dc)),
+                            Err(mismatch) => {
+                                if closest
+                                    .is_none_or(|cl| mismatch.matched_attribs > cl.matched_attribs)
+                                {
+                                    closest = Some(mismatch);
+                                }
+                                None
+                            }
+                        }
+                    });
+
+            let diagnostic = DriConfigDiagnostic {
+                fbconfig_id: c.fbconfig_id,
+                visual_id: c.visual_id,
+                driver_config: found.map(|(index, _)| index),
+                mismatch: if found.is_some() { None } else { closest },
+            };
+            match found {
+                Some(_) => log::trace!("{}", diagnostic),
+                None => log::debug!("{}", diagnostic),
             }
+            diagnostics.push(diagnostic);
+
+            found.map(|(_, dc)| dc)
         })
-    })
+        .collect()
 }
 
+/// Compare a GLX config with a driver config, returning the first attribute that differs.
 #[inline]
-fn configs_equal(
+fn compare_configs(
     core_extension: ExtensionContainer,
     config: &GlConfig,
     driver_config: *const ffi::__DRIconfig,
-) -> bool {
+    index: usize,
+) -> Result<(), DriConfigMismatch> {
     let mut i = 0;
 
     // we can iterate over the driver config's values pretty easily
@@ -87,17 +161,83 @@ fn configs_equal(
     } != 0
     {
         if !config_seg_equal(config, attrib, value) {
-            return false;
+            return Err(DriConfigMismatch {
+                driver_config: index,
+                attrib,
+                attrib_name: attrib_name(attrib),
+                driver_value: value,
+                matched_attribs: (i - 1) as usize,
+            });
         }
     }
 
-    true
+    Ok(())
+}
+
+#[inline]
+fn attrib_name(attrib: c_uint) -> &'static str {
+    match attrib {
+        ffi::__DRI_ATTRIB_BUFFER_SIZE => "BUFFER_SIZE",
+        ffi::__DRI_ATTRIB_LEVEL => "LEVEL",
+        ffi::__DRI_ATTRIB_RED_SIZE => "RED_SIZE",
+        ffi::__DRI_ATTRIB_GREEN_SIZE => "GREEN_SIZE",
+        ffi::__DRI_ATTRIB_BLUE_SIZE => "BLUE_SIZE",
+        ffi::__DRI_ATTRIB_LUMINANCE_SIZE => "LUMINANCE_SIZE",
+        ffi::__DRI_ATTRIB_ALPHA_SIZE => "ALPHA_SIZE",
+        ffi::__DRI_ATTRIB_ALPHA_MASK_SIZE => "ALPHA_MASK_SIZE",
+        ffi::__DRI_ATTRIB_DEPTH_SIZE => "DEPTH_SIZE",
+        ffi::__DRI_ATTRIB_STENCIL_SIZE => "STENCIL_SIZE",
+        ffi::__DRI_ATTRIB_ACCUM_RED_SIZE => "ACCUM_RED_SIZE",
+        ffi::__DRI_ATTRIB_ACCUM_GREEN_SIZE => "ACCUM_GREEN_SIZE",
+        ffi::__DRI_ATTRIB_ACCUM_BLUE_SIZE => "ACCUM_BLUE_SIZE",
+        ffi::__DRI_ATTRIB_ACCUM_ALPHA_SIZE => "ACCUM_ALPHA_SIZE",
+        ffi::__DRI_ATTRIB_SAMPLE_BUFFERS => "SAMPLE_BUFFERS",
+        ffi::__DRI_ATTRIB_SAMPLES => "SAMPLES",
+        ffi::__DRI_ATTRIB_RENDER_TYPE => "RENDER_TYPE",
+        ffi::__DRI_ATTRIB_CONFIG_CAVEAT => "CONFIG_CAVEAT",
+        ffi::__DRI_ATTRIB_CONFORMANT => "CONFORMANT",
+        ffi::__DRI_ATTRIB_DOUBLE_BUFFER => "DOUBLE_BUFFER",
+        ffi::__DRI_ATTRIB_STEREO => "STEREO",
+        ffi::__DRI_ATTRIB_AUX_BUFFERS => "AUX_BUFFERS",
+        ffi::__DRI_ATTRIB_TRANSPARENT_TYPE => "TRANSPARENT_TYPE",
+        ffi::__DRI_ATTRIB_TRANSPARENT_INDEX_VALUE => "TRANSPARENT_INDEX_VALUE",
+        ffi::__DRI_ATTRIB_TRANSPARENT_RED_VALUE => "TRANSPARENT_RED_VALUE",
+        ffi::__DRI_ATTRIB_TRANSPARENT_GREEN_VALUE => "TRANSPARENT_GREEN_VALUE",
+        ffi::__DRI_ATTRIB_TRANSPARENT_BLUE_VALUE => "TRANSPARENT_BLUE_VALUE",
+        ffi::__DRI_ATTRIB_TRANSPARENT_ALPHA_VALUE => "TRANSPARENT_ALPHA_VALUE",
+        ffi::__DRI_ATTRIB_FLOAT_MODE => "FLOAT_MODE",
+        ffi::__DRI_ATTRIB_RED_MASK => "RED_MASK",
+        ffi::__DRI_ATTRIB_GREEN_MASK => "GREEN_MASK",
+        ffi::__DRI_ATTRIB_BLUE_MASK => "BLUE_MASK",
+        ffi::__DRI_ATTRIB_ALPHA_MASK => "ALPHA_MASK",
+        ffi::__DRI_ATTRIB_MAX_PBUFFER_WIDTH => "MAX_PBUFFER_WIDTH",
+        ffi::__DRI_ATTRIB_MAX_PBUFFER_HEIGHT => "MAX_PBUFFER_HEIGHT",
+        ffi::__DRI_ATTRIB_MAX_PBUFFER_PIXELS => "MAX_PBUFFER_PIXELS",
+        ffi::__DRI_ATTRIB_OPTIMAL_PBUFFER_WIDTH => "OPTIMAL_PBUFFER_WIDTH",
+        ffi::__DRI_ATTRIB_OPTIMAL_PBUFFER_HEIGHT => "OPTIMAL_PBUFFER_HEIGHT",
+        ffi::__DRI_ATTRIB_VISUAL_SELECT_GROUP => "VISUAL_SELECT_GROUP",
+        ffi::__DRI_ATTRIB_SWAP_METHOD => "SWAP_METHOD",
+        ffi::__DRI_ATTRIB_MAX_SWAP_INTERVAL => "MAX_SWAP_INTERVAL",
+        ffi::__DRI_ATTRIB_MIN_SWAP_INTERVAL => "MIN_SWAP_INTERVAL",
+        ffi::__DRI_ATTRIB_BIND_TO_TEXTURE_RGB => "BIND_TO_TEXTURE_RGB",
+        ffi::__DRI_ATTRIB_BIND_TO_TEXTURE_RGBA => "BIND_TO_TEXTURE_RGBA",
+        ffi::__DRI_ATTRIB_BIND_TO_MIPMAP_TEXTURE => "BIND_TO_MIPMAP_TEXTURE",
+        ffi::__DRI_ATTRIB_BIND_TO_TEXTURE_TARGETS => "BIND_TO_TEXTURE_TARGETS",
+        ffi::__DRI_ATTRIB_YINVERTED => "YINVERTED",
+        ffi::__DRI_ATTRIB_FRAMEBUFFER_SRGB_CAPABLE => "FRAMEBUFFER_SRGB_CAPABLE",
+        ffi::__DRI_ATTRIB_MUTABLE_RENDER_BUFFER => "MUTABLE_RENDER_BUFFER",
+        ffi::__DRI_ATTRIB_RED_SHIFT => "RED_SHIFT",
+        ffi::__DRI_ATTRIB_GREEN_SHIFT => "GREEN_SHIFT",
+        ffi::__DRI_ATTRIB_BLUE_SHIFT => "BLUE_SHIFT",
+        ffi::__DRI_ATTRIB_ALPHA_SHIFT => "ALPHA_SHIFT",
+        _ => "UNKNOWN",
+    }
 }
 
 #[inline]
 fn config_seg_equal(config: &GlConfig, attrib: c_uint, value: c_uint) -> bool {
     if attrib == ffi::__DRI_ATTRIB_RENDER_TYPE {
-        let equivalent = if value & ffi::__DRI_ATTRIB_RENDER_TYPE != 0 {
+        let equivalent = if value & ffi::__DRI_ATTRIB_RGBA_BIT != 0 {
             RGBA_BIT
         } else {
             0
@@ -156,11 +296,26 @@ fn config_seg_equal(config: &GlConfig, attrib: c_uint, value: c_uint) -> bool {
         };
 
         config.swap_method == GlSwapMethod::DontCare || config.swap_method == equivalent
+    } else if let Some(mask) = color_mask(config, attrib) {
+        // masks are only known for configs with a visual; they tell apart formats that have the
+        // same channel sizes but a different channel order, like XRGB2101010 and XBGR2101010
+        mask == 0 || mask == value
     } else {
         raw_compare(config, attrib, value)
     }
 }
 
+#[inline]
+fn color_mask(config: &GlConfig, attrib: c_uint) -> Option<c_uint> {
+    match attrib {
+        ffi::__DRI_ATTRIB_RED_MASK => Some(config.red_mask),
+        ffi::__DRI_ATTRIB_GREEN_MASK => Some(config.green_mask),
+        ffi::__DRI_ATTRIB_BLUE_MASK => Some(config.blue_mask),
+        ffi::__DRI_ATTRIB_ALPHA_MASK => Some(config.alpha_mask),
+        _ => None,
+    }
+}
+
 #[inline]
 fn raw_compare(config: &GlConfig, attrib: c_uint, value: c_uint) -> bool {
     let res = ATTRIB_CONVERTERS
@@ -5,15 +5,20 @@ use crate::{
     display::{DisplayLike, GlDisplay},
 };
 use breadx::{
+    auto::glx,
     display::{Connection, Display},
     Drawable,
 };
-use std::ffi::CStr;
+use std::{ffi::CStr, os::raw::c_int};
 
 #[cfg(feature = "async")]
 use crate::{context::AsyncGlInternalContext, util::GenericFuture};
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
+#[cfg(feature = "async")]
+use futures_lite::future;
+
+const QUERY_UNSUPPORTED: &str = "Querying DRI2 contexts is not supported";
 
 #[derive(Debug)]
 pub struct Dri2Context<Dpy> {
@@ -43,6 +48,16 @@ where
     fn get_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
         unimplemented!()
     }
+
+    #[inline]
+    fn query(
+        &self,
+        _dpy: &GlDisplay<Dpy>,
+        _xid: glx::Context,
+        _attrib: c_int,
+    ) -> breadx::Result<Option<c_int>> {
+        Err(breadx::BreadError::StaticMsg(QUERY_UNSUPPORTED))
+    }
 }
 
 #[cfg(feature = "async")]
@@ -80,4 +95,20 @@ where
     {
         Box::pin(async { unimplemented!() })
     }
+
+    #[inline]
+    fn query_async<'future, 'a, 'b>(
+        &'a self,
+        _dpy: &'b GlDisplay<Dpy>,
+        _xid: glx::Context,
+        _attrib: c_int,
+    ) -> GenericFuture<'future, breadx::Result<Option<c_int>>>
+    where
+        'a: 'future,
+        'b: 'future,
+    {
+        Box::pin(future::ready(Err(breadx::BreadError::StaticMsg(
+            QUERY_UNSUPPORTED,
+        ))))
+    }
 }
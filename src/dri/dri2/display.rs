@@ -31,7 +31,7 @@ impl<Dpy: DisplayLike> Dri2Display<Dpy> {
 
     #[inline]
     pub(crate) async fn new_async(dpy: &mut Display<Dpy::Connection>) -> breadx::Result<Self> {
-        unimplemented!()
+        Err(breadx::BreadError::StaticMsg("Not supported"))
     }
 }
 
@@ -2,23 +2,23 @@
 
 use super::{Dri3Drawable, Dri3Screen};
 use crate::{
-    config::GlConfig,
+    config::{GlConfig, GLX_FBCONFIG_ID, RENDER_TYPE, VISUAL_ID_EXT},
     context::{
         promote_anyarc_ref, ContextDispatch, GlContext, GlContextRule, GlInternalContext,
         InnerGlContext, ProcAddress,
     },
     display::{DisplayLike, GlDisplay},
-    dri::{convert_dri_rules, ffi, DriRules, ExtensionContainer},
-    util::ThreadSafe,
+    dri::{context_error, convert_dri_rules, ffi, DriRules},
 };
 use breadx::{
+    auto::glx,
     display::{Connection, Display},
     Drawable,
 };
 use std::{
     ffi::{c_void, CStr},
     fmt,
-    os::raw::c_uint,
+    os::raw::{c_int, c_uint},
     ptr::{self, NonNull},
     sync::{
         atomic::{AtomicPtr, AtomicUsize, Ordering},
@@ -28,7 +28,11 @@ use std::{
 use tinyvec::ArrayVec;
 
 #[cfg(feature = "async")]
-use crate::{context::AsyncGlInternalContext, offload, util::GenericFuture};
+use crate::{
+    context::AsyncGlInternalContext,
+    offload,
+    util::{GenericFuture, ThreadSafe},
+};
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
 #[cfg(feature = "async")]
@@ -42,6 +46,7 @@ struct Dri3ContextInner<Dpy> {
     // Dri3Screen is wrapped in an Arc, we can keep a sneaky reference here
     screen: Dri3Screen<Dpy>,
     fbconfig: GlConfig,
+    rules: DriRules,
     context_id: usize,
     dropper: fn(&mut Dri3ContextInner<Dpy>),
 }
@@ -91,16 +96,22 @@ impl<Dpy: Send + Sync + 'static> Dri3Context<Dpy> {
         };
         let mut error: c_uint = 0;
 
+        // the config has already been checked against the screen, so this only fails if the
+        // driver has no config matching it
+        let dri_config =
+            screen
+                .driconfig_from_fbconfig(&fbconfig)
+                .ok_or(breadx::BreadError::StaticMsg(
+                    "Config has no matching DRI driver config",
+                ))?;
+
         let dri_context = unsafe {
             ((*(screen.inner.image_driver))
                 .createContextAttribs
                 .expect("Unable to find createContextAttribs"))(
                 screen.dri_screen().as_ptr(),
                 rules.api as _,
-                match screen.driconfig_from_fbconfig(&fbconfig) {
-                    Some(screen) => screen.as_ptr(),
-                    None => ptr::null(),
-                },
+                dri_config.as_ptr(),
                 share,
                 attrib.len() as _,
                 attrib.as_ptr(),
@@ -114,11 +125,10 @@ impl<Dpy: Send + Sync + 'static> Dri3Context<Dpy> {
 
         Ok(Self {
             inner: Arc::new(Dri3ContextInner {
-                dri_context: NonNull::new(dri_context).ok_or(breadx::BreadError::StaticMsg(
-                    "Failed to initialize DRI3 context",
-                ))?,
+                dri_context: NonNull::new(dri_context).ok_or_else(|| context_error(error))?,
                 screen,
                 fbconfig,
+                rules,
                 context_id: CONTEXT_ID.fetch_add(1, Ordering::AcqRel),
                 dropper,
             }),
@@ -175,6 +185,17 @@ impl<Dpy: Send + Sync + 'static> Dri3Context<Dpy> {
         self.inner.context_id
     }
 
+    /// Answer a context query using the state this context was created with.
+    #[inline]
+    fn query_internal(&self, attrib: c_int) -> Option<c_int> {
+        match attrib {
+            GLX_FBCONFIG_ID => Some(self.inner.fbconfig.fbconfig_id),
+            VISUAL_ID_EXT => Some(self.inner.fbconfig.visual_id),
+            RENDER_TYPE => Some(self.inner.rules.render_type as _),
+            _ => None,
+        }
+    }
+
     #[inline]
     fn unbind_internal(&self) {
         // SAFETY: even if this function goes wrong, it's not like it has much of an effect.
@@ -308,7 +329,17 @@ where
 
     #[inline]
     fn get_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
-        None
+        self.screen().driver_proc_address(name)
+    }
+
+    #[inline]
+    fn query(
+        &self,
+        _dpy: &GlDisplay<Dpy>,
+        _xid: glx::Context,
+        attrib: c_int,
+    ) -> breadx::Result<Option<c_int>> {
+        Ok(self.query_internal(attrib))
     }
 }
 
@@ -409,7 +440,23 @@ where
         'a: 'future,
         'b: 'future,
     {
-        Box::pin(future::ready(None))
+        let screen = self.screen().clone();
+        let name = name.to_owned();
+        Box::pin(blocking::unblock(move || screen.driver_proc_address(&name)))
+    }
+
+    #[inline]
+    fn query_async<'future, 'a, 'b>(
+        &'a self,
+        _dpy: &'b GlDisplay<Dpy>,
+        _xid: glx::Context,
+        attrib: c_int,
+    ) -> GenericFuture<'future, breadx::Result<Option<c_int>>>
+    where
+        'a: 'future,
+        'b: 'future,
+    {
+        Box::pin(future::ready(Ok(self.query_internal(attrib))))
     }
 }
 
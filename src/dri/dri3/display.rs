@@ -4,14 +4,16 @@ use crate::{
     config::GlConfig,
     display::{DisplayLike, DisplayLock, GlInternalDisplay},
     dll::Dll,
-    dri::dri3::Dri3Screen,
+    dri::{dri3::Dri3Screen, load},
+    indirect::IndirectScreen,
+    mesa,
     screen::{self, GlScreen},
 };
 use breadx::{
     display::{Connection, Display},
     error::BreadError,
 };
-use std::{boxed::Box, fmt, marker::PhantomData, os::raw::c_int, sync::Arc};
+use std::{boxed::Box, fmt, marker::PhantomData, os::raw::c_int, path::PathBuf, sync::Arc};
 
 #[cfg(feature = "async")]
 use crate::{display::AsyncGlInternalDisplay, util::GenericFuture};
@@ -33,6 +35,12 @@ pub(crate) struct Dri3Display<Dpy> {
     dri3_version_minor: u32,
     present_version_major: u32,
     present_version_minor: u32,
+    // the name of the driver to load instead of the one matching the device
+    driver_override: Option<String>,
+    // the directories to search for drivers in
+    driver_dirs: Vec<PathBuf>,
+    // the DRI_PRIME-style selector of the GPU to render on
+    prime: Option<String>,
     _phantom: PhantomData<Dpy>,
 }
 
@@ -70,7 +78,15 @@ where
     Dpy::Connection: Connection,
 {
     #[inline]
-    pub(crate) fn new(dpy: &mut Display<Dpy::Connection>) -> breadx::Result<Self> {
+    pub(crate) fn new(
+        dpy: &mut Display<Dpy::Connection>,
+        driver_override: Option<String>,
+        prime: Option<String>,
+    ) -> breadx::Result<Self> {
+        // make sure the libraries we need are there, so we can fall back to another backend if not
+        mesa::drm()?;
+        mesa::xshmfence()?;
+
         // query whether or not the extension's versions are present
         // note: this automatically triggers ExtensionNotPresent errors
         let dri3iv_tok = dpy.query_dri3_version(DRI3_MAJOR, DRI3_MINOR)?;
@@ -83,6 +99,9 @@ where
             dri3_version_minor: dri3iv.minor_version,
             present_version_major: presentiv.major_version,
             present_version_minor: presentiv.minor_version,
+            driver_override,
+            driver_dirs: load::driver_search_dirs(),
+            prime,
             _phantom: PhantomData,
         })
     }
@@ -94,7 +113,15 @@ where
     Dpy::Connection: AsyncConnection + Send,
 {
     #[inline]
-    pub(crate) async fn new_async(dpy: &mut Display<Dpy::Connection>) -> breadx::Result<Self> {
+    pub(crate) async fn new_async(
+        dpy: &mut Display<Dpy::Connection>,
+        driver_override: Option<String>,
+        prime: Option<String>,
+    ) -> breadx::Result<Self> {
+        // make sure the libraries we need are there, so we can fall back to another backend if not
+        mesa::drm_async().await?;
+        mesa::xshmfence_async().await?;
+
         // query whether or not the extension's versions are present
         // note: this automatically triggers ExtensionNotPresent errors
         let dri3iv_tok = dpy.query_dri3_version_async(DRI3_MAJOR, DRI3_MINOR).await?;
@@ -109,6 +136,9 @@ where
             dri3_version_minor: dri3iv.minor_version,
             present_version_major: presentiv.major_version,
             present_version_minor: presentiv.minor_version,
+            driver_override,
+            driver_dirs: load::driver_search_dirs(),
+            prime,
             _phantom: PhantomData,
         })
     }
@@ -127,9 +157,26 @@ where
         let (visuals, fbconfigs) = GlConfig::get_visuals_and_fbconfigs(dpy, index)?;
         let visuals: Arc<[GlConfig]> = visuals.into_boxed_slice().into();
         let fbconfigs: Arc<[GlConfig]> = fbconfigs.into_boxed_slice().into();
-        let screen = Dri3Screen::new(dpy, index, visuals.clone(), fbconfigs.clone())?;
-
-        Ok(GlScreen::from_dri3(index, fbconfigs, visuals, screen))
+        match Dri3Screen::new(
+            dpy,
+            index,
+            visuals.clone(),
+            fbconfigs.clone(),
+            self.driver_override.as_deref(),
+            &self.driver_dirs,
+            self.prime.as_deref(),
+        ) {
+            Ok(screen) => Ok(GlScreen::from_dri3(index, fbconfigs, visuals, screen)),
+            Err(e) => {
+                log_indirect_fallback(index, &e);
+                Ok(GlScreen::from_indirect(
+                    index,
+                    fbconfigs,
+                    visuals,
+                    IndirectScreen::new(),
+                ))
+            }
+        }
     }
 }
 
@@ -154,10 +201,61 @@ where
                 GlConfig::get_visuals_and_fbconfigs_async(dpy, index).await?;
             let visuals: Arc<[GlConfig]> = visuals.into_boxed_slice().into();
             let fbconfigs: Arc<[GlConfig]> = fbconfigs.into_boxed_slice().into();
-            let dri3_screen =
-                Dri3Screen::new_async(dpy, index, visuals.clone(), fbconfigs.clone()).await?;
-
-            Ok(GlScreen::from_dri3(index, fbconfigs, visuals, dri3_screen))
+            match Dri3Screen::new_async(
+                dpy,
+                index,
+                visuals.clone(),
+                fbconfigs.clone(),
+                self.driver_override.as_deref(),
+                &self.driver_dirs,
+                self.prime.as_deref(),
+            )
+            .await
+            {
+                Ok(dri3_screen) => Ok(GlScreen::from_dri3(index, fbconfigs, visuals, dri3_screen)),
+                Err(e) => {
+                    log_indirect_fallback(index, &e);
+                    Ok(GlScreen::from_indirect(
+                        index,
+                        fbconfigs,
+                        visuals,
+                        IndirectScreen::new(),
+                    ))
+                }
+            }
         })
     }
 }
+
+/// If no driver, not even the software renderer, can be loaded for the screen, the screen is
+/// still usable over indirect rendering.
+#[inline]
+fn log_indirect_fallback(index: usize, e: &BreadError) {
+    log::error!(
+        "Unable to create DRI3 screen {}, falling back to indirect rendering: {:?}",
+        index,
+        e
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::fake_server;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn server_without_dri3() {
+        let (mut dpy, server) = fake_server();
+        let res = Dri3Display::<Display<UnixStream>>::new(&mut dpy, None, None);
+        match res {
+            Err(BreadError::ExtensionNotPresent(_)) => (),
+            // without libdrm or libxshmfence, it fails before talking to the server
+            Err(BreadError::LoadLibraryFailed(_)) | Err(BreadError::Msg(_)) => (),
+            res => panic!("Expected DRI3 to be unavailable, got {:?}", res),
+        }
+
+        drop(dpy);
+        server.join().unwrap();
+    }
+}
@@ -1194,7 +1194,7 @@ where
 
         if !self.is_pixmap.load(Ordering::Relaxed) {
             if let Ok(ref buffer) = buffer {
-                reset_fence(buffer.shm_fence);
+                reset_fence(buffer.shm_fence)?;
 
                 log::trace!("Calculating sbc...");
                 self.prepare_swap(
@@ -1260,7 +1260,7 @@ where
                         .cloned()
                         .unwrap();
 
-                    reset_fence(new_back.shm_fence);
+                    reset_fence(new_back.shm_fence)?;
                     let gc = self.drawable_gc(&mut conn)?;
                     conn.copy_area(
                         source.pixmap,
@@ -1354,7 +1354,7 @@ where
                         .is_err()
                         && buffer.linear_buffer.is_none()
                     {
-                        reset_fence(new_buffer.shm_fence);
+                        reset_fence(new_buffer.shm_fence)?;
                         let gc = self.drawable_gc(&mut *conn)?;
                         conn.copy_area(
                             buffer.pixmap,
@@ -1378,7 +1378,7 @@ where
                     mem::drop((conn, state.take()));
                     self.swapbuffer_barrier()?;
                     let mut conn = self.display.display();
-                    reset_fence(new_buffer.shm_fence);
+                    reset_fence(new_buffer.shm_fence)?;
                     let gc = self.drawable_gc(&mut *conn)?;
                     conn.copy_area(
                         self.x_drawable,
@@ -1510,12 +1510,9 @@ where
         let this_screen = self.screen();
 
         let xshmfence = xshmfence()?;
-        let alloc_shm: XshmfenceAllocShm = unsafe { xshmfence.function(&XSHMFENCE_ALLOC_SHM) }
-            .expect("xshmfence_alloc_shm not present");
-        let map_shm: XshmfenceMapShm = unsafe { xshmfence.function(&XSHMFENCE_MAP_SHM) }
-            .expect("xshmfence_map_shm not present");
-        let unmap_shm: XshmfenceUnmapShm =
-            unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }.expect("xshmfence_unmap_shm");
+        let alloc_shm = xshmfence.alloc_shm;
+        let map_shm = xshmfence.map_shm;
+        let unmap_shm = xshmfence.unmap_shm;
 
         // set up fencing and all
         let fence_fd = unsafe { (alloc_shm)() };
@@ -2276,7 +2273,7 @@ where
 
         if !self.is_pixmap.load(Ordering::Relaxed) {
             if let Ok(ref buffer) = buffer {
-                reset_fence_async(unsafe { ThreadSafe::new(buffer.shm_fence) }).await;
+                reset_fence_async(unsafe { ThreadSafe::new(buffer.shm_fence) }).await?;
 
                 self.prepare_swap(
                     &mut state,
@@ -2341,7 +2338,7 @@ where
                         .cloned()
                         .unwrap();
 
-                    reset_fence_async(unsafe { ThreadSafe::new(new_back.shm_fence) }).await;
+                    reset_fence_async(unsafe { ThreadSafe::new(new_back.shm_fence) }).await?;
                     let gc = self.drawable_gc_async(&mut conn).await?;
                     conn.copy_area_async(
                         source.pixmap,
@@ -2456,7 +2453,7 @@ where
                         && buffer.linear_buffer.is_none()
                     {
                         let s = unsafe { ThreadSafe::new(new_buffer.shm_fence) };
-                        reset_fence_async(s).await;
+                        reset_fence_async(s).await?;
                         let gc = this.drawable_gc_async(&mut *conn).await?;
                         conn.copy_area_async(
                             buffer.pixmap,
@@ -2482,7 +2479,7 @@ where
                     this.swapbuffer_barrier_async().await?;
                     let mut conn = this.display.display_async().await;
                     let s = unsafe { ThreadSafe::new(new_buffer.shm_fence) };
-                    reset_fence_async(s).await;
+                    reset_fence_async(s).await?;
                     let gc = this.drawable_gc_async(&mut *conn).await?;
                     conn.copy_area_async(
                         this.x_drawable,
@@ -2632,14 +2629,9 @@ where
         let this_screen = self.screen();
 
         let xshmfence = xshmfence_async().await?;
-        let alloc_shm: ThreadSafe<XshmfenceAllocShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_ALLOC_SHM) }
-                .expect("xshmfence_alloc_shm not present");
-        let map_shm: ThreadSafe<XshmfenceMapShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_MAP_SHM) }
-                .expect("xshmfence_map_shm not present");
-        let unmap_shm: ThreadSafe<XshmfenceUnmapShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }.expect("xshmfence_unmap_shm");
+        let alloc_shm = unsafe { ThreadSafe::new(xshmfence.alloc_shm) };
+        let map_shm = unsafe { ThreadSafe::new(xshmfence.map_shm) };
+        let unmap_shm = unsafe { ThreadSafe::new(xshmfence.unmap_shm) };
 
         let fence_fd = blocking::unblock(move || unsafe { (alloc_shm.into_inner())() }).await;
         if fence_fd < 0 {
@@ -2899,19 +2891,6 @@ where
     }
 }
 
-const XSHMFENCE_ALLOC_SHM: ConstCstr<'static> = const_cstr(&*b"xshmfence_alloc_shm\0");
-type XshmfenceAllocShm = unsafe extern "C" fn() -> c_int;
-const XSHMFENCE_MAP_SHM: ConstCstr<'static> = const_cstr(&*b"xshmfence_map_shm\0");
-type XshmfenceMapShm = unsafe extern "C" fn(c_int) -> *mut c_void;
-const XSHMFENCE_UNMAP_SHM: ConstCstr<'static> = const_cstr(&*b"xshmfence_unmap_shm\0");
-type XshmfenceUnmapShm = unsafe extern "C" fn(*mut c_void);
-const XSHMFENCE_TRIGGER: ConstCstr<'static> = const_cstr(&*b"xshmfence_trigger\0");
-type XshmfenceTrigger = XshmfenceUnmapShm;
-const XSHMFENCE_RESET: ConstCstr<'static> = const_cstr(&*b"xshmfence_reset\0");
-type XshmfenceReset = XshmfenceTrigger;
-const XSHMFENCE_AWAIT: ConstCstr<'static> = const_cstr(&*b"xshmfence_await\0");
-type XshmfenceAwait = XshmfenceReset;
-
 impl Dri3Buffer {
     #[inline]
     fn new<Dpy: DisplayLike>(
@@ -2928,12 +2907,9 @@ impl Dri3Buffer {
         //       functions if we have a chance
 
         let xshmfence = xshmfence()?;
-        let alloc_shm: XshmfenceAllocShm = unsafe { xshmfence.function(&XSHMFENCE_ALLOC_SHM) }
-            .expect("xshmfence_alloc_shm not present");
-        let map_shm: XshmfenceMapShm = unsafe { xshmfence.function(&XSHMFENCE_MAP_SHM) }
-            .expect("xshmfence_map_shm not present");
-        let unmap_shm: XshmfenceUnmapShm =
-            unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }.expect("xshmfence_unmap_shm");
+        let alloc_shm = xshmfence.alloc_shm;
+        let map_shm = xshmfence.map_shm;
+        let unmap_shm = xshmfence.unmap_shm;
 
         // create an xshm object
         let fence_fd = unsafe { (alloc_shm)() };
@@ -3230,7 +3206,7 @@ impl Dri3Buffer {
         };
 
         let sync_fence = conn.fence_from_fd(pixmap.into(), false, fence_fd)?;
-        set_fence(shm_fence);
+        set_fence(shm_fence)?;
 
         mem::forget((fd_guard, shm_guard, image_guard, buffer_guard));
 
@@ -3274,14 +3250,9 @@ impl Dri3Buffer {
     {
         // TODO: same as above, try to break it down into smaller parts if we can
         let xshmfence = xshmfence_async().await?;
-        let alloc_shm: ThreadSafe<XshmfenceAllocShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_ALLOC_SHM) }
-                .expect("xshmfence_alloc_shm not present");
-        let map_shm: ThreadSafe<XshmfenceMapShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_MAP_SHM) }
-                .expect("xshmfence_map_shm not present");
-        let unmap_shm: ThreadSafe<XshmfenceUnmapShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }.expect("xshmfence_unmap_shm");
+        let alloc_shm = unsafe { ThreadSafe::new(xshmfence.alloc_shm) };
+        let map_shm = unsafe { ThreadSafe::new(xshmfence.map_shm) };
+        let unmap_shm = unsafe { ThreadSafe::new(xshmfence.unmap_shm) };
 
         let fence_fd = blocking::unblock(move || unsafe { (alloc_shm.into_inner())() }).await;
         if fence_fd < 0 {
@@ -3645,7 +3616,7 @@ impl Dri3Buffer {
         let sync_fence = conn
             .fence_from_fd_async(pixmap.into(), false, fence_fd)
             .await?;
-        set_fence_async(shm_fence).await;
+        set_fence_async(shm_fence).await?;
 
         mem::forget((fd_guard, shm_guard, image_guard, buffer_guard));
 
@@ -3689,9 +3660,7 @@ impl Dri3Buffer {
         conn.free_sync_fence(self.sync_fence)?;
         // free the shm fence
         let xshmfence = xshmfence()?;
-        let unmap_shm: XshmfenceUnmapShm = unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }
-            .ok_or(StaticMsg("Failed to load xshmfence_unmap_shm"))?;
-        unsafe { unmap_shm(self.shm_fence.as_ptr() as *mut _) };
+        unsafe { (xshmfence.unmap_shm)(self.shm_fence.as_ptr() as *mut _) };
 
         // destroy the buffers
         let image_ext = unsafe { &*drawable.screen().inner.image };
@@ -3722,9 +3691,7 @@ impl Dri3Buffer {
 
         dpy.free_sync_fence_async(self.sync_fence).await?;
         let xshmfence = xshmfence_async().await?;
-        let unmap_shm: ThreadSafe<XshmfenceUnmapShm> =
-            unsafe { xshmfence.function(&XSHMFENCE_UNMAP_SHM) }
-                .ok_or(StaticMsg("Failed to load xshmfence_unmap_shm"))?;
+        let unmap_shm = unsafe { ThreadSafe::new(xshmfence.unmap_shm) };
         let shm_fence = unsafe { ThreadSafe::new(self.shm_fence.as_ptr()) };
         let t1 = blocking::unblock(move || unsafe {
             (unmap_shm.into_inner())(shm_fence.into_inner() as *mut _)
@@ -3932,37 +3899,35 @@ async fn image_from_buffers_async<Dpy: DisplayLike>(
 }
 
 #[inline]
-fn set_fence(fence: NonNull<c_void>) {
-    let xshmfence = xshmfence().expect("Failed to load xshmfence"); // should be infallible
-    let trigger: XshmfenceTrigger =
-        unsafe { xshmfence.function(&*XSHMFENCE_TRIGGER) }.expect("xshmfence_trigger not found");
-    unsafe { (trigger)(fence.as_ptr()) };
+fn set_fence(fence: NonNull<c_void>) -> breadx::Result {
+    let xshmfence = xshmfence()?;
+    unsafe { (xshmfence.trigger)(fence.as_ptr()) };
+    Ok(())
 }
 
 #[cfg(feature = "async")]
 #[inline]
-async fn set_fence_async(fence: ThreadSafe<NonNull<c_void>>) {
-    let xshmfence = xshmfence_async().await.unwrap();
-    let trigger: ThreadSafe<XshmfenceTrigger> =
-        unsafe { xshmfence.function(&*XSHMFENCE_TRIGGER) }.expect("xshmfence_trigger not found");
-    blocking::unblock(move || unsafe { (trigger.into_inner())(fence.into_inner().as_ptr()) }).await
+async fn set_fence_async(fence: ThreadSafe<NonNull<c_void>>) -> breadx::Result {
+    let xshmfence = xshmfence_async().await?;
+    let trigger = unsafe { ThreadSafe::new(xshmfence.trigger) };
+    blocking::unblock(move || unsafe { (trigger.into_inner())(fence.into_inner().as_ptr()) }).await;
+    Ok(())
 }
 
 #[inline]
-fn reset_fence(fence: NonNull<c_void>) {
-    let xshmfence = xshmfence().expect("Infallible!");
-    let reset: XshmfenceReset =
-        unsafe { xshmfence.function(&*XSHMFENCE_RESET) }.expect("xshmfence_reset not found");
-    unsafe { (reset)(fence.as_ptr()) };
+fn reset_fence(fence: NonNull<c_void>) -> breadx::Result {
+    let xshmfence = xshmfence()?;
+    unsafe { (xshmfence.reset)(fence.as_ptr()) };
+    Ok(())
 }
 
 #[cfg(feature = "async")]
 #[inline]
-async fn reset_fence_async(fence: ThreadSafe<NonNull<c_void>>) {
-    let xshmfence = xshmfence_async().await.unwrap();
-    let reset: ThreadSafe<XshmfenceReset> =
-        unsafe { xshmfence.function(&*XSHMFENCE_RESET) }.expect("xshmfence_reset not found");
-    blocking::unblock(move || unsafe { (reset.into_inner())(fence.into_inner().as_ptr()) }).await
+async fn reset_fence_async(fence: ThreadSafe<NonNull<c_void>>) -> breadx::Result {
+    let xshmfence = xshmfence_async().await?;
+    let reset = unsafe { ThreadSafe::new(xshmfence.reset) };
+    blocking::unblock(move || unsafe { (reset.into_inner())(fence.into_inner().as_ptr()) }).await;
+    Ok(())
 }
 
 #[inline]
@@ -3997,10 +3962,8 @@ fn block_on_fence<Dpy: DisplayLike>(
 where
     Dpy::Connection: Connection,
 {
-    let xshmfence = xshmfence().expect("Infallible!");
-    let xawait: XshmfenceAwait =
-        unsafe { xshmfence.function(&*XSHMFENCE_AWAIT) }.expect("xshmfence_await not found");
-    unsafe { (xawait)(buffer.shm_fence.as_ptr()) };
+    let xshmfence = xshmfence()?;
+    unsafe { (xshmfence.await_fence)(buffer.shm_fence.as_ptr()) };
 
     if let Some(drawable) = drawable {
         log::trace!("Borrowing guard for drawable");
@@ -4023,9 +3986,8 @@ async fn await_on_fence<Dpy: DisplayLike>(
 where
     Dpy::Connection: AsyncConnection + Send,
 {
-    let xshmfence = xshmfence_async().await.unwrap();
-    let xawait: ThreadSafe<XshmfenceAwait> =
-        unsafe { xshmfence.function(&*XSHMFENCE_AWAIT) }.unwrap();
+    let xshmfence = xshmfence_async().await?;
+    let xawait = unsafe { ThreadSafe::new(xshmfence.await_fence) };
     let shm_fence = unsafe { ThreadSafe::new(buffer.shm_fence) };
     blocking::unblock(move || unsafe { (xawait)(shm_fence.into_inner().as_ptr()) }).await;
 
@@ -4196,7 +4158,7 @@ const fn cpp_for_format(format: c_uint) -> Option<u32> {
         4097 | 4103 => 2,
         // XRGB8888, ARGB8888, ABGR8888, XBGR8888, XRGB2101010, ARGB2101010, XBGR2101010,
         // ABGR2101010, SARGB8, SABGR8, SXRGB8,
-        4098 | 4099 | 4100 | 4101 | 4106 | 4106 | 4112 | 4113 | 4107 | 4114 | 4118 => 4,
+        4098 | 4099 | 4100 | 4101 | 4105 | 4106 | 4112 | 4113 | 4107 | 4114 | 4118 => 4,
         // XBGR16161616F, ABGR16161616F
         4116 | 4117 => 8,
         _ => return None,
@@ -2,34 +2,39 @@
 
 use super::{super::ExtensionContainer, Dri3Context, Dri3Drawable};
 use crate::{
-    config::{GlConfig, GLX_FBCONFIG_ID},
+    config::{GlConfig, DONT_CARE, GLX_FBCONFIG_ID},
     context::{
         dispatch::ContextDispatch, promote_anyarc_ref, GlContext, GlContextRule, InnerGlContext,
+        ProcAddress,
     },
     cstr::{const_cstr, ConstCstr},
     display::{DisplayDispatch, DisplayLike, GlDisplay},
     dll::Dll,
-    dri::{config, ffi, load},
+    dri::{
+        config::{self, DriConfigDiagnostic},
+        ffi, load,
+    },
     screen::GlInternalScreen,
-    util::ThreadSafe,
+    util::CallOnDrop,
 };
-use ahash::AHasher;
 use breadx::{Connection, Display, Drawable};
 use dashmap::DashMap;
 use std::{
-    cell::UnsafeCell,
-    collections::HashMap,
     ffi::{c_void, CStr},
     fmt,
-    hash::{Hash, Hasher},
-    mem::ManuallyDrop,
-    os::raw::c_int,
-    ptr::{self, addr_of as raw_const, NonNull},
+    mem::{self, ManuallyDrop},
+    os::raw::{c_char, c_int},
+    path::PathBuf,
+    ptr::{self, NonNull},
     sync::{Arc, Weak},
 };
 
 #[cfg(feature = "async")]
-use crate::{offload, screen::AsyncGlInternalScreen, util::GenericFuture};
+use crate::{
+    offload,
+    screen::AsyncGlInternalScreen,
+    util::{GenericFuture, ThreadSafe},
+};
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
 
@@ -83,8 +88,11 @@ pub struct Dri3ScreenInner<Dpy> {
     // the internal pointer to the actual DRI screen
     dri_screen: Option<NonNull<ffi::__DRIscreen>>,
 
-    // a map matching the hash values of glconfigs to driconfig pointers
-    dri_configmap: Option<HashMap<u64, NonNull<ffi::__DRIconfig>>>,
+    // the driconfigs matching each of the visuals and fbconfigs, in the same order as them
+    visual_driconfigs: Vec<Option<NonNull<ffi::__DRIconfig>>>,
+    fbconfig_driconfigs: Vec<Option<NonNull<ffi::__DRIconfig>>>,
+    // how each glconfig was matched to a driconfig, for diagnostic purposes
+    config_diagnostics: Vec<DriConfigDiagnostic>,
 
     // a map matching X11 drawables to DRI3 drawables
     drawable_map: ManuallyDrop<DashMap<Drawable, Arc<Dri3Drawable<Dpy>>>>,
@@ -120,6 +128,32 @@ unsafe impl<Dpy: Send> Send for Dri3ScreenInner<Dpy> {}
 unsafe impl<Dpy: Sync> Sync for Dri3ScreenInner<Dpy> {}
 
 impl<Dpy: DisplayLike> Dri3ScreenInner<Dpy> {
+    /// Match the visuals and fbconfigs up with the driver's configs.
+    #[inline]
+    fn load_config_map(&mut self, visuals: &[GlConfig], fbconfigs: &[GlConfig]) {
+        let core = ExtensionContainer(self.core as *const _);
+        let mut diagnostics = Vec::with_capacity(visuals.len() + fbconfigs.len());
+        let (visual_driconfigs, fbconfig_driconfigs) = unsafe {
+            (
+                config::convert_configs(core, visuals, self.driver_configs, &mut diagnostics),
+                config::convert_configs(core, fbconfigs, self.driver_configs, &mut diagnostics),
+            )
+        };
+
+        log::debug!(
+            "{} of {} GLX configs have a matching DRI config",
+            diagnostics
+                .iter()
+                .filter(|d| d.driver_config.is_some())
+                .count(),
+            diagnostics.len()
+        );
+
+        self.visual_driconfigs = visual_driconfigs;
+        self.fbconfig_driconfigs = fbconfig_driconfigs;
+        self.config_diagnostics = diagnostics;
+    }
+
     #[inline]
     fn get_extensions_core(
         &mut self,
@@ -156,15 +190,31 @@ impl<Dpy: DisplayLike> Dri3ScreenInner<Dpy> {
         Ok(())
     }
 
+    /// Rendering on a different GPU than the display GPU requires blitting into a buffer that the
+    /// display GPU can read.
+    #[inline]
+    fn check_prime_support(&self) -> breadx::Result<()> {
+        if !self.is_different_gpu {
+            return Ok(());
+        }
+
+        match unsafe { self.image.as_ref() } {
+            Some(image) if image.base.version >= 9 && image.blitImage.is_some() => Ok(()),
+            _ => Err(breadx::BreadError::StaticMsg(
+                "Rendering on a different GPU requires a driver that can blit images",
+            )),
+        }
+    }
+
     #[inline]
     fn get_extensions(&mut self) -> breadx::Result<()> {
-        let extensions = unsafe {
-            ((*self.core)
-                .getExtensions
-                .expect("failed to load getextensions function"))(
-                self.dri_screen.expect("NFI").as_ptr(),
-            )
-        };
+        let get_extensions = unsafe { (*self.core).getExtensions }.ok_or(
+            breadx::BreadError::StaticMsg("DRI core extension is missing getExtensions"),
+        )?;
+        let dri_screen = self
+            .dri_screen
+            .ok_or(breadx::BreadError::StaticMsg("DRI screen was not created"))?;
+        let extensions = unsafe { get_extensions(dri_screen.as_ptr()) };
         self.get_extensions_core(extensions)
     }
 
@@ -176,9 +226,13 @@ impl<Dpy: DisplayLike> Dri3ScreenInner<Dpy> {
         loader_extensions: &[ExtensionContainer],
         extensions: &mut [ExtensionContainer],
     ) -> breadx::Result<()> {
+        let create_new_screen2 = unsafe { (*self.image_driver).createNewScreen2 }.ok_or(
+            breadx::BreadError::StaticMsg("DRI image driver extension is missing createNewScreen2"),
+        )?;
+
         let mut driver_configs = ptr::null_mut();
         let dri_screen = unsafe {
-            ((*self.image_driver).createNewScreen2.unwrap())(
+            (create_new_screen2)(
                 scr as _,
                 fd,
                 loader_extensions.as_ptr() as *mut _,
@@ -194,6 +248,8 @@ impl<Dpy: DisplayLike> Dri3ScreenInner<Dpy> {
         };
         let dri_screen = NonNull::new(dri_screen)
             .ok_or(breadx::BreadError::StaticMsg("Failed to create DRI screen"))?;
+        // set this now, so the screen is destroyed if anything below fails
+        self.dri_screen = Some(dri_screen);
 
         if driver_configs.is_null() {
             return Err(breadx::BreadError::StaticMsg(
@@ -207,12 +263,28 @@ impl<Dpy: DisplayLike> Dri3ScreenInner<Dpy> {
             ));
         }
 
-        self.dri_screen = Some(dri_screen);
         self.driver_configs = driver_configs;
         Ok(())
     }
 }
 
+/// Close a file descriptor when dropped, unless it's been forgotten. This keeps a descriptor from
+/// leaking if screen creation fails before the screen takes ownership of it.
+#[inline]
+fn close_on_drop(fd: c_int) -> CallOnDrop<impl FnOnce()> {
+    CallOnDrop::new(move || unsafe {
+        libc::close(fd);
+    })
+}
+
+#[inline]
+fn log_prime_fallback(err: &breadx::BreadError) {
+    log::warn!(
+        "Unable to render on the GPU selected by DRI_PRIME, using the display GPU instead: {:?}",
+        err
+    );
+}
+
 #[inline]
 fn get_bootstrap_extensions(
     extensions: &[ExtensionContainer],
@@ -244,20 +316,79 @@ fn get_bootstrap_extensions(
     }
 }
 
+/// The position of a config in a screen's visuals or fbconfigs.
+#[derive(Debug, Copy, Clone)]
+enum ConfigIndex {
+    Visual(usize),
+    Fbconfig(usize),
+}
+
 impl<Dpy> Dri3Screen<Dpy> {
     #[inline]
     pub(crate) fn driconfig_from_fbconfig(
         &self,
         cfg: &GlConfig,
     ) -> Option<NonNull<ffi::__DRIconfig>> {
-        let mut hasher = AHasher::default();
-        cfg.hash(&mut hasher);
+        let dri_config = match self.config_index(cfg) {
+            Some(ConfigIndex::Fbconfig(i)) => self.inner.fbconfig_driconfigs[i],
+            Some(ConfigIndex::Visual(i)) => self.inner.visual_driconfigs[i],
+            None => {
+                log::error!(
+                    "GLX config (fbconfig {:#x}, visual {:#x}) does not belong to this screen",
+                    cfg.fbconfig_id,
+                    cfg.visual_id
+                );
+                return None;
+            }
+        };
+
+        if dri_config.is_none() {
+            if let Some(diagnostic) = self.config_diagnostic(cfg) {
+                log::error!("{}", diagnostic);
+            }
+        }
+
+        dri_config
+    }
+
+    /// Find where a config lives in this screen's list of configs. Configs are looked up by their ID,
+    /// and then compared in full, so a config from another screen (or one that has been modified)
+    /// is never mistaken for one of ours.
+    #[inline]
+    fn config_index(&self, cfg: &GlConfig) -> Option<ConfigIndex> {
+        #[inline]
+        fn find(
+            configs: &[GlConfig],
+            cfg: &GlConfig,
+            id: impl Fn(&GlConfig) -> c_int,
+        ) -> Option<usize> {
+            if id(cfg) == DONT_CARE {
+                return None;
+            }
+
+            configs
+                .iter()
+                .position(|other| id(other) == id(cfg))
+                .filter(|i| &configs[*i] == cfg)
+        }
+
+        find(&self.inner.fbconfigs, cfg, |c| c.fbconfig_id)
+            .map(ConfigIndex::Fbconfig)
+            .or_else(|| find(&self.inner.visuals, cfg, |c| c.visual_id).map(ConfigIndex::Visual))
+    }
+
+    /// Get the diagnostics explaining how each GLX config was matched with a driver config.
+    #[inline]
+    pub(crate) fn config_diagnostics(&self) -> &[DriConfigDiagnostic] {
+        &self.inner.config_diagnostics
+    }
+
+    #[inline]
+    fn config_diagnostic(&self, cfg: &GlConfig) -> Option<&DriConfigDiagnostic> {
         self.inner
-            .dri_configmap
-            .as_ref()
-            .unwrap()
-            .get(&hasher.finish())
-            .cloned()
+            .config_diagnostics
+            .iter()
+            .find(|d| d.fbconfig_id == cfg.fbconfig_id && d.visual_id == cfg.visual_id)
     }
 
     #[inline]
@@ -265,6 +396,27 @@ impl<Dpy> Dri3Screen<Dpy> {
         self.inner.dri_screen.expect("Failed to load DRI screen")
     }
 
+    /// Look up a GL function in the driver library. Drivers with glapi built in export
+    /// `_glapi_get_proc_address` themselves; otherwise, the driver may export the entry point
+    /// directly.
+    #[inline]
+    pub(crate) fn driver_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
+        type GetProcAddress = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+
+        let driver = &self.inner.driver;
+        let f = match unsafe { driver.symbol::<GetProcAddress>(b"_glapi_get_proc_address\0") } {
+            Some(get_proc_address) => unsafe { get_proc_address(name.as_ptr()) },
+            None => ptr::null_mut(),
+        };
+
+        NonNull::new(f)
+            .or_else(|| {
+                unsafe { driver.symbol::<*mut c_void>(name.to_bytes_with_nul()) }
+                    .and_then(NonNull::new)
+            })
+            .map(ProcAddress::from)
+    }
+
     #[inline]
     pub fn weak_ref(&self) -> WeakDri3ScreenRef<Dpy> {
         WeakDri3ScreenRef {
@@ -283,16 +435,61 @@ where
         scr: usize,
         visuals: Arc<[GlConfig]>,
         fbconfigs: Arc<[GlConfig]>,
+        driver_override: Option<&str>,
+        driver_dirs: &[PathBuf],
+        prime: Option<&str>,
     ) -> breadx::Result<Self> {
         // first, figure out which file descriptor corresponds to our screen
         let root = dpy.screens()[scr].root;
         let fd = dpy.open_dri3_immediate(root, 0)?;
 
-        // TODO: figure out if we need to use a different file descriptor for a different GPU
+        // if DRI_PRIME selects another GPU, try rendering on it, but hold onto the display GPU in
+        // case the other GPU's driver can't be used
+        if let Some(prime_fd) = prime.and_then(|prime| load::open_prime_fd(fd, prime)) {
+            match Self::with_fd(
+                scr,
+                prime_fd,
+                true,
+                visuals.clone(),
+                fbconfigs.clone(),
+                driver_override,
+                driver_dirs,
+            ) {
+                Ok(screen) => {
+                    unsafe { libc::close(fd) };
+                    return Ok(screen);
+                }
+                Err(e) => log_prime_fallback(&e),
+            }
+        }
+
+        Self::with_fd(
+            scr,
+            fd,
+            false,
+            visuals,
+            fbconfigs,
+            driver_override,
+            driver_dirs,
+        )
+    }
+
+    /// Create the screen on `fd`, which the screen takes ownership of.
+    #[inline]
+    fn with_fd(
+        scr: usize,
+        fd: c_int,
+        is_different_gpu: bool,
+        visuals: Arc<[GlConfig]>,
+        fbconfigs: Arc<[GlConfig]>,
+        driver_override: Option<&str>,
+        driver_dirs: &[PathBuf],
+    ) -> breadx::Result<Self> {
+        let fd_guard = close_on_drop(fd);
 
-        // then, open the the driver associated with the fd
+        // open the driver associated with the fd
         let mut extensions = vec![];
-        let driver = load::load_dri_driver(fd, &mut extensions)?;
+        let driver = load::load_dri_driver(fd, driver_override, driver_dirs, &mut extensions)?;
         extensions.push(ExtensionContainer(ptr::null()));
 
         // assign the extensions that we need to bootstrap
@@ -303,7 +500,7 @@ where
         let mut this = Arc::new(Dri3ScreenInner {
             driver,
             fd,
-            is_different_gpu: false,
+            is_different_gpu,
             dri_screen: None,
             core: core.0 as *const _,
             image_driver: image_driver.0 as *const _,
@@ -314,12 +511,16 @@ where
             renderer_query: ptr::null(),
             interop: ptr::null(),
             driver_configs: ptr::null_mut(),
-            dri_configmap: None,
+            visual_driconfigs: Vec::new(),
+            fbconfig_driconfigs: Vec::new(),
+            config_diagnostics: Vec::new(),
             drawable_map: ManuallyDrop::new(DashMap::new()),
             fbconfigs: fbconfigs.clone(),
             visuals: visuals.clone(),
             dropper: Dropper::<Dpy>::sync_dropper,
         });
+        // the screen closes the fd from here on
+        mem::forget(fd_guard);
 
         // use the image driver to actually create the screen
         let thisref = Arc::get_mut(&mut this).expect("Infallible Arc::get_mut()");
@@ -329,23 +530,8 @@ where
 
         // now we can load up the extension and additional configs
         thisref.get_extensions()?;
-        let mut extmap: HashMap<u64, NonNull<ffi::__DRIconfig>> = unsafe {
-            config::convert_configs(
-                ExtensionContainer(thisref.core as *const _),
-                &visuals,
-                thisref.driver_configs,
-            )
-        }
-        .collect();
-        extmap.extend(unsafe {
-            config::convert_configs(
-                ExtensionContainer(thisref.core as *const _),
-                &fbconfigs,
-                thisref.driver_configs,
-            )
-        });
-
-        thisref.dri_configmap = Some(extmap);
+        thisref.check_prime_support()?;
+        thisref.load_config_map(&visuals, &fbconfigs);
 
         Ok(Dri3Screen { inner: this.into() })
     }
@@ -356,8 +542,19 @@ where
         scr: usize,
         visuals: Arc<[GlConfig]>,
         fbconfigs: Arc<[GlConfig]>,
+        driver_override: Option<&str>,
+        driver_dirs: &[PathBuf],
+        prime: Option<&str>,
     ) -> breadx::Result<Self> {
-        Self::new_blocking(dpy, scr, visuals, fbconfigs)
+        Self::new_blocking(
+            dpy,
+            scr,
+            visuals,
+            fbconfigs,
+            driver_override,
+            driver_dirs,
+            prime,
+        )
     }
 
     /// Get the DRI drawable associated with an X11 drawable.
@@ -424,16 +621,65 @@ where
         scr: usize,
         visuals: Arc<[GlConfig]>,
         fbconfigs: Arc<[GlConfig]>,
+        driver_override: Option<&str>,
+        driver_dirs: &[PathBuf],
+        prime: Option<&str>,
     ) -> breadx::Result<Self> {
         // first, figure out which file descriptor corresponds to our screen
         let root = dpy.screens()[scr].root;
         let fd = dpy.open_dri3_immediate_async(root, 0).await?;
 
-        // TODO: figure out if we need to use a different file descriptor for a different GPU
+        // if DRI_PRIME selects another GPU, try rendering on it, but hold onto the display GPU in
+        // case the other GPU's driver can't be used
+        if let Some(prime_fd) = prime.and_then(|prime| load::open_prime_fd(fd, prime)) {
+            match Self::with_fd_async(
+                scr,
+                prime_fd,
+                true,
+                visuals.clone(),
+                fbconfigs.clone(),
+                driver_override,
+                driver_dirs,
+            )
+            .await
+            {
+                Ok(screen) => {
+                    unsafe { libc::close(fd) };
+                    return Ok(screen);
+                }
+                Err(e) => log_prime_fallback(&e),
+            }
+        }
+
+        Self::with_fd_async(
+            scr,
+            fd,
+            false,
+            visuals,
+            fbconfigs,
+            driver_override,
+            driver_dirs,
+        )
+        .await
+    }
 
-        // then, open the the driver associated with the fd
+    /// Create the screen on `fd`, which the screen takes ownership of, async redox.
+    #[inline]
+    async fn with_fd_async(
+        scr: usize,
+        fd: c_int,
+        is_different_gpu: bool,
+        visuals: Arc<[GlConfig]>,
+        fbconfigs: Arc<[GlConfig]>,
+        driver_override: Option<&str>,
+        driver_dirs: &[PathBuf],
+    ) -> breadx::Result<Self> {
+        let fd_guard = close_on_drop(fd);
+
+        // open the driver associated with the fd
         let mut extensions = vec![];
-        let driver = load::load_dri_driver_async(fd, &mut extensions).await?;
+        let driver =
+            load::load_dri_driver_async(fd, driver_override, driver_dirs, &mut extensions).await?;
         extensions.push(ExtensionContainer(ptr::null()));
 
         // assign the extensions that we need to bootstrap
@@ -444,7 +690,7 @@ where
         let mut this = Arc::new(Dri3ScreenInner {
             driver,
             fd,
-            is_different_gpu: false,
+            is_different_gpu,
             dri_screen: None,
             core: core.0 as *const _,
             image_driver: image_driver.0 as *const _,
@@ -455,12 +701,16 @@ where
             renderer_query: ptr::null(),
             interop: ptr::null(),
             driver_configs: ptr::null_mut(),
-            dri_configmap: None,
+            visual_driconfigs: Vec::new(),
+            fbconfig_driconfigs: Vec::new(),
+            config_diagnostics: Vec::new(),
             drawable_map: ManuallyDrop::new(DashMap::new()),
             fbconfigs: fbconfigs.clone(),
             visuals: visuals.clone(),
             dropper: Dropper::<Dpy>::async_dropper,
         });
+        // the screen closes the fd from here on
+        mem::forget(fd_guard);
 
         // use the image driver to actually create the screen
         let this = blocking::unblock(move || -> breadx::Result<Arc<Dri3ScreenInner<Dpy>>> {
@@ -471,24 +721,9 @@ where
 
             // now we can load up the extension
             thisref.get_extensions()?;
+            thisref.check_prime_support()?;
 
-            let mut extmap: HashMap<u64, NonNull<ffi::__DRIconfig>> = unsafe {
-                config::convert_configs(
-                    ExtensionContainer(thisref.core as *const _),
-                    &visuals,
-                    thisref.driver_configs,
-                )
-            }
-            .collect();
-            extmap.extend(unsafe {
-                config::convert_configs(
-                    ExtensionContainer(thisref.core as *const _),
-                    &fbconfigs,
-                    thisref.driver_configs,
-                )
-            });
-
-            thisref.dri_configmap = Some(extmap);
+            thisref.load_config_map(&visuals, &fbconfigs);
 
             Ok(this)
         })
@@ -643,7 +878,32 @@ where
         'a: 'future,
         'b: 'future,
     {
-        Box::pin(async { unimplemented!() })
+        Box::pin(async move {
+            let cur_context = GlContext::<Dpy>::get_async().await;
+            if let Some(context) = cur_context.as_ref().and_then(|m| promote_anyarc_ref(m)) {
+                if let ContextDispatch::Dri3(d3) = context.dispatch() {
+                    let drawable = self.fetch_dri_drawable_async(dpy, d3, drawable).await?;
+                    let mut flush_flags = ffi::__DRI2_FLUSH_DRAWABLE;
+                    if flush {
+                        flush_flags |= ffi::__DRI2_FLUSH_CONTEXT;
+                    }
+                    return drawable
+                        .swap_buffers_msc_async(
+                            target_msc,
+                            divisor,
+                            remainder,
+                            flush_flags,
+                            &[],
+                            false,
+                        )
+                        .await;
+                }
+            }
+
+            Err(breadx::BreadError::StaticMsg(
+                "Unable to get context for swapping buffer",
+            ))
+        })
     }
 }
 
@@ -659,14 +919,15 @@ where
         //         set.
         unsafe { ManuallyDrop::drop(&mut screen.drawable_map) };
 
-        // destroy the screen
-        if let Some(destroy_screen) = unsafe { *screen.core }.destroyScreen.take() {
-            unsafe { (destroy_screen)(screen.dri_screen.unwrap().as_ptr()) };
+        // destroy the screen, if we got as far as creating it
+        if let (Some(dri_screen), Some(destroy_screen)) =
+            (screen.dri_screen, unsafe { (*screen.core).destroyScreen })
+        {
+            unsafe { (destroy_screen)(dri_screen.as_ptr()) };
         }
 
         // drop the configurations
-        unsafe { iter_configs(screen.driver_configs, |ext| libc::free(ext as *mut _)) };
-        unsafe { libc::free(screen.driver_configs as *mut _) };
+        unsafe { free_configs(screen.driver_configs) };
 
         unsafe { libc::close(screen.fd) };
     }
@@ -682,17 +943,18 @@ where
 
         let screen_fd = screen.fd;
         let driver_configs = unsafe { ThreadSafe::new(screen.driver_configs) };
-        let dri_screen = unsafe { ThreadSafe::new(screen.dri_screen.unwrap().as_ptr()) };
+        let dri_screen = unsafe { ThreadSafe::new(screen.dri_screen) };
         let destroy_screen =
             unsafe { ThreadSafe::new((*screen.core).destroyScreen.as_ref().cloned()) };
 
         offload::offload(blocking::unblock(move || {
-            if let Some(destroy_screen) = destroy_screen.into_inner() {
-                unsafe { (destroy_screen)(dri_screen.into_inner()) };
+            if let (Some(dri_screen), Some(destroy_screen)) =
+                (dri_screen.into_inner(), destroy_screen.into_inner())
+            {
+                unsafe { (destroy_screen)(dri_screen.as_ptr()) };
             }
 
-            unsafe { iter_configs(*driver_configs, |ext| libc::free(ext as *mut _)) };
-            unsafe { libc::free(*driver_configs as *mut _) };
+            unsafe { free_configs(*driver_configs) };
             unsafe { libc::close(screen_fd) };
         }));
     }
@@ -716,3 +978,120 @@ where
         cfgs = unsafe { cfgs.offset(1) };
     }
 }
+
+/// Free the driver configs returned by `createNewScreen2`, if there are any.
+#[inline]
+unsafe fn free_configs(cfgs: *mut *const ffi::__DRIconfig) {
+    if cfgs.is_null() {
+        return;
+    }
+
+    unsafe { iter_configs(cfgs, |ext| libc::free(ext as *mut _)) };
+    unsafe { libc::free(cfgs as *mut _) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempDir;
+    use std::{
+        fs::{self, File},
+        io::{self, Read},
+        os::unix::{
+            fs::symlink,
+            io::{FromRawFd, RawFd},
+            net::UnixStream,
+        },
+        path::Path,
+    };
+
+    /// A pipe's read end and write end. Reading from the read end hits end of file once the write
+    /// end is closed, which can't be confused with another file reusing the write end's number.
+    fn pipe() -> (File, RawFd) {
+        let mut fds = [0; 2];
+        assert_eq!(
+            unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) },
+            0
+        );
+        (unsafe { File::from_raw_fd(fds[0]) }, fds[1])
+    }
+
+    /// Whether the write end of the pipe has been closed.
+    fn write_end_closed(read: &mut File) -> bool {
+        match read.read(&mut [0]) {
+            Ok(0) => true,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => false,
+            res => panic!("Unexpected read from an empty pipe: {:?}", res),
+        }
+    }
+
+    /// The shared library this process loaded libc from, which is a real library that isn't a
+    /// DRI driver.
+    fn libc_path() -> PathBuf {
+        fs::read_to_string("/proc/self/maps")
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(5))
+            .map(Path::new)
+            .find(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("libc.so") || name.starts_with("libc-"))
+            })
+            .expect("libc isn't mapped")
+            .to_path_buf()
+    }
+
+    /// Try to create a screen with the `breadglx_fake` driver from `driver_dirs`, checking that the
+    /// screen's fd is closed afterwards.
+    fn fake_driver_screen(
+        driver_dirs: &[PathBuf],
+    ) -> breadx::Result<Dri3Screen<Display<UnixStream>>> {
+        let (mut read, fd) = pipe();
+        let res = Dri3Screen::with_fd(
+            0,
+            fd,
+            false,
+            Vec::new().into(),
+            Vec::new().into(),
+            Some("breadglx_fake"),
+            driver_dirs,
+        );
+        // the screen owned the fd, so it should be closed even though creating the screen failed
+        assert!(write_end_closed(&mut read));
+        res
+    }
+
+    #[test]
+    fn invalid_driver() {
+        // a driver directory with a file named like a driver that isn't a library
+        let drivers = TempDir::new("breadglx-dri");
+        let fake_driver = drivers.path().join("breadglx_fake_dri.so");
+        fs::write(&fake_driver, b"not a shared library").unwrap();
+        match fake_driver_screen(&[drivers.path().to_path_buf()]) {
+            Err(breadx::BreadError::LoadLibraryFailed(_)) => (),
+            res => panic!("Expected the driver to not load, got {:?}", res.map(|_| ())),
+        }
+
+        // a library that loads, but isn't a DRI driver; getting past loading it means the driver
+        // was found in our directory
+        fs::remove_file(&fake_driver).unwrap();
+        symlink(libc_path(), &fake_driver).unwrap();
+        match fake_driver_screen(&[drivers.path().to_path_buf()]) {
+            Err(breadx::BreadError::Msg(msg)) => assert!(msg.contains("missing required symbols")),
+            res => panic!(
+                "Expected the driver to lack DRI symbols, got {:?}",
+                res.map(|_| ())
+            ),
+        }
+
+        // the same library isn't found if its directory isn't searched
+        match fake_driver_screen(&[]) {
+            Err(breadx::BreadError::LoadLibraryFailed(_)) => (),
+            res => panic!(
+                "Expected the driver to be missing, got {:?}",
+                res.map(|_| ())
+            ),
+        }
+    }
+}
@@ -1,22 +1,48 @@
 // MIT/Apache2 License
 
 use super::ffi;
-use crate::{
-    cstr::{const_cstr, ConstCstr},
-    dll::Dll,
-};
-use std::{ffi::CString, ptr, slice, str};
+use crate::dll::Dll;
+use std::{ptr, slice, str};
 
-const DRI_EXTENSIONS_NAME: ConstCstr<'static> = const_cstr(ffi::__DRI_DRIVER_EXTENSIONS);
+type GetExtensions = unsafe extern "C" fn() -> *const *const ffi::__DRIextension;
 
+/// The entry points of a DRI driver. A driver exports a function returning its extensions, named
+/// after the driver, or the extensions themselves (or both).
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct DriverFunctions {
+    pub get_extensions: Option<GetExtensions>,
+    pub extensions: Option<*const *const ffi::__DRIextension>,
+}
+
+impl DriverFunctions {
+    /// Resolve the entry points of the driver with the given name.
+    #[inline]
+    pub(crate) fn resolve(dll: &Dll, driver_name: &str) -> breadx::Result<Self> {
+        let get_ext_name = get_extensions_name(driver_name);
+        let this = Self {
+            get_extensions: unsafe { dll.symbol(&get_ext_name) },
+            extensions: unsafe { dll.symbol(ffi::__DRI_DRIVER_EXTENSIONS) },
+        };
+
+        if this.get_extensions.is_none() && this.extensions.is_none() {
+            return Err(breadx::BreadError::Msg(format!(
+                "DRI driver {} is missing required symbols: {}, {}",
+                driver_name,
+                str::from_utf8(&get_ext_name[..get_ext_name.len() - 1]).unwrap_or("?"),
+                str::from_utf8(
+                    &ffi::__DRI_DRIVER_EXTENSIONS[..ffi::__DRI_DRIVER_EXTENSIONS.len() - 1]
+                )
+                .unwrap_or("?"),
+            )));
+        }
+
+        Ok(this)
+    }
+}
+
+/// The null-terminated name of the function returning a driver's extensions.
 #[inline]
-pub fn load_extensions<'a, 'b>(
-    dll: &'a Dll,
-    driver_name: &str,
-) -> breadx::Result<&'b [*const ffi::__DRIextension]>
-where
-    'b: 'a,
-{
+fn get_extensions_name(driver_name: &str) -> Vec<u8> {
     let mut get_ext_name: Vec<u8> =
         Vec::with_capacity(ffi::__DRI_DRIVER_GET_EXTENSIONS.len() + driver_name.len() + 1);
     get_ext_name.extend_from_slice(ffi::__DRI_DRIVER_GET_EXTENSIONS);
@@ -30,20 +56,29 @@ where
         }
     });
 
-    // try to use the get_extensions method
-    let get_ext_name =
-        CString::new(get_ext_name).expect("Failed to construct get_extensions method name.");
-    let get_extensions: Option<unsafe extern "C" fn() -> *const *const ffi::__DRIextension> =
-        unsafe { dll.function(&get_ext_name) };
+    get_ext_name.push(0);
+    get_ext_name
+}
+
+#[inline]
+pub fn load_extensions<'a, 'b>(
+    dll: &'a Dll,
+    driver_name: &str,
+) -> breadx::Result<&'b [*const ffi::__DRIextension]>
+where
+    'b: 'a,
+{
+    let driver = DriverFunctions::resolve(dll, driver_name)?;
 
-    let mut extensions: *const *const ffi::__DRIextension = match get_extensions {
+    // try to use the get_extensions method
+    let mut extensions: *const *const ffi::__DRIextension = match driver.get_extensions {
         Some(get_extensions) => unsafe { get_extensions() },
         None => ptr::null(),
     };
 
     // if get_extensions didn't pan out, try loading the extensions directly
     if extensions.is_null() {
-        extensions = match unsafe { dll.function(&DRI_EXTENSIONS_NAME) } {
+        extensions = match driver.extensions {
             Some(exts) => exts,
             None => {
                 return Err(breadx::BreadError::StaticMsg(
@@ -3,21 +3,26 @@
 //! Attempt to load the DRI library, given that the DRM library is present.
 
 mod pci_table;
+mod prime;
+mod sysfs;
+
+pub(crate) use prime::open_prime_fd;
 
 use crate::{
-    cstr::{const_cstr, ConstCstr},
     dll::Dll,
     dri::{ffi, ExtensionContainer},
     mesa,
 };
 use std::{
     borrow::Cow,
-    ffi::{CStr, OsString},
-    mem,
+    collections::HashSet,
+    env,
+    ffi::{c_void, CStr, OsString},
     os::{
-        raw::{c_char, c_int},
+        raw::{c_char, c_int, c_ulong},
         unix::ffi::OsStringExt,
     },
+    path::PathBuf,
     ptr::{self, NonNull},
     slice,
 };
@@ -28,9 +33,12 @@ const DRM_HOST1X_DEVICE_NAME_LEN: usize = 512;
 // FFI Naughtiness
 
 const DRM_BUS_PCI: c_int = 0;
+const DRM_BUS_USB: c_int = 1;
+const DRM_BUS_PLATFORM: c_int = 2;
+const DRM_BUS_HOST1X: c_int = 3;
 
 #[repr(C)]
-struct DrmVersion {
+pub(crate) struct DrmVersion {
     version_major: c_int,
     version_minor: c_int,
     version_patchlevel: c_int,
@@ -108,7 +116,7 @@ union DrmDeviceInfo {
 }
 
 #[repr(C)]
-struct DrmDevice {
+pub(crate) struct DrmDevice {
     nodes: *mut *mut c_char,
     available_nodes: c_int,
     bustype: c_int,
@@ -116,26 +124,29 @@ struct DrmDevice {
     deviceinfo: DrmDeviceInfo,
 }
 
-type DrmGetVersion = unsafe extern "C" fn(c_int) -> *mut DrmVersion;
-const DRM_GET_VERSION: ConstCstr<'static> = const_cstr(&*b"drmGetVersion\0");
-
-type DrmFreeVersion = unsafe extern "C" fn(*mut DrmVersion);
-const DRM_FREE_VERSION: ConstCstr<'static> = const_cstr(&*b"drmFreeVersion\0");
-
-type DrmGetDevice2 = unsafe extern "C" fn(c_int, u32, *mut *mut DrmDevice) -> c_int;
-const DRM_GET_DEVICE2: ConstCstr<'static> = const_cstr(&*b"drmGetDevice2\0");
-
-type DrmFreeDevice = unsafe extern "C" fn(*mut *mut DrmDevice);
-const DRM_FREE_DEVICE: ConstCstr<'static> = const_cstr(&*b"drmFreeDevice\0");
+function_table! {
+    /// Functions from libdrm, used to find out which driver to load for a device.
+    #[derive(Debug, Copy, Clone)]
+    pub(crate) struct DrmFunctions {
+        get_version: unsafe extern "C" fn(c_int) -> *mut DrmVersion = "drmGetVersion",
+        free_version: unsafe extern "C" fn(*mut DrmVersion) = "drmFreeVersion",
+        command_write_read: unsafe extern "C" fn(c_int, c_ulong, *mut c_void, c_ulong) -> c_int =
+            "drmCommandWriteRead",
+    }
+    optional {
+        // added in libdrm 2.4.74; without it, we fall back to the kernel driver's name
+        get_device2: unsafe extern "C" fn(c_int, u32, *mut *mut DrmDevice) -> c_int =
+            "drmGetDevice2",
+        free_device: unsafe extern "C" fn(*mut *mut DrmDevice) = "drmFreeDevice",
+    }
+}
 
 #[inline]
-pub(crate) fn driver_name_from_kernel_name(drm: &Dll, fd: c_int) -> breadx::Result<String> {
-    let drmGetVersion: DrmGetVersion =
-        unsafe { drm.function(&DRM_GET_VERSION) }.expect("drmGetVersion not present");
-    let drmFreeVersion: DrmFreeVersion =
-        unsafe { drm.function(&DRM_FREE_VERSION) }.expect("drmFreeVersion not present");
-
-    let version: *mut DrmVersion = unsafe { (drmGetVersion)(fd) };
+pub(crate) fn driver_name_from_kernel_name(
+    drm: &DrmFunctions,
+    fd: c_int,
+) -> breadx::Result<String> {
+    let version: *mut DrmVersion = unsafe { (drm.get_version)(fd) };
 
     if version.is_null() {
         return Err(breadx::BreadError::StaticMsg(
@@ -143,19 +154,17 @@ pub(crate) fn driver_name_from_kernel_name(drm: &Dll, fd: c_int) -> breadx::Resu
         ));
     }
 
-    // convert the name to a slice of c_chars
+    // convert the name to a slice of c_chars. libdrm NUL-terminates the name, but doesn't count
+    // the terminator in its length
     let name = unsafe {
-        slice::from_raw_parts(
-            (*version).name as *const u8,
-            (*version).name_len as usize + 1,
-        )
+        slice::from_raw_parts((*version).name as *const u8, (*version).name_len as usize)
     };
 
     // convert the name to its OsString equivalent
     let name = OsString::from_vec(name.to_vec());
 
     // free the version data
-    unsafe { (drmFreeVersion)(version) };
+    unsafe { (drm.free_version)(version) };
 
     Ok(name
         .to_str()
@@ -163,12 +172,27 @@ pub(crate) fn driver_name_from_kernel_name(drm: &Dll, fd: c_int) -> breadx::Resu
         .to_string())
 }
 
+/// What libdrm knows about the device behind a file descriptor.
+#[derive(Debug)]
+enum DrmDeviceIds {
+    Pci {
+        vendor_id: c_int,
+        chip_id: c_int,
+    },
+    /// A platform or host1x device, along with its device tree `compatible` strings.
+    Platform(Vec<String>),
+    Usb,
+}
+
 #[inline]
-fn ids_from_pci_id(drm: &Dll, fd: c_int) -> Option<(c_int, c_int)> {
-    let drmGetDevice2: DrmGetDevice2 =
-        unsafe { drm.function(&DRM_GET_DEVICE2) }.expect("drmGetDevice2 not present");
-    let drmFreeDevice: DrmFreeDevice =
-        unsafe { drm.function(&DRM_FREE_DEVICE) }.expect("drmFreeDevice not present");
+fn drm_device_ids(drm: &DrmFunctions, fd: c_int) -> Option<DrmDeviceIds> {
+    let (drmGetDevice2, drmFreeDevice) = match (drm.get_device2, drm.free_device) {
+        (Some(get_device2), Some(free_device)) => (get_device2, free_device),
+        _ => {
+            log::debug!("libdrm is too old to have drmGetDevice2");
+            return None;
+        }
+    };
 
     let mut device: *mut DrmDevice = ptr::null_mut();
 
@@ -180,24 +204,68 @@ fn ids_from_pci_id(drm: &Dll, fd: c_int) -> Option<(c_int, c_int)> {
     let mut device_ptr = NonNull::new(device).expect("Infallible non-null ptr conversion");
     let device = unsafe { device_ptr.as_mut() };
 
-    if device.bustype != DRM_BUS_PCI {
-        log::warn!("Failed to get DRM PCI bus");
-        unsafe { (drmFreeDevice)(&mut device_ptr as *mut NonNull<_> as *mut _) };
-        return None;
-    }
-
-    let vendor_id = unsafe { (*device.deviceinfo.pci).vendor_id };
-    let chip_id = unsafe { (*device.deviceinfo.pci).device_id };
+    let ids = match device.bustype {
+        DRM_BUS_PCI => Some(DrmDeviceIds::Pci {
+            vendor_id: unsafe { (*device.deviceinfo.pci).vendor_id } as _,
+            chip_id: unsafe { (*device.deviceinfo.pci).device_id } as _,
+        }),
+        DRM_BUS_PLATFORM => Some(DrmDeviceIds::Platform(unsafe {
+            compatible_strings((*device.deviceinfo.platform).compatible)
+        })),
+        DRM_BUS_HOST1X => Some(DrmDeviceIds::Platform(unsafe {
+            compatible_strings((*device.deviceinfo.host1x).compatible)
+        })),
+        DRM_BUS_USB => Some(DrmDeviceIds::Usb),
+        bustype => {
+            log::warn!("Unknown DRM bus type {}", bustype);
+            None
+        }
+    };
     unsafe { (drmFreeDevice)(&mut device_ptr as *mut NonNull<_> as *mut _) };
 
-    Some((vendor_id as _, chip_id as _))
+    ids
 }
 
+/// Collect a null-terminated list of `compatible` strings.
 #[inline]
-fn driver_name_from_pci(drm: &Dll, fd: c_int) -> breadx::Result<&'static str> {
-    let (vendor_id, chip_id) =
-        ids_from_pci_id(drm, fd).ok_or(breadx::BreadError::StaticMsg("Failed to load PCI IDs"))?;
+unsafe fn compatible_strings(mut list: *mut *mut c_char) -> Vec<String> {
+    let mut strings = vec![];
+    if list.is_null() {
+        return strings;
+    }
 
+    while !(*list).is_null() {
+        strings.push(CStr::from_ptr(*list).to_string_lossy().into_owned());
+        list = list.add(1);
+    }
+    strings
+}
+
+#[inline]
+fn driver_name_from_device(drm: &DrmFunctions, fd: c_int) -> breadx::Result<Cow<'static, str>> {
+    match drm_device_ids(drm, fd)
+        .ok_or(breadx::BreadError::StaticMsg("Failed to get DRM device"))?
+    {
+        DrmDeviceIds::Pci { vendor_id, chip_id } => {
+            driver_name_from_pci(drm, fd, vendor_id, chip_id).map(Cow::Borrowed)
+        }
+        DrmDeviceIds::Platform(compatible) => {
+            log::debug!("DRM platform device is compatible with {:?}", compatible);
+            driver_name_from_platform(drm, fd, &compatible)
+        }
+        DrmDeviceIds::Usb => Err(breadx::BreadError::StaticMsg(
+            "USB DRM devices do not have a DRI driver",
+        )),
+    }
+}
+
+#[inline]
+fn driver_name_from_pci(
+    drm: &DrmFunctions,
+    fd: c_int,
+    vendor_id: c_int,
+    chip_id: c_int,
+) -> breadx::Result<&'static str> {
     pci_table::PCI_TABLE
         .iter()
         .find_map(|entry| {
@@ -225,42 +293,293 @@ fn driver_name_from_pci(drm: &Dll, fd: c_int) -> breadx::Result<&'static str> {
         .ok_or_else(|| breadx::BreadError::StaticMsg("Unable to match ID to driver"))
 }
 
-const POSSIBLE_DRI_LEN: usize = 5;
+/// Match a platform device's `compatible` strings to the driver for its GPU.
+#[inline]
+fn driver_name_from_compatible(compatible: &[String]) -> Option<&'static str> {
+    compatible.iter().find_map(|c| {
+        let c = c.as_str();
+        Some(
+            if c.starts_with("arm,mali-utgard") || c == "arm,mali-400" || c == "arm,mali-450" {
+                "lima"
+            } else if is_panfrost_compatible(c) {
+                "panfrost"
+            } else if c.starts_with("vivante,gc") {
+                "etnaviv"
+            } else if c.starts_with("brcm,") && c.ends_with("-v3d") {
+                "v3d"
+            } else if c.starts_with("brcm,") && (c.ends_with("-vc4") || c.ends_with("-vc5")) {
+                "vc4"
+            } else if c.starts_with("qcom,adreno") {
+                "msm"
+            } else if c.starts_with("nvidia,tegra") {
+                "tegra"
+            } else {
+                return None;
+            },
+        )
+    })
+}
+
+/// Whether a `compatible` string names a Midgard, Bifrost or Valhall GPU. Mali display controllers
+/// (`arm,mali-dp500` and friends, and komeda's `arm,mali-d71`) share the prefix, but go through kmsro.
+#[inline]
+fn is_panfrost_compatible(compatible: &str) -> bool {
+    match compatible.strip_prefix("arm,mali-") {
+        Some(model) => {
+            ["t6", "t7", "t8"].iter().any(|gen| model.starts_with(gen))
+                || model == "bifrost"
+                || model.starts_with("valhall")
+        }
+        None => false,
+    }
+}
+
+/// Display controllers without a GPU of their own. Mesa installs a driver named after each of
+/// these that forwards rendering to the GPU's render node ("kmsro", for KMS render-only).
+const KMSRO_KERNEL_DRIVERS: &[&str] = &[
+    "armada-drm",
+    "exynos",
+    "hdlcd",
+    "hx8357d",
+    "ili9225",
+    "ili9341",
+    "imx-dcss",
+    "imx-drm",
+    "imx-lcdif",
+    "ingenic-drm",
+    "kirin",
+    "komeda",
+    "mali-dp",
+    "mcde",
+    "mediatek",
+    "meson",
+    "mi0283qt",
+    "mxsfb-drm",
+    "pl111",
+    "rcar-du",
+    "repaper",
+    "rockchip",
+    "st7586",
+    "st7735r",
+    "stm",
+    "sun4i-drm",
+];
+
+#[inline]
+fn driver_name_from_platform(
+    drm: &DrmFunctions,
+    fd: c_int,
+    compatible: &[String],
+) -> breadx::Result<Cow<'static, str>> {
+    if let Some(driver) = driver_name_from_compatible(compatible) {
+        return Ok(driver.into());
+    }
+
+    // display controllers use the kmsro driver installed under their own name
+    let kernel_driver = driver_name_from_kernel_name(drm, fd)?;
+    if KMSRO_KERNEL_DRIVERS.contains(&kernel_driver.as_str()) {
+        log::debug!("Using kmsro for display controller {}", kernel_driver);
+        Ok(kernel_driver.into())
+    } else {
+        Err(breadx::BreadError::StaticMsg(
+            "Unable to match compatible string to driver",
+        ))
+    }
+}
+
+/// Kernel drivers that are only ever paired with one Mesa driver.
+const KERNEL_DRIVERS: &[(&str, &str)] = &[
+    ("amdgpu", "radeonsi"),
+    ("xe", "iris"),
+    ("virtio_gpu", "virtio_gpu"),
+    ("vmwgfx", "vmwgfx"),
+    ("msm", "msm"),
+    ("v3d", "v3d"),
+    ("vc4", "vc4"),
+    ("panfrost", "panfrost"),
+    ("lima", "lima"),
+    ("etnaviv", "etnaviv"),
+    ("tegra", "tegra"),
+];
+
+/// Figure out the driver from the kernel driver and PCI IDs that sysfs reports for the device. This
+/// works for hardware newer than our PCI ID lists, since it only needs the lists to tell apart older
+/// generations of hardware that share a kernel driver.
+#[inline]
+fn driver_name_from_sysfs(fd: c_int) -> Option<&'static str> {
+    let device = sysfs::SysfsDevice::for_fd(fd)?;
+    log::debug!("sysfs reports device {:?}", device);
+    driver_name_from_sysfs_device(&device)
+}
+
+#[inline]
+fn driver_name_from_sysfs_device(device: &sysfs::SysfsDevice) -> Option<&'static str> {
+    let kernel_driver = device.driver.as_deref()?;
+
+    if let Some((_, driver)) = KERNEL_DRIVERS.iter().find(|(kd, _)| *kd == kernel_driver) {
+        return Some(driver);
+    }
+
+    match kernel_driver {
+        // Gen 2-7 hardware uses the older drivers; everything since uses iris
+        "i915" => {
+            let device_id = device.device_id?;
+            Some(
+                pci_table::PCI_TABLE
+                    .iter()
+                    .filter(|entry| entry.id == INTEL_VENDOR_ID && !entry.pci_ids.is_empty())
+                    .find(|entry| entry.pci_ids.contains(&device_id))
+                    .map_or("iris", |entry| entry.driver),
+            )
+        }
+        // radeon and nouveau need the PCI table and the DRM device to pick a driver
+        _ => None,
+    }
+}
+
+const INTEL_VENDOR_ID: c_int = 0x8086;
 const TARGET_TRIPLE: &str = env!("TARGET");
 
+/// Environment variables containing colon-separated lists of directories to search for DRI
+/// drivers in, before the default directories. `LIBGL_DRIVERS_DIR` is the older name.
+const DRIVER_PATH_VARS: &[&str] = &["LIBGL_DRIVERS_PATH", "LIBGL_DRIVERS_DIR"];
+
+/// Tell whether or not we can trust the environment. Like Mesa, we ignore the driver path
+/// variables in setuid/setgid programs, since they would allow loading arbitrary code.
+#[inline]
+fn environment_trusted() -> bool {
+    unsafe { libc::getuid() == libc::geteuid() && libc::getgid() == libc::getegid() }
+}
+
+/// The Debian-style multiarch tuple for the target, e.g. `x86_64-linux-gnu`.
+#[inline]
+fn multiarch_tuple() -> String {
+    let tuple = TARGET_TRIPLE.replace("unknown-", "");
+    match tuple.split_once('-') {
+        Some(("i586", rest)) | Some(("i686", rest)) => format!("i386-{}", rest),
+        _ => tuple,
+    }
+}
+
+/// The directories to look for DRI drivers in, in the order they should be searched.
+#[inline]
+pub(crate) fn driver_search_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+
+    // directories from the environment come first
+    if environment_trusted() {
+        DRIVER_PATH_VARS
+            .iter()
+            .filter_map(env::var_os)
+            .for_each(|paths| {
+                dirs.extend(env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()))
+            });
+    } else if DRIVER_PATH_VARS
+        .iter()
+        .any(|var| env::var_os(var).is_some())
+    {
+        log::warn!("Ignoring LIBGL_DRIVERS_PATH in a setuid/setgid program");
+    }
+
+    let tuple = multiarch_tuple();
+    for prefix in &["/usr", "/usr/local"] {
+        // Debian-style multiarch
+        dirs.push(format!("{}/lib/{}/dri", prefix, tuple).into());
+        // Fedora-style lib64
+        if cfg!(target_pointer_width = "64") {
+            dirs.push(format!("{}/lib64/dri", prefix).into());
+        }
+        dirs.push(format!("{}/lib/dri", prefix).into());
+    }
+
+    // Flatpak GL extensions and NixOS
+    dirs.push(format!("/usr/lib/{}/GL/default/lib/dri", tuple).into());
+    dirs.push("/run/opengl-driver/lib/dri".into());
+
+    // don't try the same directory twice
+    let mut seen = HashSet::with_capacity(dirs.len());
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+/// The paths that the DRI driver with the given name may be loaded from, given the directories to
+/// search.
+#[inline]
+fn dri_lib_name(name: &str, driver_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let filename = format!("{}_dri.so", name);
+    let mut paths: Vec<PathBuf> = driver_dirs.iter().map(|dir| dir.join(&filename)).collect();
+    // last resort: let the dynamic linker search for it
+    paths.push(filename.into());
+    paths
+}
+
+/// The driver that renders in software on any DRM device. This is used if the device's own driver
+/// can't be loaded.
+const SWRAST_DRIVER: &str = "kms_swrast";
+
+/// Figure out the name of the driver for the device behind the file descriptor.
+#[inline]
+fn driver_name_for_fd(fd: c_int) -> breadx::Result<Cow<'static, str>> {
+    if let Some(driver) = driver_name_from_sysfs(fd) {
+        return Ok(driver.into());
+    }
+
+    let drm = mesa::drm()?;
+    match driver_name_from_device(drm, fd) {
+        Ok(driver) => Ok(driver),
+        Err(_) => Ok(driver_name_from_kernel_name(drm, fd)?.into()),
+    }
+}
+
+/// Load the driver with the given name, along with its extensions.
 #[inline]
-fn dri_lib_name(name: &str) -> [String; POSSIBLE_DRI_LEN] {
-    let target_triple = TARGET_TRIPLE.replace("unknown-", "");
+fn open_driver(
+    driver_name: &str,
+    driver_dirs: &[PathBuf],
+) -> breadx::Result<(Dll, Vec<ExtensionContainer>)> {
+    let dll = Dll::load("DRI", &dri_lib_name(driver_name, driver_dirs))?;
+    let extensions = super::extensions::load_extensions(&dll, driver_name)?
+        .iter()
+        .map(|ext| ExtensionContainer(*ext))
+        .collect();
+    Ok((dll, extensions))
+}
 
-    [
-        format!("/usr/lib/dri/{}_dri.so", name),
-        format!("/usr/local/lib/dri/{}_dri.so", name),
-        format!("/usr/lib/{}/dri/{}_dri.so", &target_triple, name),
-        format!("/usr/local/lib/{}/dri/{}_dri.so", target_triple, name),
-        format!("{}_dri.so", name),
-    ]
+/// Load the driver for the device behind the file descriptor. If the device's driver can't be found
+/// or loaded, fall back to the software renderer. An overridden driver is never replaced.
+#[inline]
+fn open_driver_for_fd(
+    fd: c_int,
+    driver_override: Option<&str>,
+    driver_dirs: &[PathBuf],
+) -> breadx::Result<(Dll, Vec<ExtensionContainer>)> {
+    if let Some(driver) = driver_override {
+        log::info!("Overriding DRI driver with {}", driver);
+        return open_driver(driver, driver_dirs);
+    }
+
+    match driver_name_for_fd(fd).and_then(|driver| open_driver(&driver, driver_dirs)) {
+        Ok(driver) => Ok(driver),
+        Err(e) => {
+            log::warn!(
+                "Unable to load the DRI driver for this device, falling back to {}: {:?}",
+                SWRAST_DRIVER,
+                e
+            );
+            open_driver(SWRAST_DRIVER, driver_dirs)
+        }
+    }
 }
 
 #[inline]
 pub(crate) fn load_dri_driver(
     fd: c_int,
+    driver_override: Option<&str>,
+    driver_dirs: &[PathBuf],
     extensions: &mut Vec<ExtensionContainer>,
 ) -> breadx::Result<Dll> {
-    let drm = mesa::drm()?;
-    let driver_name: Cow<'static, str> = match driver_name_from_pci(drm, fd) {
-        Ok(driver) => driver.into(),
-        Err(_) => driver_name_from_kernel_name(drm, fd)?.into(),
-    };
-
-    let dlls = dri_lib_name(&driver_name);
-    let dll = Dll::load("DRI", &dlls)?;
-
-    extensions.extend(
-        super::extensions::load_extensions(&dll, &driver_name)?
-            .into_iter()
-            .map(|ext| ExtensionContainer(*ext))
-            .collect::<Vec<_>>(),
-    );
+    let (dll, exts) = open_driver_for_fd(fd, driver_override, driver_dirs)?;
+    extensions.extend(exts);
     Ok(dll)
 }
 
@@ -268,35 +587,223 @@ pub(crate) fn load_dri_driver(
 #[inline]
 pub(crate) async fn load_dri_driver_async(
     fd: c_int,
+    driver_override: Option<&str>,
+    driver_dirs: &[PathBuf],
     extensions: &mut Vec<ExtensionContainer>,
 ) -> breadx::Result<Dll> {
-    let drm = mesa::drm_async().await?;
-    let driver_name: Cow<'static, str> = {
-        match blocking::unblock(move || driver_name_from_pci(drm, fd)).await {
-            Ok(driver) => driver.into(),
-            Err(_) => blocking::unblock(move || driver_name_from_kernel_name(drm, fd))
-                .await?
-                .into(),
+    let driver_override = driver_override.map(str::to_string);
+    let driver_dirs = driver_dirs.to_vec();
+    let (dll, exts) =
+        blocking::unblock(move || open_driver_for_fd(fd, driver_override.as_deref(), &driver_dirs))
+            .await?;
+    extensions.extend(exts);
+    Ok(dll)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::auto::pci_ids;
+    use std::ffi::CString;
+
+    /// The kernel drivers behind the file descriptors that `fake_drm` knows about, indexed by the
+    /// file descriptor.
+    const FAKE_KERNEL_DRIVERS: &[&str] = &["i915", "xe", "rockchip", "mali-dp"];
+
+    /// The file descriptor that `fake_drm` reports the given kernel driver for.
+    pub(crate) fn fake_fd(kernel_driver: &str) -> c_int {
+        FAKE_KERNEL_DRIVERS
+            .iter()
+            .position(|kd| *kd == kernel_driver)
+            .expect("no fake file descriptor for that kernel driver") as _
+    }
+
+    unsafe extern "C" fn fake_get_version(fd: c_int) -> *mut DrmVersion {
+        let name = match FAKE_KERNEL_DRIVERS.get(fd as usize) {
+            Some(name) => CString::new(*name).unwrap(),
+            None => return ptr::null_mut(),
+        };
+
+        // like libdrm, the name is NUL-terminated and the length doesn't include the terminator
+        Box::into_raw(Box::new(DrmVersion {
+            version_major: 1,
+            version_minor: 0,
+            version_patchlevel: 0,
+            name_len: name.as_bytes().len() as _,
+            name: name.into_raw(),
+            date_len: 0,
+            date: ptr::null_mut(),
+            desc_len: 0,
+            desc: ptr::null_mut(),
+        }))
+    }
+
+    unsafe extern "C" fn fake_free_version(version: *mut DrmVersion) {
+        let version = Box::from_raw(version);
+        drop(CString::from_raw(version.name));
+    }
+
+    unsafe extern "C" fn fake_command_write_read(
+        _fd: c_int,
+        _index: c_ulong,
+        _data: *mut c_void,
+        _size: c_ulong,
+    ) -> c_int {
+        -libc::EINVAL
+    }
+
+    /// A libdrm that reports the kernel drivers of the file descriptors from `fake_fd`.
+    pub(crate) fn fake_drm() -> DrmFunctions {
+        DrmFunctions {
+            get_version: fake_get_version,
+            free_version: fake_free_version,
+            command_write_read: fake_command_write_read,
+            get_device2: None,
+            free_device: None,
         }
-    };
+    }
 
-    let dlls = dri_lib_name(&driver_name);
-    let dll = blocking::unblock(move || Dll::load("DRI", &dlls)).await?;
-
-    let (dll, e) =
-        blocking::unblock(
-            move || match super::extensions::load_extensions(&dll, &driver_name) {
-                Err(e) => Err(e),
-                Ok(a) => Ok((
-                    dll,
-                    a.iter()
-                        .cloned()
-                        .map(|e| ExtensionContainer(e))
-                        .collect::<Box<[ExtensionContainer]>>(),
-                )),
-            },
-        )
-        .await?;
-    extensions.extend(e.into_iter());
-    Ok(dll)
+    fn compatible(strings: &[&str]) -> Vec<String> {
+        strings.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn compatible_driver_names() {
+        let driver = |strings: &[&str]| driver_name_from_compatible(&compatible(strings));
+
+        assert_eq!(
+            driver(&["allwinner,sun50i-h5-mali", "arm,mali-450"]),
+            Some("lima")
+        );
+        assert_eq!(driver(&["arm,mali-utgard"]), Some("lima"));
+        assert_eq!(
+            driver(&["rockchip,rk3399-mali", "arm,mali-t860"]),
+            Some("panfrost")
+        );
+        assert_eq!(driver(&["arm,mali-t624"]), Some("panfrost"));
+        assert_eq!(driver(&["arm,mali-t720"]), Some("panfrost"));
+        assert_eq!(
+            driver(&["amlogic,meson-g12a-mali", "arm,mali-bifrost"]),
+            Some("panfrost")
+        );
+        assert_eq!(
+            driver(&["mediatek,mt8192-mali", "arm,mali-valhall-jm"]),
+            Some("panfrost")
+        );
+        assert_eq!(driver(&["fsl,imx8mq-gpu", "vivante,gc"]), Some("etnaviv"));
+        assert_eq!(driver(&["brcm,2711-v3d"]), Some("v3d"));
+        assert_eq!(driver(&["brcm,bcm2835-vc4"]), Some("vc4"));
+        assert_eq!(driver(&["qcom,adreno-630.2", "qcom,adreno"]), Some("msm"));
+        assert_eq!(driver(&["nvidia,tegra124-host1x"]), Some("tegra"));
+
+        // Mali display controllers aren't GPUs
+        assert_eq!(driver(&["arm,mali-dp500"]), None);
+        assert_eq!(driver(&["arm,mali-dp650"]), None);
+        assert_eq!(driver(&["arm,mali-d71"]), None);
+        assert_eq!(driver(&["rockchip,rk3399-vop"]), None);
+        assert_eq!(driver(&[]), None);
+    }
+
+    #[test]
+    fn kmsro() {
+        let drm = fake_drm();
+        let platform = |kernel_driver, strings: &[&str]| {
+            driver_name_from_platform(&drm, fake_fd(kernel_driver), &compatible(strings))
+        };
+
+        assert_eq!(platform("mali-dp", &["arm,mali-dp650"]).unwrap(), "mali-dp");
+        assert_eq!(
+            platform("rockchip", &["rockchip,rk3399-vop"]).unwrap(),
+            "rockchip"
+        );
+        // GPUs don't need the kernel driver
+        assert_eq!(
+            platform("rockchip", &["arm,mali-t860"]).unwrap(),
+            "panfrost"
+        );
+        assert!(platform("xe", &["rockchip,rk3399-vop"]).is_err());
+    }
+
+    #[test]
+    fn kernel_names() {
+        let drm = fake_drm();
+        for kernel_driver in FAKE_KERNEL_DRIVERS {
+            assert_eq!(
+                driver_name_from_kernel_name(&drm, fake_fd(kernel_driver)).unwrap(),
+                *kernel_driver
+            );
+        }
+        assert!(driver_name_from_kernel_name(&drm, -1).is_err());
+    }
+
+    fn device(driver: Option<&str>, device_id: Option<c_int>) -> sysfs::SysfsDevice {
+        sysfs::SysfsDevice {
+            driver: driver.map(str::to_string),
+            device_id,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn kernel_driver_names() {
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("amdgpu"), Some(0x73BF))),
+            Some("radeonsi")
+        );
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("xe"), None)),
+            Some("iris")
+        );
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("vc4"), None)),
+            Some("vc4")
+        );
+
+        // these need the PCI table and the DRM device
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("radeon"), Some(0x6779))),
+            None
+        );
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("nouveau"), Some(0x1C82))),
+            None
+        );
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(None, Some(0x3E92))),
+            None
+        );
+    }
+
+    #[test]
+    fn i915_driver_names() {
+        let i915 =
+            |device_id| driver_name_from_sysfs_device(&device(Some("i915"), Some(device_id)));
+
+        assert_eq!(i915(pci_ids::I915_PCI_IDS[0]), Some("i915"));
+        assert_eq!(i915(pci_ids::CROCUS_PCI_IDS[0]), Some("crocus"));
+        assert_eq!(i915(pci_ids::IRIS_PCI_IDS[0]), Some("iris"));
+        // hardware newer than the table is driven by iris
+        assert_eq!(i915(0x7FFF), Some("iris"));
+        // without a device ID, we can't tell which generation it is
+        assert_eq!(
+            driver_name_from_sysfs_device(&device(Some("i915"), None)),
+            None
+        );
+    }
+
+    #[test]
+    fn fake_sysfs_device() {
+        let root = crate::util::TempDir::new("breadglx-sysfs");
+        sysfs::tests::fake_pci_device(
+            root.path(),
+            226,
+            128,
+            "i915",
+            "0x8086",
+            &format!("0x{:x}", pci_ids::CROCUS_PCI_IDS[0]),
+        );
+
+        let device = sysfs::SysfsDevice::read(root.path(), 226, 128).unwrap();
+        assert_eq!(driver_name_from_sysfs_device(&device), Some("crocus"));
+    }
 }
@@ -1,11 +1,7 @@
 // MIT/Apache2 License
 
-use super::driver_name_from_kernel_name;
-use crate::{
-    auto::pci_ids,
-    cstr::{const_cstr, ConstCstr},
-    dll::Dll,
-};
+use super::{driver_name_from_kernel_name, DrmFunctions};
+use crate::auto::pci_ids;
 use std::{
     env,
     ffi::c_void,
@@ -23,10 +19,7 @@ struct DrmNouveauGetParam {
     value: u64,
 }
 
-type DrmCommandWriteRead = unsafe extern "C" fn(c_int, c_ulong, *mut c_void, c_ulong) -> c_int;
-const DRM_COMMAND_WRITE_READ: ConstCstr<'static> = const_cstr(&*b"drmCommandWriteRead");
-
-pub(crate) type Predicate = Option<&'static dyn Fn(&Dll, c_int) -> bool>;
+pub(crate) type Predicate = Option<&'static dyn Fn(&DrmFunctions, c_int) -> bool>;
 
 // Entry in the PCI table
 pub(crate) struct PciTableEntry {
@@ -47,6 +40,21 @@ impl PciTableEntry {
         }
     }
 
+    #[inline]
+    const fn with_ids_and_predicate(
+        id: c_int,
+        name: &'static str,
+        pci_ids: &'static [c_int],
+        pred: Predicate,
+    ) -> PciTableEntry {
+        Self {
+            id,
+            driver: name,
+            pci_ids,
+            pred,
+        }
+    }
+
     #[inline]
     const fn with_predicate(id: c_int, name: &'static str, pred: Predicate) -> PciTableEntry {
         Self {
@@ -58,14 +66,39 @@ impl PciTableEntry {
     }
 }
 
-pub(crate) const PCI_TABLE: [PciTableEntry; 12] = [
+// Mirrors Mesa's loader/pci_id_driver_map. Entries are checked in order, so the catch-all entries
+// for each vendor come after the explicit lists. This differs from Mesa's table in a few places:
+//
+// * Mesa has no i810 entry, since it no longer ships the i810 driver. It's kept here so those chips
+//   are matched to i810_dri.so on systems that still have it, instead of not being matched at all.
+// * Mesa has an "iris" catch-all but no explicit list. The explicit list is matched first and
+//   picks the same driver, so it only makes known gen8+ chips match by ID.
+// * Mesa has a "radeonsi" catch-all but no explicit list. As with iris, the explicit list picks
+//   the same driver as the catch-all right after it.
+//
+// Like Mesa, crocus and iris are only used when the kernel driver is i915, since neither works with
+// anything else. radeon and r200 are from the Mesa releases that still shipped those drivers.
+pub(crate) const PCI_TABLE: [PciTableEntry; 15] = [
+    PciTableEntry::new(0x8086, "i810", &pci_ids::I810_PCI_IDS),
     PciTableEntry::new(0x8086, "i915", &pci_ids::I915_PCI_IDS),
-    PciTableEntry::new(0x8086, "i965", &pci_ids::I965_PCI_IDS),
+    PciTableEntry::with_ids_and_predicate(
+        0x8086,
+        "crocus",
+        &pci_ids::CROCUS_PCI_IDS,
+        Some(&is_kernel_i915),
+    ),
+    PciTableEntry::with_ids_and_predicate(
+        0x8086,
+        "iris",
+        &pci_ids::IRIS_PCI_IDS,
+        Some(&is_kernel_i915),
+    ),
     PciTableEntry::with_predicate(0x8086, "iris", Some(&is_kernel_i915)),
     PciTableEntry::new(0x1002, "radeon", &pci_ids::RADEON_PCI_IDS),
     PciTableEntry::new(0x1002, "r200", &pci_ids::R200_PCI_IDS),
     PciTableEntry::new(0x1002, "r300", &pci_ids::R300_PCI_IDS),
     PciTableEntry::new(0x1002, "r600", &pci_ids::R600_PCI_IDS),
+    PciTableEntry::new(0x1002, "radeonsi", &pci_ids::RADEONSI_PCI_IDS),
     PciTableEntry::with_predicate(0x1002, "radeonsi", None),
     PciTableEntry::with_predicate(0x10de, "nouveau_vieux", Some(&is_nouveau_vieux)),
     PciTableEntry::with_predicate(0x10de, "nouveau", None),
@@ -73,7 +106,7 @@ pub(crate) const PCI_TABLE: [PciTableEntry; 12] = [
     PciTableEntry::new(0x15ad, "vmwgfx", &pci_ids::VMWGFX_PCI_IDS),
 ];
 
-fn is_kernel_i915(drm: &Dll, fd: c_int) -> bool {
+fn is_kernel_i915(drm: &DrmFunctions, fd: c_int) -> bool {
     match driver_name_from_kernel_name(drm, fd) {
         Ok(name) => name.as_str() == "i915",
         Err(_) => false,
@@ -81,16 +114,13 @@ fn is_kernel_i915(drm: &Dll, fd: c_int) -> bool {
 }
 
 #[inline]
-fn nouveau_chipset(drm: &Dll, fd: c_int) -> Option<c_int> {
-    let drmCommandWriteRead: DrmCommandWriteRead =
-        unsafe { drm.function(&DRM_COMMAND_WRITE_READ) }.expect("drmCommandWriteRead not found");
-
+fn nouveau_chipset(drm: &DrmFunctions, fd: c_int) -> Option<c_int> {
     let mut gp = DrmNouveauGetParam {
         param: NOUVEAU_GETPARAM_CHIPSET_ID,
         value: 0,
     };
     if unsafe {
-        (drmCommandWriteRead)(
+        (drm.command_write_read)(
             fd,
             DRM_NOUVEAU_GETPARAM,
             &mut gp as *mut DrmNouveauGetParam as *mut c_void,
@@ -104,7 +134,7 @@ fn nouveau_chipset(drm: &Dll, fd: c_int) -> Option<c_int> {
     }
 }
 
-fn is_nouveau_vieux(drm: &Dll, fd: c_int) -> bool {
+fn is_nouveau_vieux(drm: &DrmFunctions, fd: c_int) -> bool {
     match nouveau_chipset(drm, fd) {
         None => false,
         Some(chipset) => {
@@ -113,3 +143,87 @@ fn is_nouveau_vieux(drm: &Dll, fd: c_int) -> bool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{
+            driver_name_from_pci,
+            tests::{fake_drm, fake_fd},
+        },
+        *,
+    };
+
+    /// The names of the constants build.rs generated from `pci_ids/`.
+    fn generated_tables() -> Vec<&'static str> {
+        include_str!(concat!(env!("OUT_DIR"), "/auto/pci_ids.rs"))
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub(crate) const "))
+            .filter_map(|line| line.split(':').next())
+            .collect()
+    }
+
+    #[test]
+    fn every_table_is_used() {
+        let source = include_str!("pci_table.rs");
+        let tables = generated_tables();
+        assert!(!tables.is_empty());
+
+        for table in tables {
+            assert!(
+                source.contains(&format!("&pci_ids::{}", table)),
+                "{} is not used in the PCI table",
+                table
+            );
+        }
+    }
+
+    #[test]
+    fn catch_alls_come_last() {
+        // once a vendor's catch-all without a predicate matches, nothing after it can
+        for (i, entry) in PCI_TABLE.iter().enumerate() {
+            if entry.pci_ids.is_empty() && entry.pred.is_none() {
+                assert!(
+                    PCI_TABLE[i + 1..].iter().all(|later| later.id != entry.id),
+                    "{} shadows later entries",
+                    entry.driver
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn intel_drivers_on_i915() {
+        let drm = fake_drm();
+        let i915 = fake_fd("i915");
+        assert!(is_kernel_i915(&drm, i915));
+
+        let driver = |chip_id| driver_name_from_pci(&drm, i915, 0x8086, chip_id).unwrap();
+        assert_eq!(driver(pci_ids::I915_PCI_IDS[0]), "i915");
+        assert_eq!(driver(pci_ids::CROCUS_PCI_IDS[0]), "crocus");
+        assert_eq!(driver(pci_ids::IRIS_PCI_IDS[0]), "iris");
+        // hardware newer than the table falls through to the catch-all
+        assert_eq!(driver(0x7FFF), "iris");
+    }
+
+    #[test]
+    fn intel_drivers_on_other_kernel_drivers() {
+        let drm = fake_drm();
+        let xe = fake_fd("xe");
+        assert!(!is_kernel_i915(&drm, xe));
+        assert!(driver_name_from_pci(&drm, xe, 0x8086, pci_ids::IRIS_PCI_IDS[0]).is_err());
+        // the kernel driver can't be read, so it isn't i915
+        assert!(!is_kernel_i915(&drm, -1));
+    }
+
+    #[test]
+    fn intel_drivers_need_i915() {
+        for entry in PCI_TABLE.iter().filter(|entry| entry.id == 0x8086) {
+            let gated = entry.pred.is_some();
+            match entry.driver {
+                "crocus" | "iris" => assert!(gated, "{} is not gated on i915", entry.driver),
+                _ => assert!(!gated, "{} should not have a predicate", entry.driver),
+            }
+        }
+    }
+}
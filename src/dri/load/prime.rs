@@ -0,0 +1,320 @@
+// MIT/Apache2 License
+
+//! Choose a GPU to render on other than the one driving the display, as requested by `DRI_PRIME`.
+//! This is how hybrid graphics setups offload rendering onto their discrete GPU.
+
+use super::sysfs::{self, SysfsDevice};
+use std::{
+    fs::{self, OpenOptions},
+    os::{
+        raw::c_int,
+        unix::{
+            fs::{FileTypeExt, MetadataExt},
+            io::IntoRawFd,
+        },
+    },
+    path::{Path, PathBuf},
+};
+
+const DEV_DRI: &str = "/dev/dri";
+const RENDER_NODE_PREFIX: &str = "renderD";
+
+/// The GPU selected by a `DRI_PRIME` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PrimeSelector {
+    /// The nth GPU that isn't driving the display, starting at one. Zero selects the display GPU.
+    Index(usize),
+    /// The GPU with this PCI tag, like `pci-0000_01_00_0`.
+    Tag(String),
+    /// The GPU with these PCI vendor and device IDs.
+    Ids { vendor_id: c_int, device_id: c_int },
+}
+
+impl PrimeSelector {
+    /// Parse a `DRI_PRIME` value. This is either an index, a PCI tag (`pci-0000_01_00_0`), or a
+    /// vendor and device ID pair in hexadecimal (`10de:1f82`).
+    #[inline]
+    pub(crate) fn parse(value: &str) -> Option<PrimeSelector> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            None
+        } else if value.starts_with("pci-") {
+            Some(PrimeSelector::Tag(value.to_ascii_lowercase()))
+        } else if let Some(colon) = value.find(':') {
+            let vendor_id = parse_hex(&value[..colon])?;
+            let device_id = parse_hex(&value[colon + 1..])?;
+            Some(PrimeSelector::Ids {
+                vendor_id,
+                device_id,
+            })
+        } else {
+            value.parse().ok().map(PrimeSelector::Index)
+        }
+    }
+
+    /// Does this select the given GPU? `others` is the position of the GPU among the ones that
+    /// aren't driving the display, starting at one, or zero for the display GPU.
+    #[inline]
+    fn selects(&self, device: &SysfsDevice, others: usize) -> bool {
+        match self {
+            PrimeSelector::Index(index) => *index == others,
+            PrimeSelector::Tag(tag) => device.pci_tag().as_deref() == Some(tag.as_str()),
+            PrimeSelector::Ids {
+                vendor_id,
+                device_id,
+            } => device.vendor_id == Some(*vendor_id) && device.device_id == Some(*device_id),
+        }
+    }
+}
+
+#[inline]
+fn parse_hex(s: &str) -> Option<c_int> {
+    let s = s.trim();
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    c_int::from_str_radix(digits, 16).ok()
+}
+
+/// A DRM render node, along with the device it belongs to.
+#[derive(Debug, Clone)]
+pub(crate) struct RenderNode {
+    pub path: PathBuf,
+    pub device: SysfsDevice,
+}
+
+/// Find the render nodes in `dev_dri`, using the sysfs tree rooted at `sysfs_root` to identify
+/// their devices. The nodes are sorted by their minor number.
+#[inline]
+pub(crate) fn render_nodes(dev_dri: &Path, sysfs_root: &Path) -> Vec<RenderNode> {
+    let entries = match fs::read_dir(dev_dri) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::debug!("Unable to read {}: {}", dev_dri.display(), e);
+            return vec![];
+        }
+    };
+
+    let mut nodes: Vec<(u32, RenderNode)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path.file_name()?.to_str()?.starts_with(RENDER_NODE_PREFIX) {
+                return None;
+            }
+
+            let metadata = fs::metadata(&path).ok()?;
+            if !metadata.file_type().is_char_device() {
+                return None;
+            }
+
+            let (major, minor) = (libc::major(metadata.rdev()), libc::minor(metadata.rdev()));
+            let device = SysfsDevice::read(sysfs_root, major, minor).ok()?;
+            Some((minor, RenderNode { path, device }))
+        })
+        .collect();
+
+    nodes.sort_by_key(|(minor, _)| *minor);
+    nodes.into_iter().map(|(_, node)| node).collect()
+}
+
+/// Choose the render node that `selector` asks for from `nodes`, given the device driving the
+/// display. Returns `None` if the display GPU is selected, or if nothing matches.
+#[inline]
+pub(crate) fn choose_render_node<'a>(
+    selector: &PrimeSelector,
+    display_device: &SysfsDevice,
+    nodes: &'a [RenderNode],
+) -> Option<&'a RenderNode> {
+    nodes
+        .iter()
+        .filter(|node| node.device.path != display_device.path)
+        .enumerate()
+        .find(|(i, node)| selector.selects(&node.device, i + 1))
+        .map(|(_, node)| node)
+}
+
+/// Open the render node of the GPU selected by a `DRI_PRIME` value, if it's a different GPU than
+/// the one behind `fd`. The returned file descriptor should be rendered on instead of `fd`.
+#[inline]
+pub(crate) fn open_prime_fd(fd: c_int, prime: &str) -> Option<c_int> {
+    let selector = match PrimeSelector::parse(prime) {
+        Some(selector) => selector,
+        None => {
+            log::warn!("Ignoring invalid DRI_PRIME value \"{}\"", prime);
+            return None;
+        }
+    };
+
+    let (major, minor) = sysfs::device_numbers(fd)?;
+    let display_device = SysfsDevice::for_device_numbers(major, minor)?;
+    let nodes = render_nodes(Path::new(DEV_DRI), Path::new(sysfs::SYSFS_ROOT));
+
+    let node = match choose_render_node(&selector, &display_device, &nodes) {
+        Some(node) => node,
+        None => {
+            // selecting the display GPU is fine, it just means there's nothing to offload
+            if !selector.selects(&display_device, 0) {
+                log::warn!("No GPU matches DRI_PRIME={}, using the display GPU", prime);
+            }
+            return None;
+        }
+    };
+
+    match OpenOptions::new().read(true).write(true).open(&node.path) {
+        Ok(file) => {
+            log::info!(
+                "Rendering on {} for DRI_PRIME={}",
+                node.path.display(),
+                prime
+            );
+            Some(file.into_raw_fd())
+        }
+        Err(e) => {
+            log::error!("Failed to open {}: {}", node.path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::sysfs::tests::fake_pci_device_at, *};
+    use crate::util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn parse_index() {
+        assert_eq!(PrimeSelector::parse("1"), Some(PrimeSelector::Index(1)));
+        assert_eq!(PrimeSelector::parse(" 0 "), Some(PrimeSelector::Index(0)));
+    }
+
+    #[test]
+    fn parse_tag() {
+        assert_eq!(
+            PrimeSelector::parse("pci-0000_01_00_0"),
+            Some(PrimeSelector::Tag("pci-0000_01_00_0".to_string()))
+        );
+        assert_eq!(
+            PrimeSelector::parse("pci-0000_0A_00_0"),
+            Some(PrimeSelector::Tag("pci-0000_0a_00_0".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_ids() {
+        let ids = Some(PrimeSelector::Ids {
+            vendor_id: 0x10de,
+            device_id: 0x1f82,
+        });
+        assert_eq!(PrimeSelector::parse("10de:1f82"), ids);
+        assert_eq!(PrimeSelector::parse("0x10DE:0X1F82"), ids);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for value in [
+            "",
+            "  ",
+            "abc",
+            "-1",
+            "10de:",
+            ":1f82",
+            "zz:1f82",
+            "10de:1f82:0",
+        ]
+        .iter()
+        {
+            assert_eq!(PrimeSelector::parse(value), None, "{:?}", value);
+        }
+    }
+
+    /// A system with an integrated Intel GPU driving the display and a discrete NVIDIA GPU. The
+    /// render nodes link to character devices that exist everywhere, so that their device numbers
+    /// can be looked up in the fake sysfs tree.
+    struct HybridSystem {
+        root: TempDir,
+        display_device: SysfsDevice,
+    }
+
+    const INTEGRATED_SLOT: &str = "0000:00:02.0";
+    const DISCRETE_SLOT: &str = "0000:01:00.0";
+
+    impl HybridSystem {
+        fn new() -> Self {
+            let root = TempDir::new("breadglx-prime");
+            let sysfs = root.path().join("sys");
+            let dev_dri = root.path().join("dev/dri");
+            fs::create_dir_all(&dev_dri).unwrap();
+
+            fake_pci_device_at(&sysfs, INTEGRATED_SLOT, 226, 0, "i915", "0x8086", "0x3e92");
+            fake_pci_device_at(&sysfs, INTEGRATED_SLOT, 1, 3, "i915", "0x8086", "0x3e92");
+            fake_pci_device_at(&sysfs, DISCRETE_SLOT, 1, 5, "nouveau", "0x10de", "0x1f82");
+            symlink("/dev/null", dev_dri.join("renderD128")).unwrap();
+            symlink("/dev/zero", dev_dri.join("renderD129")).unwrap();
+
+            // only render nodes that are character devices count
+            symlink("/dev/full", dev_dri.join("card0")).unwrap();
+            fs::write(dev_dri.join("renderD130"), "").unwrap();
+
+            let display_device = SysfsDevice::read(&sysfs, 226, 0).unwrap();
+            Self {
+                root,
+                display_device,
+            }
+        }
+
+        fn render_nodes(&self) -> Vec<RenderNode> {
+            render_nodes(
+                &self.root.path().join("dev/dri"),
+                &self.root.path().join("sys"),
+            )
+        }
+
+        fn choose(&self, prime: &str) -> Option<PathBuf> {
+            let selector = PrimeSelector::parse(prime).unwrap();
+            let nodes = self.render_nodes();
+            choose_render_node(&selector, &self.display_device, &nodes)
+                .map(|node| node.path.clone())
+        }
+    }
+
+    #[test]
+    fn find_render_nodes() {
+        let system = HybridSystem::new();
+        let nodes = system.render_nodes();
+        let names: Vec<_> = nodes
+            .iter()
+            .map(|node| node.path.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["renderD128", "renderD129"]);
+        assert_eq!(nodes[0].device.path, system.display_device.path);
+        assert_eq!(nodes[1].device.bus_id.as_deref(), Some(DISCRETE_SLOT));
+
+        assert!(render_nodes(&system.root.path().join("missing"), Path::new("/")).is_empty());
+    }
+
+    #[test]
+    fn choose_discrete_gpu() {
+        let system = HybridSystem::new();
+        let discrete = Some(system.root.path().join("dev/dri/renderD129"));
+        assert_eq!(system.choose("1"), discrete);
+        assert_eq!(system.choose("pci-0000_01_00_0"), discrete);
+        assert_eq!(system.choose("10de:1f82"), discrete);
+    }
+
+    #[test]
+    fn choose_display_gpu() {
+        // selecting the display GPU, or one that isn't there, leaves rendering where it is
+        let system = HybridSystem::new();
+        assert_eq!(system.choose("0"), None);
+        assert_eq!(system.choose("pci-0000_00_02_0"), None);
+        assert_eq!(system.choose("8086:3e92"), None);
+        assert_eq!(system.choose("2"), None);
+        assert_eq!(system.choose("pci-0000_02_00_0"), None);
+        assert_eq!(system.choose("1002:73bf"), None);
+
+        assert!(PrimeSelector::Index(0).selects(&system.display_device, 0));
+    }
+}
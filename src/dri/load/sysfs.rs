@@ -0,0 +1,262 @@
+// MIT/Apache2 License
+
+//! Find out which device a DRM file descriptor refers to by reading sysfs, rather than relying on
+//! a table of known PCI IDs.
+
+use std::{
+    fs, io,
+    mem::MaybeUninit,
+    os::raw::c_int,
+    path::{Path, PathBuf},
+};
+
+pub(crate) const SYSFS_ROOT: &str = "/sys";
+
+/// The information sysfs has about a DRM device.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SysfsDevice {
+    /// The canonical path of the device's sysfs directory. Every DRM node of a device (e.g. `card0`
+    /// and `renderD128`) resolves to the same directory.
+    pub path: PathBuf,
+    /// The PCI slot of the device (e.g. `0000:01:00.0`), if this is a PCI device.
+    pub bus_id: Option<String>,
+    /// The PCI vendor ID, if this is a PCI device.
+    pub vendor_id: Option<c_int>,
+    /// The PCI device ID, if this is a PCI device.
+    pub device_id: Option<c_int>,
+    /// The name of the kernel driver bound to the device.
+    pub driver: Option<String>,
+}
+
+impl SysfsDevice {
+    /// Read the device that a DRM file descriptor was opened on.
+    #[inline]
+    pub(crate) fn for_fd(fd: c_int) -> Option<SysfsDevice> {
+        let (major, minor) = device_numbers(fd)?;
+        Self::for_device_numbers(major, minor)
+    }
+
+    /// Read the device with the given device numbers from the system's sysfs.
+    #[inline]
+    pub(crate) fn for_device_numbers(major: u32, minor: u32) -> Option<SysfsDevice> {
+        match Self::read(Path::new(SYSFS_ROOT), major, minor) {
+            Ok(device) => Some(device),
+            Err(e) => {
+                log::debug!("Unable to read sysfs for device {}:{}: {}", major, minor, e);
+                None
+            }
+        }
+    }
+
+    /// Read the device with the given device numbers from a sysfs tree rooted at `root`.
+    #[inline]
+    pub(crate) fn read(root: &Path, major: u32, minor: u32) -> io::Result<SysfsDevice> {
+        let device_dir = device_dir(root, major, minor);
+        if !device_dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", device_dir.display()),
+            ));
+        }
+
+        // the driver is a symlink to the driver's directory, which is named after the driver
+        let driver = link_name(&device_dir.join("driver"));
+
+        // PCI devices are named after their slot
+        let path = fs::canonicalize(&device_dir)?;
+        let bus_id = match link_name(&device_dir.join("subsystem")) {
+            Some(subsystem) if subsystem == "pci" => path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_string),
+            _ => None,
+        };
+
+        Ok(SysfsDevice {
+            path,
+            bus_id,
+            vendor_id: read_hex_id(&device_dir.join("vendor")),
+            device_id: read_hex_id(&device_dir.join("device")),
+            driver,
+        })
+    }
+
+    /// The tag Mesa uses to identify a PCI device, like `pci-0000_01_00_0`.
+    #[inline]
+    pub(crate) fn pci_tag(&self) -> Option<String> {
+        self.bus_id
+            .as_ref()
+            .map(|bus_id| format!("pci-{}", bus_id.replace([':', '.'], "_")))
+    }
+}
+
+/// Get the name of the file a symlink points to.
+#[inline]
+fn link_name(link: &Path) -> Option<String> {
+    fs::read_link(link)
+        .ok()
+        .and_then(|target| target.file_name()?.to_str().map(str::to_string))
+}
+
+#[inline]
+fn device_dir(root: &Path, major: u32, minor: u32) -> PathBuf {
+    root.join("dev")
+        .join("char")
+        .join(format!("{}:{}", major, minor))
+        .join("device")
+}
+
+/// Read a file containing a single hexadecimal ID, like `0x8086`.
+#[inline]
+fn read_hex_id(path: &Path) -> Option<c_int> {
+    let contents = fs::read_to_string(path).ok()?;
+    let contents = contents.trim();
+    let digits = contents
+        .strip_prefix("0x")
+        .or_else(|| contents.strip_prefix("0X"))
+        .unwrap_or(contents);
+    c_int::from_str_radix(digits, 16).ok()
+}
+
+/// Get the major and minor device numbers of the character device a file descriptor refers to.
+#[inline]
+pub(crate) fn device_numbers(fd: c_int) -> Option<(u32, u32)> {
+    let mut stat = MaybeUninit::<libc::stat>::uninit();
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+
+    // SAFETY: fstat succeeded, so the struct is initialized
+    let stat = unsafe { stat.assume_init() };
+    if stat.st_mode & libc::S_IFMT != libc::S_IFCHR {
+        return None;
+    }
+
+    Some((libc::major(stat.st_rdev), libc::minor(stat.st_rdev)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::util::TempDir;
+    use std::os::unix::fs::symlink;
+
+    const PCI_SLOT: &str = "0000:00:02.0";
+
+    /// Lay out a sysfs tree with one PCI device, the way Linux does: the device node's `device`
+    /// link leads to the PCI device, which links to its driver and its bus.
+    pub(crate) fn fake_pci_device(
+        root: &Path,
+        major: u32,
+        minor: u32,
+        driver: &str,
+        vendor: &str,
+        device: &str,
+    ) {
+        fake_pci_device_at(root, PCI_SLOT, major, minor, driver, vendor, device)
+    }
+
+    /// Add a device node for a PCI device in the given slot to a sysfs tree, creating the device if
+    /// it isn't there yet.
+    pub(crate) fn fake_pci_device_at(
+        root: &Path,
+        slot: &str,
+        major: u32,
+        minor: u32,
+        driver: &str,
+        vendor: &str,
+        device: &str,
+    ) {
+        let pci_device = root.join("devices/pci0000:00").join(slot);
+        let driver_dir = root.join("bus/pci/drivers").join(driver);
+        let node_name = if minor < 128 {
+            format!("card{}", minor)
+        } else {
+            format!("renderD{}", minor)
+        };
+        let node_dir = pci_device.join("drm").join(node_name);
+        fs::create_dir_all(&driver_dir).unwrap();
+        fs::create_dir_all(&node_dir).unwrap();
+        fs::create_dir_all(root.join("dev/char")).unwrap();
+
+        fs::write(pci_device.join("vendor"), vendor).unwrap();
+        fs::write(pci_device.join("device"), device).unwrap();
+        // the device's own links are only made once, for its first node
+        let _ = symlink(&driver_dir, pci_device.join("driver"));
+        let _ = symlink(root.join("bus/pci"), pci_device.join("subsystem"));
+        symlink(&pci_device, node_dir.join("device")).unwrap();
+        symlink(
+            &node_dir,
+            root.join(format!("dev/char/{}:{}", major, minor)),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn read_pci_device() {
+        let root = TempDir::new("breadglx-sysfs");
+        fake_pci_device(root.path(), 226, 0, "i915", "0x8086\n", "0x3e92\n");
+
+        let device = SysfsDevice::read(root.path(), 226, 0).unwrap();
+        assert_eq!(
+            device.path,
+            fs::canonicalize(root.path().join("devices/pci0000:00").join(PCI_SLOT)).unwrap()
+        );
+        assert_eq!(device.bus_id.as_deref(), Some(PCI_SLOT));
+        assert_eq!(device.vendor_id, Some(0x8086));
+        assert_eq!(device.device_id, Some(0x3E92));
+        assert_eq!(device.driver.as_deref(), Some("i915"));
+        assert_eq!(device.pci_tag().as_deref(), Some("pci-0000_00_02_0"));
+    }
+
+    #[test]
+    fn read_platform_device() {
+        let root = TempDir::new("breadglx-sysfs");
+        let device_dir = root.path().join("devices/platform/gpu");
+        let driver_dir = root.path().join("bus/platform/drivers/vc4-drm");
+        fs::create_dir_all(&device_dir).unwrap();
+        fs::create_dir_all(&driver_dir).unwrap();
+        fs::create_dir_all(root.path().join("dev/char/226:1")).unwrap();
+        symlink(&driver_dir, device_dir.join("driver")).unwrap();
+        symlink(
+            root.path().join("bus/platform"),
+            device_dir.join("subsystem"),
+        )
+        .unwrap();
+        symlink(&device_dir, root.path().join("dev/char/226:1/device")).unwrap();
+
+        // platform devices have no PCI slot or IDs
+        let device = SysfsDevice::read(root.path(), 226, 1).unwrap();
+        assert_eq!(device.driver.as_deref(), Some("vc4-drm"));
+        assert_eq!(device.bus_id, None);
+        assert_eq!((device.vendor_id, device.device_id), (None, None));
+        assert_eq!(device.pci_tag(), None);
+    }
+
+    #[test]
+    fn read_missing_device() {
+        let root = TempDir::new("breadglx-sysfs");
+        fake_pci_device(root.path(), 226, 0, "amdgpu", "0x1002", "0x73bf");
+
+        let err = SysfsDevice::read(root.path(), 226, 128).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn hex_ids() {
+        let root = TempDir::new("breadglx-sysfs");
+        let id = |contents: &str| {
+            let path = root.path().join("id");
+            fs::write(&path, contents).unwrap();
+            read_hex_id(&path)
+        };
+
+        assert_eq!(id("0x8086\n"), Some(0x8086));
+        assert_eq!(id("0X10DE"), Some(0x10DE));
+        assert_eq!(id("1002"), Some(0x1002));
+        assert_eq!(id("  0x15ad  \n"), Some(0x15AD));
+        assert_eq!(id("not an id"), None);
+        assert_eq!(id(""), None);
+        assert_eq!(read_hex_id(&root.path().join("missing")), None);
+    }
+}
@@ -3,7 +3,7 @@
 use super::ffi;
 use crate::{
     config::RGBA_TYPE,
-    context::{GlContextRule, Profile, ReleaseBehavior, ResetNotificationStrategy},
+    context::{GlApi, GlContextRule, ReleaseBehavior, ResetNotificationStrategy},
 };
 use std::os::raw::{c_int, c_uint};
 use tinyvec::ArrayVec;
@@ -73,7 +73,7 @@ impl DriRules {
         } else {
             ArrayVec::<[u32; 2]>::new()
         })
-        .chain(if flags == 0 {
+        .chain(if flags != 0 {
             ArrayVec::<[u32; 2]>::from([ffi::__DRI_CTX_ATTRIB_FLAGS, flags])
         } else {
             ArrayVec::<[u32; 2]>::new()
@@ -85,7 +85,6 @@ impl DriRules {
 /// Get the important rules from the set of GlContext rules.
 pub(crate) fn convert_dri_rules(glrules: &[GlContextRule]) -> breadx::Result<DriRules> {
     let mut rules: DriRules = Default::default();
-    let mut profile = Profile::Core;
 
     if glrules.is_empty() {
         return Err(breadx::BreadError::StaticMsg("Rules list was empty"));
@@ -106,9 +105,7 @@ pub(crate) fn convert_dri_rules(glrules: &[GlContextRule]) -> breadx::Result<Dri
                 rules.flags |= ffi::__DRI_CTX_FLAG_NO_ERROR;
             }
         }
-        GlContextRule::Profile(p) => {
-            profile = *p;
-        }
+        GlContextRule::Profile(_) => (),
         GlContextRule::RenderType(rt) => {
             rules.render_type = *rt;
         }
@@ -120,21 +117,107 @@ pub(crate) fn convert_dri_rules(glrules: &[GlContextRule]) -> breadx::Result<Dri
         }
     });
 
-    // if we have a profile, set the api value accordingly
-    rules.api = match (profile, rules.major, rules.minor) {
-        (Profile::Core, major, minor) if major > 3 || (major == 3 && minor >= 2) => {
-            ffi::__DRI_API_OPENGL_CORE
-        }
-        (Profile::Core, _, _) | (Profile::Compatibility, _, _) => ffi::__DRI_API_OPENGL,
-        (Profile::Es, major, minor) if major >= 3 => ffi::__DRI_API_GLES3,
-        (Profile::Es, major, minor) if major == 2 && minor == 0 => ffi::__DRI_API_GLES2,
-        (Profile::Es, major, minor) if major == 1 && minor < 2 => ffi::__DRI_API_GLES,
-        _ => {
-            return Err(breadx::BreadError::StaticMsg(
-                "Failed to determine API version",
-            ))
-        }
-    };
+    // the profile and version determine which API the driver should use
+    rules.api = dri_api(GlContextRule::requested_api(glrules)?);
 
     Ok(rules)
 }
+
+/// Convert a client API to its DRI equivalent.
+#[inline]
+pub(crate) fn dri_api(api: GlApi) -> c_uint {
+    match api {
+        GlApi::OpenGl => ffi::__DRI_API_OPENGL,
+        GlApi::OpenGlCore => ffi::__DRI_API_OPENGL_CORE,
+        GlApi::Gles1 => ffi::__DRI_API_GLES,
+        GlApi::Gles2 => ffi::__DRI_API_GLES2,
+        GlApi::Gles3 => ffi::__DRI_API_GLES3,
+    }
+}
+
+/// Convert an error returned by createContextAttribs into an error.
+#[inline]
+pub(crate) fn context_error(error: c_uint) -> breadx::BreadError {
+    breadx::BreadError::StaticMsg(match error {
+        ffi::__DRI_CTX_ERROR_NO_MEMORY => "Driver ran out of memory while creating context",
+        ffi::__DRI_CTX_ERROR_BAD_API => "Driver does not support the requested API",
+        ffi::__DRI_CTX_ERROR_BAD_VERSION => "Driver does not support the requested version",
+        ffi::__DRI_CTX_ERROR_BAD_FLAG => "Driver does not support the requested flags",
+        ffi::__DRI_CTX_ERROR_UNKNOWN_ATTRIBUTE => "Driver did not recognize a context attribute",
+        ffi::__DRI_CTX_ERROR_UNKNOWN_FLAG => "Driver did not recognize a context flag",
+        _ => "Failed to initialize DRI context",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Profile;
+
+    #[inline]
+    fn rules(profile: Profile, major: i32, minor: i32) -> breadx::Result<DriRules> {
+        convert_dri_rules(&[
+            GlContextRule::Profile(profile),
+            GlContextRule::MajorVersion(major),
+            GlContextRule::MinorVersion(minor),
+        ])
+    }
+
+    #[test]
+    fn api_conversion() {
+        assert_eq!(dri_api(GlApi::OpenGl), ffi::__DRI_API_OPENGL);
+        assert_eq!(dri_api(GlApi::OpenGlCore), ffi::__DRI_API_OPENGL_CORE);
+        assert_eq!(dri_api(GlApi::Gles1), ffi::__DRI_API_GLES);
+        assert_eq!(dri_api(GlApi::Gles2), ffi::__DRI_API_GLES2);
+        assert_eq!(dri_api(GlApi::Gles3), ffi::__DRI_API_GLES3);
+    }
+
+    #[test]
+    fn rule_conversion() {
+        assert_eq!(
+            rules(Profile::Compatibility, 2, 1).unwrap().api,
+            ffi::__DRI_API_OPENGL
+        );
+        assert_eq!(
+            rules(Profile::Core, 4, 5).unwrap().api,
+            ffi::__DRI_API_OPENGL_CORE
+        );
+        assert_eq!(rules(Profile::Es, 1, 1).unwrap().api, ffi::__DRI_API_GLES);
+        assert_eq!(rules(Profile::Es, 2, 0).unwrap().api, ffi::__DRI_API_GLES2);
+
+        let gles3 = rules(Profile::Es, 3, 2).unwrap();
+        assert_eq!(gles3.api, ffi::__DRI_API_GLES3);
+        assert_eq!((gles3.major, gles3.minor), (3, 2));
+
+        assert!(rules(Profile::Es, 2, 1).is_err());
+        assert!(convert_dri_rules(&[]).is_err());
+    }
+
+    #[test]
+    fn flags_only_sent_when_set() {
+        let attribs = rules(Profile::Core, 3, 3).unwrap().as_dri3_attribs();
+        assert!(!attribs.contains(&ffi::__DRI_CTX_ATTRIB_FLAGS));
+
+        let attribs = convert_dri_rules(&[GlContextRule::NoError(true)])
+            .unwrap()
+            .as_dri3_attribs();
+        let flags = attribs
+            .iter()
+            .position(|a| *a == ffi::__DRI_CTX_ATTRIB_FLAGS)
+            .expect("flags attribute is missing");
+        assert_eq!(attribs[flags + 1], ffi::__DRI_CTX_FLAG_NO_ERROR);
+    }
+
+    #[test]
+    fn context_errors() {
+        let message = |error| match context_error(error) {
+            breadx::BreadError::StaticMsg(msg) => msg,
+            e => panic!("unexpected error: {:?}", e),
+        };
+
+        assert!(message(ffi::__DRI_CTX_ERROR_BAD_API).contains("API"));
+        assert!(message(ffi::__DRI_CTX_ERROR_BAD_VERSION).contains("version"));
+        assert!(message(ffi::__DRI_CTX_ERROR_NO_MEMORY).contains("memory"));
+        assert_eq!(message(0xFFFF), "Failed to initialize DRI context");
+    }
+}
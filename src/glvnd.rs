@@ -0,0 +1,473 @@
+// MIT/Apache2 License
+
+//! Interop with libglvnd, the vendor-neutral GL dispatch library. On systems that use it, every
+//! GLX vendor library (Mesa's `libGLX_mesa.so.0`, NVIDIA's `libGLX_nvidia.so.0`, etc) shares the
+//! dispatch table in libGLdispatch, and the X server says which vendor drives each screen. GL
+//! functions are looked up through the vendor library, so they're the right ones for the screen
+//! even when several vendors are installed side by side.
+//!
+//! breadglx creates its contexts itself, using Mesa's DRI drivers, rather than through the vendor
+//! library. A vendor's functions only work with contexts that vendor created, so only Mesa's vendor
+//! library is ever used, and only for direct contexts. Screens driven by other vendors, like
+//! NVIDIA's, don't have a vendor library at all; their functions are looked up the same way they
+//! are without libglvnd, and the other vendor's library is never loaded.
+
+use crate::{
+    context::{Backend, ProcAddress},
+    dll::Dll,
+    mesa,
+};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::{
+    ffi::{c_void, CStr},
+    fmt,
+    os::raw::{c_int, c_uchar, c_ulong},
+    ptr::{self, addr_of_mut, NonNull},
+};
+
+/// The name of the GLX string listing the vendors of a screen, from GLX_EXT_libglvnd.
+pub(crate) const GLX_VENDOR_NAMES_EXT: u32 = 0x20F6;
+/// The environment variable that forces every screen to use one vendor.
+pub(crate) const VENDOR_OVERRIDE_VAR: &str = "__GLX_VENDOR_LIBRARY_NAME";
+
+/// Mesa's vendor library, which is also the one used if the server doesn't name one, same as
+/// libglvnd.
+const MESA_VENDOR: &str = "mesa";
+
+/// The version of libGLdispatch's ABI that breadglx understands.
+const GLDISPATCH_ABI_VERSION: c_int = 1;
+/// The version of the vendor library ABI that breadglx understands, 1.1.
+const GLX_VENDOR_ABI_VERSION: u32 = (1 << 16) | 1;
+
+function_table! {
+    /// Functions from libGLdispatch, which owns the dispatch table shared by the vendor libraries.
+    #[derive(Debug, Copy, Clone)]
+    pub(crate) struct GlDispatchFunctions {
+        init: unsafe extern "C" fn() = "__glDispatchInit",
+        get_abi_version: unsafe extern "C" fn() -> c_int = "__glDispatchGetABIVersion",
+    }
+}
+
+/// Initialize libGLdispatch after it's been loaded. This has to happen before any vendor library is
+/// loaded.
+#[inline]
+pub(crate) fn init_gldispatch(gldispatch: &GlDispatchFunctions) -> breadx::Result {
+    let version = unsafe { (gldispatch.get_abi_version)() };
+    if version != GLDISPATCH_ABI_VERSION {
+        return Err(breadx::BreadError::Msg(format!(
+            "LibGLdispatch has ABI version {}, but breadglx only supports version {}",
+            version, GLDISPATCH_ABI_VERSION
+        )));
+    }
+
+    unsafe { (gldispatch.init)() };
+    Ok(())
+}
+
+type VendorFn = Option<unsafe extern "C" fn()>;
+
+// Pointers to Xlib and GLX objects that vendors pass back to us. breadglx has no Xlib display, and
+// never gives vendors any of these, so they're only ever used as map keys.
+type XDisplay = *mut c_void;
+type GlxObject = *mut c_void;
+type VendorInfo = *mut c_void;
+
+/// The functions libglvnd provides to vendor libraries, laid out like `__GLXapiExports`.
+#[repr(C)]
+struct GlxExports {
+    get_dyn_dispatch: unsafe extern "C" fn(XDisplay, c_int) -> VendorInfo,
+    get_current_dyn_dispatch: unsafe extern "C" fn() -> VendorInfo,
+    fetch_dispatch_entry: unsafe extern "C" fn(VendorInfo, c_int) -> VendorFn,
+    get_current_context: unsafe extern "C" fn() -> GlxObject,
+    add_vendor_context_mapping: unsafe extern "C" fn(XDisplay, GlxObject, VendorInfo) -> c_int,
+    remove_vendor_context_mapping: unsafe extern "C" fn(XDisplay, GlxObject),
+    vendor_from_context: unsafe extern "C" fn(GlxObject) -> VendorInfo,
+    add_vendor_fbconfig_mapping: unsafe extern "C" fn(XDisplay, GlxObject, VendorInfo) -> c_int,
+    remove_vendor_fbconfig_mapping: unsafe extern "C" fn(XDisplay, GlxObject),
+    vendor_from_fbconfig: unsafe extern "C" fn(XDisplay, GlxObject) -> VendorInfo,
+    add_vendor_drawable_mapping: unsafe extern "C" fn(XDisplay, c_ulong, VendorInfo) -> c_int,
+    remove_vendor_drawable_mapping: unsafe extern "C" fn(XDisplay, c_ulong),
+    vendor_from_drawable: unsafe extern "C" fn(XDisplay, c_ulong) -> VendorInfo,
+}
+
+// breadglx does its own GLX dispatch, so vendors are never asked to route GLX calls through
+// libglvnd. They can still call these, so the dispatch functions report that there's nothing to
+// dispatch to, and the mapping functions keep track of what they're told.
+
+static GLX_EXPORTS: GlxExports = GlxExports {
+    get_dyn_dispatch,
+    get_current_dyn_dispatch,
+    fetch_dispatch_entry,
+    get_current_context,
+    add_vendor_context_mapping,
+    remove_vendor_context_mapping,
+    vendor_from_context,
+    add_vendor_fbconfig_mapping,
+    remove_vendor_fbconfig_mapping,
+    vendor_from_fbconfig,
+    add_vendor_drawable_mapping,
+    remove_vendor_drawable_mapping,
+    vendor_from_drawable,
+};
+
+/// The kinds of objects vendors can map to themselves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum MappingKind {
+    Context,
+    FbConfig,
+    Drawable,
+}
+
+/// The vendor that owns each object, keyed by the kind of object, its display and its handle.
+/// Contexts aren't tied to a display, so they're stored with a display of zero.
+static VENDOR_MAPPINGS: Lazy<DashMap<(MappingKind, usize, usize), usize>> = Lazy::new(DashMap::new);
+
+#[inline]
+fn add_mapping(kind: MappingKind, dpy: XDisplay, object: usize, vendor: VendorInfo) -> c_int {
+    if vendor.is_null() {
+        return -1;
+    }
+    VENDOR_MAPPINGS.insert((kind, dpy as usize, object), vendor as usize);
+    0
+}
+
+#[inline]
+fn remove_mapping(kind: MappingKind, dpy: XDisplay, object: usize) {
+    VENDOR_MAPPINGS.remove(&(kind, dpy as usize, object));
+}
+
+#[inline]
+fn lookup_mapping(kind: MappingKind, dpy: XDisplay, object: usize) -> VendorInfo {
+    VENDOR_MAPPINGS
+        .get(&(kind, dpy as usize, object))
+        .map_or(ptr::null_mut(), |vendor| *vendor as VendorInfo)
+}
+
+unsafe extern "C" fn get_dyn_dispatch(_dpy: XDisplay, _screen: c_int) -> VendorInfo {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn get_current_dyn_dispatch() -> VendorInfo {
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn fetch_dispatch_entry(_vendor: VendorInfo, _index: c_int) -> VendorFn {
+    None
+}
+
+unsafe extern "C" fn get_current_context() -> GlxObject {
+    // breadglx contexts aren't GLX contexts the vendor would recognize
+    ptr::null_mut()
+}
+
+unsafe extern "C" fn add_vendor_context_mapping(
+    _dpy: XDisplay,
+    context: GlxObject,
+    vendor: VendorInfo,
+) -> c_int {
+    add_mapping(
+        MappingKind::Context,
+        ptr::null_mut(),
+        context as usize,
+        vendor,
+    )
+}
+
+unsafe extern "C" fn remove_vendor_context_mapping(_dpy: XDisplay, context: GlxObject) {
+    remove_mapping(MappingKind::Context, ptr::null_mut(), context as usize)
+}
+
+unsafe extern "C" fn vendor_from_context(context: GlxObject) -> VendorInfo {
+    lookup_mapping(MappingKind::Context, ptr::null_mut(), context as usize)
+}
+
+unsafe extern "C" fn add_vendor_fbconfig_mapping(
+    dpy: XDisplay,
+    config: GlxObject,
+    vendor: VendorInfo,
+) -> c_int {
+    add_mapping(MappingKind::FbConfig, dpy, config as usize, vendor)
+}
+
+unsafe extern "C" fn remove_vendor_fbconfig_mapping(dpy: XDisplay, config: GlxObject) {
+    remove_mapping(MappingKind::FbConfig, dpy, config as usize)
+}
+
+unsafe extern "C" fn vendor_from_fbconfig(dpy: XDisplay, config: GlxObject) -> VendorInfo {
+    lookup_mapping(MappingKind::FbConfig, dpy, config as usize)
+}
+
+unsafe extern "C" fn add_vendor_drawable_mapping(
+    dpy: XDisplay,
+    drawable: c_ulong,
+    vendor: VendorInfo,
+) -> c_int {
+    add_mapping(MappingKind::Drawable, dpy, drawable as usize, vendor)
+}
+
+unsafe extern "C" fn remove_vendor_drawable_mapping(dpy: XDisplay, drawable: c_ulong) {
+    remove_mapping(MappingKind::Drawable, dpy, drawable as usize)
+}
+
+unsafe extern "C" fn vendor_from_drawable(dpy: XDisplay, drawable: c_ulong) -> VendorInfo {
+    lookup_mapping(MappingKind::Drawable, dpy, drawable as usize)
+}
+
+/// The functions a vendor library fills in when it's loaded. Only the ones at the front are named;
+/// the rest of the space is left for the fields newer vendors know about.
+///
+/// `is_screen_supported` is never called. It takes an Xlib `Display`, which breadglx doesn't have,
+/// and Mesa's implementation opens its own GLX connection through it. The server's
+/// `GLX_VENDOR_NAMES_EXT` string is used to pick a vendor for each screen instead.
+#[repr(C)]
+struct GlxImports {
+    is_screen_supported: Option<unsafe extern "C" fn(*mut c_void, c_int) -> c_int>,
+    get_proc_address: Option<unsafe extern "C" fn(*const c_uchar) -> *mut c_void>,
+    get_dispatch_address: Option<unsafe extern "C" fn(*const c_uchar) -> *mut c_void>,
+    set_dispatch_index: Option<unsafe extern "C" fn(*const c_uchar, c_int)>,
+    reserved: [VendorFn; 32],
+}
+
+type GlxMain = unsafe extern "C" fn(u32, *const GlxExports, *mut c_void, *mut GlxImports) -> c_int;
+
+/// A GLX vendor library.
+pub(crate) struct GlxVendor {
+    name: String,
+    imports: GlxImports,
+    // keep the library loaded for as long as the imports are around
+    _dll: Dll,
+}
+
+impl fmt::Debug for GlxVendor {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlxVendor")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl GlxVendor {
+    /// Load the vendor library with the given name, e.g. `libGLX_mesa.so.0` for `mesa`.
+    #[inline]
+    fn load(name: &str) -> breadx::Result<Box<GlxVendor>> {
+        // vendor libraries expect libGLdispatch to be initialized before they're loaded
+        mesa::gldispatch()?;
+
+        let lib_name = format!("libGLX_{}.so.0", name);
+        let dll = Dll::load("GLX vendor", &[&lib_name])?;
+        let glx_main = unsafe { dll.symbol::<GlxMain>(b"__glx_Main\0") }.ok_or_else(|| {
+            breadx::BreadError::Msg(format!("{} is not a GLX vendor library", lib_name))
+        })?;
+
+        let mut vendor = Box::new(GlxVendor {
+            name: name.to_string(),
+            imports: GlxImports {
+                is_screen_supported: None,
+                get_proc_address: None,
+                get_dispatch_address: None,
+                set_dispatch_index: None,
+                reserved: [None; 32],
+            },
+            _dll: dll,
+        });
+
+        // the vendor holds onto the pointer we give it to identify itself, which stays valid
+        // because vendors are never unloaded
+        let vendor_ptr: *mut GlxVendor = &mut *vendor;
+        let res = unsafe {
+            glx_main(
+                GLX_VENDOR_ABI_VERSION,
+                &GLX_EXPORTS,
+                vendor_ptr as *mut c_void,
+                addr_of_mut!((*vendor_ptr).imports),
+            )
+        };
+
+        if res == 0 {
+            Err(breadx::BreadError::Msg(format!(
+                "{} does not support GLX vendor ABI version {}.{}",
+                lib_name,
+                GLX_VENDOR_ABI_VERSION >> 16,
+                GLX_VENDOR_ABI_VERSION & 0xFFFF
+            )))
+        } else if vendor.imports.get_proc_address.is_none() {
+            Err(breadx::BreadError::Msg(format!(
+                "{} did not provide getProcAddress",
+                lib_name
+            )))
+        } else {
+            Ok(vendor)
+        }
+    }
+
+    /// The name of the vendor.
+    #[inline]
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the functions this vendor hands out work with contexts from a backend. The direct
+    /// backends render with Mesa's DRI drivers, so only Mesa's functions match them, and indirect
+    /// contexts have no driver to match.
+    #[inline]
+    pub(crate) fn supports_backend(&self, backend: Backend) -> bool {
+        match backend {
+            Backend::Dri2 | Backend::Dri3 => self.name == MESA_VENDOR,
+            Backend::Indirect => false,
+        }
+    }
+
+    /// Look up a GL function in the vendor library.
+    #[inline]
+    pub(crate) fn proc_address(&self, function: &CStr) -> Option<ProcAddress> {
+        let get_proc_address = self.imports.get_proc_address?;
+        NonNull::new(unsafe { get_proc_address(function.as_ptr() as *const _) })
+            .map(ProcAddress::from)
+    }
+}
+
+/// Vendor libraries that have already been loaded, or `None` if they failed to load. Vendors are
+/// never unloaded, since they can't be made to forget the exports they were given.
+static VENDORS: Lazy<DashMap<String, Option<&'static GlxVendor>>> = Lazy::new(DashMap::new);
+
+/// Get the vendor library with the given name, loading it if it hasn't been loaded yet.
+#[inline]
+pub(crate) fn vendor(name: &str) -> Option<&'static GlxVendor> {
+    if let Some(vendor) = VENDORS.get(name) {
+        return *vendor;
+    }
+
+    let vendor = match GlxVendor::load(name) {
+        Ok(vendor) => {
+            log::debug!("Loaded GLX vendor {}", name);
+            Some(&*Box::leak(vendor))
+        }
+        Err(e) => {
+            log::error!("Unable to load GLX vendor {}: {:?}", name, e);
+            None
+        }
+    };
+
+    // if another thread loaded it first, use theirs instead
+    *VENDORS.entry(name.to_string()).or_insert(vendor)
+}
+
+/// The vendors to try for a screen, in order. `server_names` is the screen's `GLX_VENDOR_NAMES_EXT`
+/// string, which lists vendors separated by spaces.
+#[inline]
+pub(crate) fn vendor_names(vendor_override: Option<&str>, server_names: &str) -> Vec<String> {
+    if let Some(vendor_override) = vendor_override {
+        return vec![vendor_override.to_string()];
+    }
+
+    let mut names: Vec<String> = server_names
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    if names.is_empty() {
+        names.push(MESA_VENDOR.to_string());
+    }
+    names
+}
+
+/// Choose the vendor to use out of the ones that can drive a screen. breadglx only renders with
+/// Mesa, so that's the only vendor it will choose.
+#[inline]
+fn choose_vendor_name(names: &[String]) -> Option<&'static str> {
+    if names.iter().any(|name| name == MESA_VENDOR) {
+        Some(MESA_VENDOR)
+    } else {
+        None
+    }
+}
+
+/// Choose and load the vendor library for a screen out of the vendors in `names`.
+#[inline]
+pub(crate) fn choose_vendor(names: &[String]) -> Option<&'static GlxVendor> {
+    match choose_vendor_name(names) {
+        Some(name) => vendor(name),
+        None => {
+            log::info!(
+                "Not using GLX vendors {:?}, breadglx only renders with {}",
+                names,
+                MESA_VENDOR
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn vendor_names_from_server() {
+        assert_eq!(vendor_names(None, "mesa"), names(&["mesa"]));
+        assert_eq!(
+            vendor_names(None, " nvidia  mesa\n"),
+            names(&["nvidia", "mesa"])
+        );
+        // servers without GLX_EXT_libglvnd don't name a vendor, so Mesa is assumed
+        assert_eq!(vendor_names(None, ""), names(&["mesa"]));
+        assert_eq!(vendor_names(None, " \t"), names(&["mesa"]));
+    }
+
+    #[test]
+    fn vendor_names_override() {
+        assert_eq!(vendor_names(Some("nvidia"), "mesa"), names(&["nvidia"]));
+        assert_eq!(vendor_names(Some("mesa"), ""), names(&["mesa"]));
+    }
+
+    #[test]
+    fn only_mesa_is_chosen() {
+        assert_eq!(choose_vendor_name(&names(&["mesa"])), Some("mesa"));
+        assert_eq!(
+            choose_vendor_name(&names(&["nvidia", "mesa"])),
+            Some("mesa")
+        );
+        assert_eq!(choose_vendor_name(&names(&["nvidia"])), None);
+        assert_eq!(choose_vendor_name(&names(&["Mesa"])), None);
+        assert_eq!(choose_vendor_name(&[]), None);
+    }
+
+    #[test]
+    fn other_vendors_are_not_loaded() {
+        assert!(choose_vendor(&names(&["breadglx_fake"])).is_none());
+        assert!(VENDORS.get("breadglx_fake").is_none());
+    }
+
+    #[test]
+    fn exports_track_mappings() {
+        let dpy = 0x1000 as XDisplay;
+        let vendor = 0x2000 as VendorInfo;
+        let config = 0x3000 as GlxObject;
+
+        unsafe {
+            assert!((GLX_EXPORTS.vendor_from_fbconfig)(dpy, config).is_null());
+            assert_eq!(
+                (GLX_EXPORTS.add_vendor_fbconfig_mapping)(dpy, config, vendor),
+                0
+            );
+            assert_eq!((GLX_EXPORTS.vendor_from_fbconfig)(dpy, config), vendor);
+            // the same handle on another display, or as another kind of object, is separate
+            assert!((GLX_EXPORTS.vendor_from_fbconfig)(ptr::null_mut(), config).is_null());
+            assert!((GLX_EXPORTS.vendor_from_drawable)(dpy, config as c_ulong).is_null());
+            (GLX_EXPORTS.remove_vendor_fbconfig_mapping)(dpy, config);
+            assert!((GLX_EXPORTS.vendor_from_fbconfig)(dpy, config).is_null());
+
+            assert_ne!(
+                (GLX_EXPORTS.add_vendor_context_mapping)(dpy, config, ptr::null_mut()),
+                0
+            );
+            assert!((GLX_EXPORTS.get_current_context)().is_null());
+            assert!((GLX_EXPORTS.fetch_dispatch_entry)(vendor, 0).is_none());
+        }
+    }
+}
@@ -1,19 +1,26 @@
 // MIT/Apache2 License
 
 use crate::{
-    context::{GlInternalContext, ProcAddress, Profile},
+    context::{ContextInfo, GlApi, GlInternalContext, ProcAddress, Profile},
     display::{DisplayLike, GlDisplay},
 };
 use breadx::{
+    auto::glx,
     display::{Connection, Display},
     Drawable,
 };
-use std::{ffi::CStr, fmt};
+use std::{ffi::CStr, fmt, os::raw::c_int};
 
 #[cfg(feature = "async")]
 use crate::{context::AsyncGlInternalContext, util::GenericFuture};
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
+#[cfg(feature = "async")]
+use futures_lite::future;
+
+// breadglx doesn't implement the GLX rendering protocol, so there's nothing to render with once an
+// indirect context is bound
+const BIND_UNSUPPORTED: &str = "Binding indirect contexts is not supported";
 
 pub struct IndirectContext<Dpy> {
     // hold a reference to the display so we can call commands
@@ -34,6 +41,24 @@ impl<Dpy> fmt::Debug for IndirectContext<Dpy> {
     }
 }
 
+impl<Dpy> IndirectContext<Dpy> {
+    #[inline]
+    pub(crate) fn new(display: GlDisplay<Dpy>, info: &ContextInfo) -> Self {
+        Self {
+            display,
+            glx_buffer: Vec::new(),
+            render_type: info.render_type.into(),
+            major_version: info.major as _,
+            minor_version: info.minor as _,
+            profile: match info.api {
+                GlApi::OpenGl => Profile::Compatibility,
+                GlApi::OpenGlCore => Profile::Core,
+                GlApi::Gles1 | GlApi::Gles2 | GlApi::Gles3 => Profile::Es,
+            },
+        }
+    }
+}
+
 impl<Dpy: DisplayLike> GlInternalContext<Dpy> for IndirectContext<Dpy>
 where
     Dpy::Connection: Connection,
@@ -45,24 +70,39 @@ where
         read: Option<Drawable>,
         draw: Option<Drawable>,
     ) -> breadx::Result {
-        unimplemented!()
+        Err(breadx::BreadError::StaticMsg(BIND_UNSUPPORTED))
     }
 
     #[inline]
     fn unbind(&self) -> breadx::Result {
-        unimplemented!()
+        // indirect contexts are never bound, so there's nothing to do
+        Ok(())
     }
 
     #[inline]
     fn get_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
-        unimplemented!()
+        // there's no driver behind an indirect context to look functions up in
+        None
+    }
+
+    #[inline]
+    fn query(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> breadx::Result<Option<c_int>> {
+        Ok(dpy
+            .query_context(xid)?
+            .get(&(attrib as u32))
+            .map(|val| *val as c_int))
     }
 }
 
 #[cfg(feature = "async")]
 impl<Dpy: DisplayLike> AsyncGlInternalContext<Dpy> for IndirectContext<Dpy>
 where
-    Dpy::Connection: AsyncConnection,
+    Dpy::Connection: AsyncConnection + Send,
 {
     #[inline]
     fn bind_async<'future, 'a, 'b>(
@@ -75,12 +115,14 @@ where
         'a: 'future,
         'b: 'future,
     {
-        Box::pin(async { unimplemented!() })
+        Box::pin(future::ready(Err(breadx::BreadError::StaticMsg(
+            BIND_UNSUPPORTED,
+        ))))
     }
 
     #[inline]
     fn unbind_async<'future>(&'future self) -> GenericFuture<'future, breadx::Result> {
-        Box::pin(async { unimplemented!() })
+        Box::pin(future::ready(Ok(())))
     }
 
     #[inline]
@@ -92,6 +134,26 @@ where
         'a: 'future,
         'b: 'future,
     {
-        Box::pin(async { unimplemented!() })
+        Box::pin(future::ready(None))
+    }
+
+    #[inline]
+    fn query_async<'future, 'a, 'b>(
+        &'a self,
+        dpy: &'b GlDisplay<Dpy>,
+        xid: glx::Context,
+        attrib: c_int,
+    ) -> GenericFuture<'future, breadx::Result<Option<c_int>>>
+    where
+        'a: 'future,
+        'b: 'future,
+    {
+        Box::pin(async move {
+            Ok(dpy
+                .query_context_async(xid)
+                .await?
+                .get(&(attrib as u32))
+                .map(|val| *val as c_int))
+        })
     }
 }
@@ -31,23 +31,28 @@ impl<Dpy> fmt::Debug for IndirectDisplay<Dpy> {
 impl<Dpy: DisplayLike> IndirectDisplay<Dpy> {
     #[inline]
     pub fn new(_dpy: &mut Display<Dpy::Connection>) -> breadx::Result<Self> {
-        /*Ok(Self {
+        Ok(Self {
             _private: PhantomData,
-        })*/
-        Err(breadx::BreadError::StaticMsg(
-            "Indirect displays are currently unavailable",
-        ))
+        })
     }
 
     #[cfg(feature = "async")]
     #[inline]
     pub async fn new_async(_dpy: &mut Display<Dpy::Connection>) -> breadx::Result<Self> {
-        /*Ok(Self {
+        Ok(Self {
             _private: PhantomData,
-        })*/
-        Err(breadx::BreadError::StaticMsg(
-            "Indirect displays are currently unavailable",
-        ))
+        })
+    }
+}
+
+#[cfg(test)]
+impl<Dpy> IndirectDisplay<Dpy> {
+    /// An indirect display that never talked to the server.
+    #[inline]
+    pub(crate) fn placeholder() -> Self {
+        Self {
+            _private: PhantomData,
+        }
     }
 }
 
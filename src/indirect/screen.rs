@@ -13,6 +13,15 @@ use std::{fmt, marker::PhantomData, sync::Arc};
 use crate::{screen::AsyncGlInternalScreen, util::GenericFuture};
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
+#[cfg(feature = "async")]
+use futures_lite::future;
+
+// breadglx doesn't implement the GLX rendering protocol, so indirect screens can't create contexts
+// or present anything. This is reported as an error rather than a panic, since the indirect
+// display is what's left when direct rendering can't be set up.
+const CREATE_CONTEXT_UNSUPPORTED: &str =
+    "Creating indirect contexts is not supported; direct rendering is unavailable";
+const SWAP_BUFFERS_UNSUPPORTED: &str = "Swapping buffers on an indirect screen is not supported";
 
 pub struct IndirectScreen<Dpy> {
     // As before, the indirect screen doesn't really have any
@@ -48,7 +57,7 @@ where
         rules: &[GlContextRule],
         share: Option<&GlContext<Dpy>>,
     ) -> breadx::Result<ContextDispatch<Dpy>> {
-        unimplemented!()
+        Err(breadx::BreadError::StaticMsg(CREATE_CONTEXT_UNSUPPORTED))
     }
 
     #[inline]
@@ -61,7 +70,7 @@ where
         remainder: i64,
         flush: bool,
     ) -> breadx::Result {
-        unimplemented!()
+        Err(breadx::BreadError::StaticMsg(SWAP_BUFFERS_UNSUPPORTED))
     }
 }
 
@@ -85,7 +94,9 @@ where
         'd: 'future,
         'e: 'future,
     {
-        Box::pin(async { unimplemented!() })
+        Box::pin(future::ready(Err(breadx::BreadError::StaticMsg(
+            CREATE_CONTEXT_UNSUPPORTED,
+        ))))
     }
 
     #[inline]
@@ -102,6 +113,8 @@ where
         'a: 'future,
         'b: 'future,
     {
-        Box::pin(async { unimplemented!() })
+        Box::pin(future::ready(Err(breadx::BreadError::StaticMsg(
+            SWAP_BUFFERS_UNSUPPORTED,
+        ))))
     }
 }
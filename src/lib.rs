@@ -11,11 +11,14 @@
 
 pub(crate) mod auto;
 pub(crate) mod cstr;
+#[macro_use]
 pub(crate) mod dll;
 pub(crate) mod indirect;
 pub(crate) mod mesa;
 pub(crate) mod util;
 
+#[cfg(feature = "glvnd")]
+pub(crate) mod glvnd;
 #[cfg(feature = "async")]
 pub(crate) mod offload;
 
@@ -28,7 +31,10 @@ pub mod screen;
 pub use config::*;
 pub use context::*;
 pub use display::*;
+pub use drawable::*;
 pub use screen::*;
 
 #[cfg(feature = "dri")]
 pub(crate) mod dri;
+#[cfg(feature = "dri")]
+pub use dri::config::{DriConfigDiagnostic, DriConfigMismatch};
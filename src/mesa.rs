@@ -2,7 +2,16 @@
 
 //! This module provides links to the Mesa3D bindings.
 
-use super::dll::Dll;
+use super::dll::{Dll, LoadedDll};
+use std::{
+    ffi::c_void,
+    os::raw::{c_char, c_int},
+};
+
+#[cfg(feature = "dri")]
+use crate::dri::load::DrmFunctions;
+#[cfg(feature = "glvnd")]
+use crate::glvnd::{self, GlDispatchFunctions};
 
 #[cfg(not(feature = "async"))]
 use once_cell::sync::Lazy;
@@ -84,15 +93,48 @@ const GL_LIB_NAMES: [&str; 2] = ["libGL.so", "libGL.so.1"];
 const DRM_LIB_NAMES: [&str; 2] = ["libdrm.so", "libdrm.so.2"];
 const XSHMFENCE_LIB_NAMES: [&str; 2] = ["libxshmfence.so", "libxshmfence.so.1"];
 const GLAPI_LIB_NAMES: [&str; 3] = ["libglapi.so", "libglapi.so.0", "libglapi.so.0.0.0"];
+#[cfg(feature = "glvnd")]
+const GLDISPATCH_LIB_NAMES: [&str; 2] = ["libGLdispatch.so.0", "libGLdispatch.so"];
+
+function_table! {
+    /// Functions from libxshmfence, used to synchronize buffers with the X server.
+    #[derive(Debug, Copy, Clone)]
+    pub(crate) struct XshmfenceFunctions {
+        alloc_shm: unsafe extern "C" fn() -> c_int = "xshmfence_alloc_shm",
+        map_shm: unsafe extern "C" fn(c_int) -> *mut c_void = "xshmfence_map_shm",
+        unmap_shm: unsafe extern "C" fn(*mut c_void) = "xshmfence_unmap_shm",
+        trigger: unsafe extern "C" fn(*mut c_void) -> c_int = "xshmfence_trigger",
+        reset: unsafe extern "C" fn(*mut c_void) = "xshmfence_reset",
+        await_fence: unsafe extern "C" fn(*mut c_void) -> c_int = "xshmfence_await",
+    }
+}
+
+function_table! {
+    /// Functions from libglapi, which holds the GL dispatch table.
+    #[derive(Debug, Copy, Clone)]
+    pub(crate) struct GlapiFunctions {
+        get_proc_address: unsafe extern "C" fn(*const c_char) -> *mut c_void =
+            "_glapi_get_proc_address",
+    }
+}
 
 static GL: Lazy<breadx::Result<Dll>> = Lazy::new(|| Dll::load("LibGL", &GL_LIB_NAMES));
-static DRM: Lazy<breadx::Result<Dll>> = Lazy::new(|| Dll::load("LibDRM", &DRM_LIB_NAMES));
-static XSHMFENCE: Lazy<breadx::Result<Dll>> =
-    Lazy::new(|| Dll::load("LibXShmFence", &XSHMFENCE_LIB_NAMES));
-static GLAPI: Lazy<breadx::Result<Dll>> = Lazy::new(|| Dll::load("LibGLAPI", &GLAPI_LIB_NAMES));
+#[cfg(feature = "dri")]
+static DRM: Lazy<breadx::Result<LoadedDll<DrmFunctions>>> =
+    Lazy::new(|| LoadedDll::load("LibDRM", &DRM_LIB_NAMES));
+static XSHMFENCE: Lazy<breadx::Result<LoadedDll<XshmfenceFunctions>>> =
+    Lazy::new(|| LoadedDll::load("LibXShmFence", &XSHMFENCE_LIB_NAMES));
+static GLAPI: Lazy<breadx::Result<LoadedDll<GlapiFunctions>>> =
+    Lazy::new(|| LoadedDll::load("LibGLAPI", &GLAPI_LIB_NAMES));
+#[cfg(feature = "glvnd")]
+static GLDISPATCH: Lazy<breadx::Result<LoadedDll<GlDispatchFunctions>>> = Lazy::new(|| {
+    let gldispatch = LoadedDll::load("LibGLdispatch", &GLDISPATCH_LIB_NAMES)?;
+    glvnd::init_gldispatch(&gldispatch)?;
+    Ok(gldispatch)
+});
 
 #[inline]
-fn unwrap_result(res: &breadx::Result<Dll>) -> breadx::Result<&Dll> {
+fn unwrap_result<T>(res: &breadx::Result<T>) -> breadx::Result<&T> {
     match res {
         Ok(ref dll) => Ok(dll),
         Err(e) => Err(e.clone()),
@@ -119,60 +161,76 @@ pub(crate) async fn gl_async() -> breadx::Result<&'static Dll> {
     unwrap_result(GL.get().await)
 }
 
+#[cfg(feature = "dri")]
 #[inline]
-pub(crate) fn drm() -> breadx::Result<&'static Dll> {
+pub(crate) fn drm() -> breadx::Result<&'static DrmFunctions> {
     #[cfg(feature = "async")]
     {
         future::block_on(drm_async())
     }
     #[cfg(not(feature = "async"))]
     {
-        let res = &*DRM;
-
-        unwrap_result(res)
+        unwrap_result(&*DRM).map(|drm| &**drm)
     }
 }
 
-#[cfg(feature = "async")]
+#[cfg(all(feature = "dri", feature = "async"))]
 #[inline]
-pub(crate) async fn drm_async() -> breadx::Result<&'static Dll> {
-    unwrap_result(DRM.get().await)
+pub(crate) async fn drm_async() -> breadx::Result<&'static DrmFunctions> {
+    unwrap_result(DRM.get().await).map(|drm| &**drm)
 }
 
 #[inline]
-pub(crate) fn xshmfence() -> breadx::Result<&'static Dll> {
+pub(crate) fn xshmfence() -> breadx::Result<&'static XshmfenceFunctions> {
     #[cfg(feature = "async")]
     {
         future::block_on(xshmfence_async())
     }
     #[cfg(not(feature = "async"))]
     {
-        let res = &*XSHMFENCE;
-        unwrap_result(res)
+        unwrap_result(&*XSHMFENCE).map(|xshmfence| &**xshmfence)
     }
 }
 
 #[cfg(feature = "async")]
 #[inline]
-pub(crate) async fn xshmfence_async() -> breadx::Result<&'static Dll> {
-    unwrap_result(XSHMFENCE.get().await)
+pub(crate) async fn xshmfence_async() -> breadx::Result<&'static XshmfenceFunctions> {
+    unwrap_result(XSHMFENCE.get().await).map(|xshmfence| &**xshmfence)
 }
 
 #[inline]
-pub(crate) fn glapi() -> breadx::Result<&'static Dll> {
+pub(crate) fn glapi() -> breadx::Result<&'static GlapiFunctions> {
     #[cfg(feature = "async")]
     {
         future::block_on(glapi_async())
     }
     #[cfg(not(feature = "async"))]
     {
-        let res = &*GLAPI;
-        unwrap_result(res)
+        unwrap_result(&*GLAPI).map(|glapi| &**glapi)
     }
 }
 
 #[cfg(feature = "async")]
 #[inline]
-pub(crate) async fn glapi_async() -> breadx::Result<&'static Dll> {
-    unwrap_result(GLAPI.get().await)
+pub(crate) async fn glapi_async() -> breadx::Result<&'static GlapiFunctions> {
+    unwrap_result(GLAPI.get().await).map(|glapi| &**glapi)
+}
+
+#[cfg(feature = "glvnd")]
+#[inline]
+pub(crate) fn gldispatch() -> breadx::Result<&'static GlDispatchFunctions> {
+    #[cfg(feature = "async")]
+    {
+        future::block_on(gldispatch_async())
+    }
+    #[cfg(not(feature = "async"))]
+    {
+        unwrap_result(&*GLDISPATCH).map(|gldispatch| &**gldispatch)
+    }
+}
+
+#[cfg(all(feature = "glvnd", feature = "async"))]
+#[inline]
+pub(crate) async fn gldispatch_async() -> breadx::Result<&'static GlDispatchFunctions> {
+    unwrap_result(GLDISPATCH.get().await).map(|gldispatch| &**gldispatch)
 }
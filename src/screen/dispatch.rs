@@ -3,13 +3,13 @@
 use super::GlInternalScreen;
 use crate::{
     config::GlConfig,
-    context::{ContextDispatch, GlContext, GlContextRule, InnerGlContext},
+    context::{Backend, ContextDispatch, GlContext, GlContextRule, InnerGlContext, ProcAddress},
     display::{DisplayLike, GlDisplay},
-    dri::{dri2, dri3},
+    dri::{config::DriConfigDiagnostic, dri2, dri3},
     indirect,
 };
 use breadx::{display::Connection, Drawable};
-use std::sync::Arc;
+use std::{ffi::CStr, sync::Arc};
 
 #[cfg(feature = "async")]
 use crate::{screen::AsyncGlInternalScreen, util::GenericFuture};
@@ -48,6 +48,49 @@ impl<Dpy> From<dri3::Dri3Screen<Dpy>> for ScreenDispatch<Dpy> {
     }
 }
 
+impl<Dpy> ScreenDispatch<Dpy> {
+    #[inline]
+    pub fn is_direct(&self) -> bool {
+        match self {
+            Self::Indirect(_) => false,
+            #[cfg(feature = "dri")]
+            Self::Dri2(_) => true,
+            #[cfg(feature = "dri3")]
+            Self::Dri3(_) => true,
+        }
+    }
+
+    #[cfg(feature = "dri")]
+    #[inline]
+    pub fn dri_config_diagnostics(&self) -> Option<&[DriConfigDiagnostic]> {
+        match self {
+            #[cfg(feature = "dri3")]
+            Self::Dri3(d3) => Some(d3.config_diagnostics()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn driver_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
+        match self {
+            #[cfg(feature = "dri3")]
+            Self::Dri3(d3) => d3.driver_proc_address(name),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        match self {
+            Self::Indirect(_) => Backend::Indirect,
+            #[cfg(feature = "dri")]
+            Self::Dri2(_) => Backend::Dri2,
+            #[cfg(feature = "dri3")]
+            Self::Dri3(_) => Backend::Dri3,
+        }
+    }
+}
+
 impl<Dpy: DisplayLike> GlInternalScreen<Dpy> for ScreenDispatch<Dpy>
 where
     Dpy::Connection: Connection,
@@ -1,13 +1,13 @@
 // MIT/Apache2 License
 
 use crate::{
-    config::{GlConfig, GlConfigRule},
+    config::{FbConfigError, FbConfigRequest, GlConfig, GlConfigRule},
     context::{
-        dispatch::ContextDispatch, promote_anyarc_ref, GlContext, GlContextRule, GlInternalContext,
-        InnerGlContext,
+        dispatch::ContextDispatch, promote_anyarc_ref, ContextInfo, GlApi, GlContext,
+        GlContextRule, InnerGlContext, ProcAddress, ShareGroup,
     },
     display::{DisplayLike, GlDisplay},
-    dri::{dri2, dri3},
+    dri::{config::DriConfigDiagnostic, dri2, dri3},
     indirect,
 };
 use breadx::{
@@ -15,14 +15,31 @@ use breadx::{
     display::{Connection, Display},
     Drawable, XidType,
 };
-use std::{convert::TryInto, sync::Arc};
+use std::{ffi::CStr, sync::Arc};
 
 #[cfg(feature = "async")]
 use crate::util::GenericFuture;
 #[cfg(feature = "async")]
 use breadx::display::AsyncConnection;
 
+/// Make sure a screen can create contexts for a client API. GLX has no protocol for OpenGL ES, so
+/// ES contexts can only be created through direct rendering.
+#[inline]
+fn check_api_support(api: GlApi, direct: bool) -> breadx::Result {
+    if api.is_es() && !direct {
+        Err(breadx::BreadError::StaticMsg(
+            "OpenGL ES contexts require direct rendering",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 mod dispatch;
+mod visual;
+mod window;
+
+pub use visual::*;
 
 /// The screen used by the GL system.
 #[derive(Debug)]
@@ -142,12 +159,87 @@ impl<Dpy> GlScreen<Dpy> {
         }
     }
 
+    /// Figure out the API a context created with these rules would use, and make sure that this screen
+    /// can support it.
+    #[inline]
+    fn context_info(&self, rules: &[GlContextRule]) -> breadx::Result<ContextInfo> {
+        let info = ContextInfo::from_rules(rules)?;
+        check_api_support(info.api, self.disp.is_direct())?;
+        Ok(info)
+    }
+
+    /// Get the share group a new context should join, making sure the share context (if any) is
+    /// compatible with this screen before the backend ever sees it.
+    #[inline]
+    fn share_group(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        share: Option<&GlContext<Dpy>>,
+    ) -> breadx::Result<ShareGroup<Dpy>> {
+        let backend = self.disp.backend();
+        let direct = self.disp.is_direct();
+        match share {
+            Some(share) => {
+                let group = share.share_group();
+                group.validate(dpy, self.screen, backend, direct)?;
+                Ok(group.clone())
+            }
+            None => Ok(ShareGroup::new(dpy.clone(), self.screen, backend, direct)),
+        }
+    }
+
+    /// Make sure that a config is one of this screen's fbconfigs or visuals.
+    #[inline]
+    fn check_config(&self, fbconfig: &GlConfig) -> breadx::Result {
+        if self
+            .fbconfigs
+            .iter()
+            .chain(self.visuals.iter())
+            .any(|cfg| cfg == fbconfig)
+        {
+            Ok(())
+        } else {
+            Err(breadx::BreadError::StaticMsg(
+                "Config does not belong to this screen",
+            ))
+        }
+    }
+
     /// Get the framebuffer configs associated with this screen.
     #[inline]
     pub fn fbconfigs(&self) -> &[GlConfig] {
         &self.fbconfigs
     }
 
+    /// Get the configs describing the visuals associated with this screen.
+    #[inline]
+    pub fn visuals(&self) -> &[GlConfig] {
+        &self.visuals
+    }
+
+    /// Look up a GL function in the driver behind this screen. Only DRI3 screens have a driver.
+    #[inline]
+    pub(crate) fn driver_proc_address(&self, name: &CStr) -> Option<ProcAddress> {
+        self.disp.driver_proc_address(name)
+    }
+
+    /// The rendering backend this screen's contexts use. This isn't necessarily the display's
+    /// backend, since screens without a usable driver fall back to indirect rendering.
+    #[cfg(feature = "glvnd")]
+    #[inline]
+    pub(crate) fn backend(&self) -> crate::context::Backend {
+        self.disp.backend()
+    }
+
+    /// Explain how this screen's visuals and framebuffer configs were matched with the configs the
+    /// DRI driver supports. Configs without a matching driver config can't be used for direct
+    /// rendering. Returns `None` if this screen doesn't use DRI3.
+    #[cfg(feature = "dri")]
+    #[inline]
+    pub fn dri_config_diagnostics(&self) -> Option<&[DriConfigDiagnostic]> {
+        self.disp.dri_config_diagnostics()
+    }
+
     /// Get the framebuffer configs matching a certain set of rules.
     #[inline]
     pub fn choose_fbconfigs<'a, 'b>(
@@ -161,6 +253,32 @@ impl<Dpy> GlScreen<Dpy> {
             .iter()
             .filter(move |fb| fb.fulfills_rules(rules))
     }
+
+    /// Get the framebuffer configs matching a certain set of rules, sorted from best to worst match.
+    /// This follows the semantics of `glXChooseFBConfig`: attributes not mentioned in the rules use
+    /// the GLX defaults (e.g. `RenderType(RGBA_BIT)` and `DrawableType(WINDOW_BIT)`), and configs are
+    /// sorted by visual select group, caveat, color bits, buffer size, double buffering, aux
+    /// buffers, samples, depth, stencil, accumulation bits and visual type, in that order. The
+    /// visual select group comes first because libGL sorts by it first too.
+    #[inline]
+    pub fn choose_fbconfigs_sorted(&self, rules: &[GlConfigRule]) -> Vec<&GlConfig> {
+        let template = GlConfig::chooser_template(rules);
+        let mut configs: Vec<&GlConfig> = self
+            .fbconfigs
+            .iter()
+            .filter(|fb| fb.matches_template(&template))
+            .collect();
+        // stable sort, so configs that compare equal stay in the order the server gave them in
+        configs.sort_by(|a, b| a.cmp_for_template(b, &template));
+        configs
+    }
+
+    /// Choose the framebuffer config that best fits a request. See `FbConfigRequest` for more
+    /// information.
+    #[inline]
+    pub fn choose_fbconfig(&self, request: &FbConfigRequest) -> Result<&GlConfig, FbConfigError> {
+        request.choose(&self.fbconfigs)
+    }
 }
 
 impl<Dpy: DisplayLike> GlScreen<Dpy>
@@ -194,7 +312,22 @@ where
         log::trace!("Creating context...");
 
         // create the base
-        let mut ctx = GlContext::new(Context::from_xid(0), self.screen, fbconfig.clone());
+        self.check_config(fbconfig)?;
+        let info = self.context_info(rules)?;
+        let share_group = self.share_group(dpy, share)?;
+        let share_xid = match share {
+            Some(share) => share.xid(),
+            None => Context::default(),
+        };
+        let mut ctx = GlContext::new(
+            Context::from_xid(0),
+            self.screen,
+            fbconfig.clone(),
+            share_xid,
+            share.cloned(),
+            share_group,
+            info,
+        );
         // create the dispatch
         let disp = self
             .disp
@@ -210,16 +343,15 @@ where
         let xid = dpy.display().create_context_attribs_arb(
             glx::Fbconfig::const_from_xid(fbconfig.fbconfig_id as _),
             self.screen,
-            match share {
-                Some(share) => share.xid(),
-                None => Context::default(),
-            },
+            share_xid,
             ctx.dispatch().is_direct(),
             attribs,
         )?;
         Arc::get_mut(&mut ctx.inner)
             .expect("Infallible Arc::get_mut()")
             .xid = xid;
+        // only join the group once we're done mutating the context
+        ctx.share_group().join(&ctx);
 
         log::trace!("Created context.");
         Ok(ctx)
@@ -231,6 +363,19 @@ impl<Dpy: DisplayLike> GlScreen<Dpy>
 where
     Dpy::Connection: AsyncConnection + Send,
 {
+    /// Swap buffers for this screen, async redox.
+    #[inline]
+    pub async fn swap_buffers_async<Target: Into<Drawable>>(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        drawable: Target,
+    ) -> breadx::Result {
+        let drawable = drawable.into();
+        self.disp
+            .swap_buffers_async(dpy, drawable, 0, 0, 0, true)
+            .await
+    }
+
     /// Create an OpenGL context, async redox.
     #[inline]
     pub async fn create_context_async(
@@ -241,7 +386,22 @@ where
         share: Option<&GlContext<Dpy>>,
     ) -> breadx::Result<GlContext<Dpy>> {
         // as above, so below
-        let mut ctx = GlContext::new(Context::from_xid(0), self.screen, fbconfig.clone());
+        self.check_config(fbconfig)?;
+        let info = self.context_info(rules)?;
+        let share_group = self.share_group(dpy, share)?;
+        let share_xid = match share {
+            Some(share) => share.xid(),
+            None => Context::default(),
+        };
+        let mut ctx = GlContext::new(
+            Context::from_xid(0),
+            self.screen,
+            fbconfig.clone(),
+            share_xid,
+            share.cloned(),
+            share_group,
+            info,
+        );
         let disp = self
             .disp
             .create_context_async(&mut ctx.inner, fbconfig, rules, share)
@@ -257,10 +417,7 @@ where
             .create_context_attribs_arb_async(
                 glx::Fbconfig::const_from_xid(fbconfig.fbconfig_id as _),
                 self.screen,
-                match share {
-                    Some(share) => share.xid(),
-                    None => Context::default(),
-                },
+                share_xid,
                 ctx.dispatch().is_direct(),
                 attribs,
             )
@@ -268,6 +425,29 @@ where
         Arc::get_mut(&mut ctx.inner)
             .expect("Infallible Arc::get_mut()")
             .xid = xid;
+        // only join the group once we're done mutating the context
+        ctx.share_group().join(&ctx);
         Ok(ctx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indirect_screens_reject_es() {
+        for api in &[GlApi::Gles1, GlApi::Gles2, GlApi::Gles3] {
+            assert!(check_api_support(*api, false).is_err());
+            assert!(check_api_support(*api, true).is_ok());
+        }
+    }
+
+    #[test]
+    fn indirect_screens_accept_desktop_gl() {
+        for api in &[GlApi::OpenGl, GlApi::OpenGlCore] {
+            assert!(check_api_support(*api, false).is_ok());
+            assert!(check_api_support(*api, true).is_ok());
+        }
+    }
+}
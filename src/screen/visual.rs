@@ -0,0 +1,129 @@
+// MIT/Apache2 License
+
+use super::GlScreen;
+use crate::{
+    config::{GlConfig, GlConfigRule},
+    display::{DisplayLike, GlDisplay},
+};
+use breadx::{display::Display, Visualid, Visualtype};
+use std::os::raw::c_int;
+
+/// A GL config for one of a screen's X visuals, along with the visual itself and its depth. These are
+/// what's needed to create a window that can be rendered to with the config.
+#[derive(Debug, Clone)]
+pub struct GlVisual {
+    /// The GL config describing the visual.
+    pub config: GlConfig,
+    /// The X visual.
+    pub visual: Visualtype,
+    /// The depth of the visual.
+    pub depth: u8,
+}
+
+impl GlVisual {
+    /// The ID of the X visual.
+    #[inline]
+    pub fn visual_id(&self) -> Visualid {
+        self.visual.visual_id
+    }
+}
+
+impl<Dpy> GlScreen<Dpy> {
+    /// Get the visual configs matching a certain set of rules, sorted from best to worst match. This
+    /// uses the same matching and sorting rules as `choose_fbconfigs_sorted`.
+    #[inline]
+    fn choose_visual_configs(&self, rules: &[GlConfigRule]) -> Vec<&GlConfig> {
+        let template = GlConfig::chooser_template(rules);
+        let mut configs: Vec<&GlConfig> = self
+            .visuals
+            .iter()
+            .filter(|vis| vis.matches_template(&template))
+            .collect();
+        configs.sort_by(|a, b| a.cmp_for_template(b, &template));
+        configs
+    }
+
+    /// Find the config for a visual ID, preferring the visual configs over the fbconfigs.
+    #[inline]
+    fn find_visual_config(&self, visual_id: Visualid) -> Option<&GlConfig> {
+        let visual_id = visual_id as c_int;
+        self.visuals
+            .iter()
+            .chain(self.fbconfigs.iter())
+            .find(|cfg| cfg.visual_id == visual_id)
+    }
+
+    /// Pair a config with the X visual it describes, if that visual exists on this screen.
+    #[inline]
+    pub(crate) fn pair_with_visual<Conn>(
+        &self,
+        dpy: &Display<Conn>,
+        config: &GlConfig,
+    ) -> Option<GlVisual> {
+        dpy.setup()
+            .roots
+            .get(self.screen)?
+            .allowed_depths
+            .iter()
+            .find_map(|depth| {
+                depth
+                    .visuals
+                    .iter()
+                    .find(|visual| visual.visual_id as c_int == config.visual_id)
+                    .map(|visual| GlVisual {
+                        config: config.clone(),
+                        visual: visual.clone(),
+                        depth: depth.depth,
+                    })
+            })
+    }
+}
+
+impl<Dpy: DisplayLike> GlScreen<Dpy> {
+    /// Choose the visual that best matches a set of rules, like `glXChooseVisual`. Unlike
+    /// `glXChooseVisual`, attributes not mentioned in the rules use the `glXChooseFBConfig` defaults,
+    /// so `RenderType(RGBA_BIT)` is implied.
+    #[inline]
+    pub fn choose_visual(&self, dpy: &GlDisplay<Dpy>, rules: &[GlConfigRule]) -> Option<GlVisual> {
+        let configs = self.choose_visual_configs(rules);
+        let dpy = dpy.display();
+        configs
+            .into_iter()
+            .find_map(|config| self.pair_with_visual(&dpy, config))
+    }
+
+    /// Get the config, X visual and depth associated with a visual ID. This is usually used to find
+    /// the visual to create a window with, given the `visual_id` of a chosen fbconfig.
+    #[inline]
+    pub fn config_for_visual(&self, dpy: &GlDisplay<Dpy>, visual_id: Visualid) -> Option<GlVisual> {
+        let config = self.find_visual_config(visual_id)?;
+        self.pair_with_visual(&dpy.display(), config)
+    }
+
+    /// Choose the visual that best matches a set of rules, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn choose_visual_async(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        rules: &[GlConfigRule],
+    ) -> Option<GlVisual> {
+        let configs = self.choose_visual_configs(rules);
+        let dpy = dpy.display_async().await;
+        configs
+            .into_iter()
+            .find_map(|config| self.pair_with_visual(&dpy, config))
+    }
+
+    /// Get the config, X visual and depth associated with a visual ID, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn config_for_visual_async(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        visual_id: Visualid,
+    ) -> Option<GlVisual> {
+        let config = self.find_visual_config(visual_id)?;
+        self.pair_with_visual(&*dpy.display_async().await, config)
+    }
+}
@@ -0,0 +1,264 @@
+// MIT/Apache2 License
+
+use super::{GlScreen, GlVisual};
+use crate::{
+    config::GlConfig,
+    display::{DisplayLike, GlDisplay},
+    drawable::{self, GlDrawable, GlWindowHints},
+};
+use breadx::{
+    auto::xproto::{ChangePropertyRequest, Colormap, PropMode, ATOM_CARDINAL},
+    display::{Connection, Display},
+    Atom, ColormapAlloc, Pixmap, Rectangle, Window, WindowClass, WindowParameters,
+};
+
+#[cfg(feature = "async")]
+use breadx::display::AsyncConnection;
+
+impl<Dpy> GlScreen<Dpy> {
+    /// Find the X visual for an fbconfig that will be used to create a window.
+    #[inline]
+    fn window_visual<Conn>(
+        &self,
+        dpy: &Display<Conn>,
+        fbconfig: &GlConfig,
+    ) -> breadx::Result<GlVisual> {
+        self.check_config(fbconfig)?;
+        self.pair_with_visual(dpy, fbconfig)
+            .ok_or(breadx::BreadError::StaticMsg(
+                "Config does not have an X visual on this screen",
+            ))
+    }
+}
+
+impl<Dpy: DisplayLike> GlScreen<Dpy>
+where
+    Dpy::Connection: Connection,
+{
+    /// Create a window that can be rendered to with the given fbconfig. This creates a colormap for
+    /// the fbconfig's visual, creates the window with the right depth and visual, and then applies
+    /// the hints. The colormap is freed once the returned `GlDrawable` is dropped.
+    #[inline]
+    pub fn create_window(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        parent: Window,
+        geometry: Rectangle,
+        fbconfig: &GlConfig,
+        hints: &GlWindowHints,
+    ) -> breadx::Result<(Window, GlDrawable<Dpy>)> {
+        log::trace!("Creating window for fbconfig {:#x}", fbconfig.fbconfig_id);
+
+        let mut display = dpy.display();
+        let visual = self.window_visual(&display, fbconfig)?;
+        let colormap = display.create_colormap(parent, visual.visual_id(), ColormapAlloc::None)?;
+
+        let window = match create_window(&mut display, parent, geometry, &visual, colormap, hints) {
+            Ok(window) => window,
+            Err(e) => {
+                drawable::free_colormap(&mut display, colormap).ok();
+                return Err(e);
+            }
+        };
+
+        // the drawable locks the display when it's dropped, so make sure it's unlocked by then
+        drop(display);
+        let drawable = GlDrawable::new(dpy.clone(), window, colormap, visual);
+        Ok((window, drawable))
+    }
+}
+
+#[inline]
+fn window_parameters(colormap: Colormap, hints: &GlWindowHints) -> WindowParameters {
+    WindowParameters {
+        colormap: Some(colormap),
+        background_pixmap: Some(Pixmap::const_from_xid(0)),
+        border_pixel: Some(0),
+        event_mask: Some(hints.event_mask),
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn bypass_compositor_request(window: Window, property: Atom) -> ChangePropertyRequest {
+    ChangePropertyRequest {
+        mode: PropMode::Replace,
+        window,
+        property,
+        ty: ATOM_CARDINAL,
+        format: 32,
+        data_len: 1,
+        data: 1u32.to_ne_bytes().to_vec(),
+        ..Default::default()
+    }
+}
+
+/// Create the window and apply the hints to it, destroying it if any of the hints can't be applied.
+#[inline]
+fn create_window<Conn: Connection>(
+    dpy: &mut Display<Conn>,
+    parent: Window,
+    geometry: Rectangle,
+    visual: &GlVisual,
+    colormap: Colormap,
+    hints: &GlWindowHints,
+) -> breadx::Result<Window> {
+    let window = dpy.create_window(
+        parent,
+        WindowClass::InputOutput,
+        Some(visual.depth),
+        Some(visual.visual_id()),
+        geometry.x,
+        geometry.y,
+        geometry.width,
+        geometry.height,
+        0,
+        window_parameters(colormap, hints),
+    )?;
+
+    match apply_hints(dpy, window, hints) {
+        Ok(()) => Ok(window),
+        Err(e) => {
+            window.free(dpy).ok();
+            Err(e)
+        }
+    }
+}
+
+#[inline]
+fn apply_hints<Conn: Connection>(
+    dpy: &mut Display<Conn>,
+    window: Window,
+    hints: &GlWindowHints,
+) -> breadx::Result {
+    if let Some(ref title) = hints.title {
+        window.set_title(dpy, title)?;
+    }
+
+    if hints.delete_window_protocol {
+        let wdw = dpy.intern_atom_immediate("WM_DELETE_WINDOW".to_string(), false)?;
+        window.set_wm_protocols(dpy, &[wdw])?;
+    }
+
+    if hints.bypass_compositor {
+        let bypass = dpy.intern_atom_immediate("_NET_WM_BYPASS_COMPOSITOR".to_string(), false)?;
+        let tok = dpy.send_request(bypass_compositor_request(window, bypass))?;
+        dpy.resolve_request(tok)?;
+    }
+
+    if hints.map {
+        window.map(dpy)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+impl<Dpy: DisplayLike> GlScreen<Dpy>
+where
+    Dpy::Connection: AsyncConnection + Send,
+{
+    /// Create a window that can be rendered to with the given fbconfig, async redox.
+    #[inline]
+    pub async fn create_window_async(
+        &self,
+        dpy: &GlDisplay<Dpy>,
+        parent: Window,
+        geometry: Rectangle,
+        fbconfig: &GlConfig,
+        hints: &GlWindowHints,
+    ) -> breadx::Result<(Window, GlDrawable<Dpy>)> {
+        log::trace!("Creating window for fbconfig {:#x}", fbconfig.fbconfig_id);
+
+        let mut display = dpy.display_async().await;
+        let visual = self.window_visual(&display, fbconfig)?;
+        let colormap = display
+            .create_colormap_async(parent, visual.visual_id(), ColormapAlloc::None)
+            .await?;
+
+        let window =
+            create_window_async(&mut display, parent, geometry, &visual, colormap, hints).await;
+        let window = match window {
+            Ok(window) => window,
+            Err(e) => {
+                drawable::free_colormap_async(&mut display, colormap)
+                    .await
+                    .ok();
+                return Err(e);
+            }
+        };
+
+        drop(display);
+        let drawable = GlDrawable::new_async(dpy.clone(), window, colormap, visual);
+        Ok((window, drawable))
+    }
+}
+
+#[cfg(feature = "async")]
+#[inline]
+async fn create_window_async<Conn: AsyncConnection + Send>(
+    dpy: &mut Display<Conn>,
+    parent: Window,
+    geometry: Rectangle,
+    visual: &GlVisual,
+    colormap: Colormap,
+    hints: &GlWindowHints,
+) -> breadx::Result<Window> {
+    let window = dpy
+        .create_window_async(
+            parent,
+            WindowClass::InputOutput,
+            Some(visual.depth),
+            Some(visual.visual_id()),
+            geometry.x,
+            geometry.y,
+            geometry.width,
+            geometry.height,
+            0,
+            window_parameters(colormap, hints),
+        )
+        .await?;
+
+    match apply_hints_async(dpy, window, hints).await {
+        Ok(()) => Ok(window),
+        Err(e) => {
+            window.free_async(dpy).await.ok();
+            Err(e)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[inline]
+async fn apply_hints_async<Conn: AsyncConnection + Send>(
+    dpy: &mut Display<Conn>,
+    window: Window,
+    hints: &GlWindowHints,
+) -> breadx::Result {
+    if let Some(ref title) = hints.title {
+        window.set_title_async(dpy, title).await?;
+    }
+
+    if hints.delete_window_protocol {
+        let wdw = dpy
+            .intern_atom_immediate_async("WM_DELETE_WINDOW".to_string(), false)
+            .await?;
+        window.set_wm_protocols_async(dpy, &[wdw]).await?;
+    }
+
+    if hints.bypass_compositor {
+        let bypass = dpy
+            .intern_atom_immediate_async("_NET_WM_BYPASS_COMPOSITOR".to_string(), false)
+            .await?;
+        let tok = dpy
+            .send_request_async(bypass_compositor_request(window, bypass))
+            .await?;
+        dpy.resolve_request_async(tok).await?;
+    }
+
+    if hints.map {
+        window.map_async(dpy).await?;
+    }
+
+    Ok(())
+}
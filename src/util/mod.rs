@@ -6,6 +6,17 @@ use std::{
     ptr::NonNull,
 };
 
+#[cfg(test)]
+use breadx::{AuthInfo, Display};
+#[cfg(test)]
+use std::{
+    fs,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+};
+
 #[cfg(feature = "async")]
 use crate::offload;
 #[cfg(feature = "async")]
@@ -115,3 +126,92 @@ impl<T: ?Sized> DerefMut for ThreadSafe<T> {
         &mut self.0
     }
 }
+
+/// A directory in the system's temporary directory, deleted along with its contents on drop.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// Create a new, empty directory whose name starts with `prefix`.
+    #[inline]
+    pub(crate) fn new(prefix: &str) -> TempDir {
+        loop {
+            let path = env::temp_dir().join(format!(
+                "{}-{}-{:016x}",
+                prefix,
+                std::process::id(),
+                fastrand::u64(..)
+            ));
+            match fs::create_dir(&path) {
+                Ok(()) => return TempDir(path),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => panic!("Unable to create {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Connect to a fake X server with no screens, which answers every request with an empty reply. To
+/// the client, every extension it asks about is missing. Drop the display before joining the
+/// server's thread.
+#[cfg(test)]
+pub(crate) fn fake_server() -> (Display<UnixStream>, JoinHandle<()>) {
+    let (client, server) = UnixStream::pair().unwrap();
+    let server = thread::spawn(move || serve_empty_replies(server));
+    let dpy = Display::from_connection(client, Some(AuthInfo::default())).unwrap();
+    (dpy, server)
+}
+
+#[cfg(test)]
+fn serve_empty_replies(mut server: UnixStream) {
+    // the setup request, with no authorization
+    let mut request = [0; 12];
+    server.read_exact(&mut request).unwrap();
+
+    // a successful setup with no screens or formats
+    let mut setup = vec![1, 0];
+    setup.extend_from_slice(&11u16.to_ne_bytes());
+    setup.extend_from_slice(&0u16.to_ne_bytes());
+    setup.extend_from_slice(&8u16.to_ne_bytes());
+    setup.extend_from_slice(&0u32.to_ne_bytes()); // release number
+    setup.extend_from_slice(&0x0040_0000u32.to_ne_bytes()); // resource ID base
+    setup.extend_from_slice(&0x001F_FFFFu32.to_ne_bytes()); // resource ID mask
+    setup.extend_from_slice(&0u32.to_ne_bytes()); // motion buffer size
+    setup.extend_from_slice(&0u16.to_ne_bytes()); // vendor length
+    setup.extend_from_slice(&u16::MAX.to_ne_bytes()); // maximum request length
+    setup.extend_from_slice(&[0, 0, 0, 0, 32, 32, 8, 255, 0, 0, 0, 0]);
+    server.write_all(&setup).unwrap();
+
+    let mut sequence: u16 = 0;
+    let mut header = [0; 4];
+    while server.read_exact(&mut header).is_ok() {
+        let length = u16::from_ne_bytes([header[2], header[3]]) as usize * 4;
+        let mut body = vec![0; length.saturating_sub(4)];
+        if server.read_exact(&mut body).is_err() {
+            break;
+        }
+
+        sequence = sequence.wrapping_add(1);
+        let mut reply = [0; 32];
+        reply[0] = 1;
+        reply[2..4].copy_from_slice(&sequence.to_ne_bytes());
+        if server.write_all(&reply).is_err() {
+            break;
+        }
+    }
+}